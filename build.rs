@@ -1,6 +1,10 @@
 use rustc_version::{version_meta, Channel};
 
 fn main() {
+    // smoelius: Older versions of Cargo treat unrecognized `cargo:` keys as metadata, so these
+    // lines are harmless there.
+    println!("cargo:rustc-check-cfg=cfg(fuzzing)");
+    println!("cargo:rustc-check-cfg=cfg(nightly)");
     if version_meta().unwrap().channel == Channel::Nightly {
         println!("cargo:rustc-cfg=nightly");
     }
@@ -16,6 +16,8 @@ mod safe_path_lint;
 pub fn register_lints(_sess: &rustc_session::Session, lint_store: &mut rustc_lint::LintStore) {
     lint_store.register_lints(&[safe_path_lint::SAFE_JOIN_OPPORTUNITY]);
     lint_store.register_lints(&[safe_path_lint::SAFE_JOIN_MISAPPLICATION]);
+    lint_store.register_lints(&[safe_path_lint::SAFE_DIR_BYPASS]);
+    lint_store.register_lints(&[safe_path_lint::PARENT_CHAIN]);
     lint_store.register_late_pass(|| Box::new(safe_path_lint::SafePathLint));
 }
 
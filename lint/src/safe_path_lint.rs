@@ -9,7 +9,7 @@ use if_chain::if_chain;
 use rustc_hir::{
     def::Res,
     def_id::{DefId, LocalDefId},
-    Expr, ExprKind, HirId, ItemKind,
+    Expr, ExprKind, HirId, ItemKind, Node,
 };
 use rustc_lint::{LateContext, LateLintPass};
 use rustc_middle::ty::{
@@ -75,8 +75,8 @@ declare_lint! {
 }
 
 declare_lint! {
-    /// **What it does:** Checks for calls to `SafePath::safe_join` or `SafePath::relaxed_safe_join`
-    /// that return an error when the receiver is not `/`.
+    /// **What it does:** Checks for calls to `SafePath::safe_join`, `SafePath::relaxed_safe_join`,
+    /// or `SafePath::strict_safe_join` that return an error when the receiver is not `/`.
     ///
     /// **Why is this bad?** Such behavior is likely not what the programmer intended. There are
     /// simpler ways to check whether the receiver is `/`, if this is what the programmer intended.
@@ -97,23 +97,87 @@ declare_lint! {
     /// ```
     pub SAFE_JOIN_MISAPPLICATION,
     Warn,
-    "calls to `safe_join` or `relaxed_safe_join` that are likely erroneous"
+    "calls to `safe_join`, `relaxed_safe_join`, or `strict_safe_join` that are likely erroneous"
 }
 
-declare_lint_pass!(SafePathLint => [SAFE_JOIN_OPPORTUNITY, SAFE_PARENT_OPPORTUNITY, SAFE_JOIN_MISAPPLICATION]);
+declare_lint! {
+    /// **What it does:** Checks for `std::fs` calls in modules that use `safe_path::fs::SafeDir`.
+    ///
+    /// **Why is this bad?** Filesystem accesses that do not go through the `SafeDir` bypass its
+    /// checks.
+    ///
+    /// **Known problems:** The lint is allow-by-default because it flags all `std::fs` calls in
+    /// such modules, including ones that legitimately access files outside of the base directory.
+    ///
+    /// **Example:**
+    ///
+    /// ```no_run
+    /// # use safe_path::fs::SafeDir;
+    /// # let filename = String::new();
+    /// let uploads = SafeDir::new("uploads");
+    /// let contents = std::fs::read(uploads.root().join(filename));
+    /// ```
+    /// Use instead:
+    /// ```no_run
+    /// # use safe_path::fs::SafeDir;
+    /// # let filename = String::new();
+    /// let uploads = SafeDir::new("uploads");
+    /// let contents = uploads.join(filename).and_then(std::fs::read);
+    /// ```
+    pub SAFE_DIR_BYPASS,
+    Allow,
+    "`std::fs` calls in modules that use `SafeDir`"
+}
+
+declare_lint! {
+    /// **What it does:** Checks for chains of two or more calls to `Path::parent` or
+    /// `Utf8Path::parent`, and for calls to `PathBuf::pop` or `Utf8PathBuf::pop` inside loops.
+    ///
+    /// **Why is this bad?** Repeatedly ascending is how cleanup code accidentally climbs out of its
+    /// directory.
+    ///
+    /// **Known problems:** The lint does not consider where the path came from. A chain of `n`
+    /// calls produces `n - 1` warnings.
+    ///
+    /// **Example:**
+    ///
+    /// ```
+    /// # let dir = std::path::PathBuf::new();
+    /// let grandparent = dir.parent().unwrap().parent();
+    /// ```
+    /// Use instead:
+    /// ```
+    /// # use safe_path::SafePath;
+    /// # fn main() -> std::io::Result<()> {
+    /// # let dir = std::path::PathBuf::from("x/y/z");
+    /// let grandparent = dir.safe_parent()?.unwrap().safe_parent()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub PARENT_CHAIN,
+    Warn,
+    "chains of `parent` calls and calls to `pop` in loops"
+}
+
+declare_lint_pass!(SafePathLint => [SAFE_JOIN_OPPORTUNITY, SAFE_PARENT_OPPORTUNITY, SAFE_JOIN_MISAPPLICATION, SAFE_DIR_BYPASS, PARENT_CHAIN]);
 
 const UTF8PATH_JOIN: [&str; 3] = ["camino", "Utf8Path", "join"];
 const UTF8PATH_PARENT: [&str; 3] = ["camino", "Utf8Path", "parent"];
+const UTF8PATHBUF_POP: [&str; 3] = ["camino", "Utf8PathBuf", "pop"];
+const SAFE_DIR: [&str; 3] = ["safe_path", "fs", "SafeDir"];
 const SAFE_JOIN_TRAIT: [&str; 2] = ["safe_path", "SafePath"];
 const RELAXED_SAFE_JOIN: [&str; 3] = ["safe_path", "SafePath", "relaxed_safe_join"];
 const SAFE_JOIN: [&str; 3] = ["safe_path", "SafePath", "safe_join"];
+const STRICT_SAFE_JOIN: [&str; 3] = ["safe_path", "SafePath", "strict_safe_join"];
 const INTO_TRAIT: [&str; 3] = ["std", "convert", "Into"];
 const IO_ERROR: [&str; 4] = ["std", "io", "error", "Error"];
 const PATH_JOIN: [&str; 4] = ["std", "path", "Path", "join"];
 const PATH_PARENT: [&str; 4] = ["std", "path", "Path", "parent"];
+const PATHBUF_POP: [&str; 4] = ["std", "path", "PathBuf", "pop"];
 
 impl<'tcx> LateLintPass<'tcx> for SafePathLint {
     fn check_expr(&mut self, cx: &LateContext<'tcx>, expr: &Expr<'_>) {
+        check_safe_dir_bypass(cx, expr);
         if_chain! {
             if let ExprKind::MethodCall(method_name, method_span, args, _) = expr.kind;
             if let Some(method_def_id) = cx.typeck_results().type_dependent_def_id(expr.hir_id);
@@ -122,6 +186,7 @@ impl<'tcx> LateLintPass<'tcx> for SafePathLint {
                 check_safe_join_opportunity(cx, expr, method_name.ident.name, method_span, args, method_def_id, method_arg_span);
                 check_safe_parent_opportunity(cx, expr, method_name.ident.name, method_span, args, method_def_id, method_arg_span);
                 check_safe_join_misapplication(cx, expr, method_name.ident.name, method_span, args, method_def_id, method_arg_span);
+                check_parent_chain(cx, expr, method_name.ident.name, method_span, args, method_def_id, method_arg_span);
             }
         }
     }
@@ -152,7 +217,7 @@ fn check_safe_join_opportunity(
             } else {
                 "module"
             };
-            let import_msg = if module_uses_safe_path(cx, module_local_def_id) {
+            let import_msg = if module_uses(cx, module_local_def_id, &SAFE_JOIN_TRAIT) {
                 String::new()
             } else {
                 format!("add `use safe_path::SafePath;` to the {} and ", file_or_module)
@@ -211,7 +276,7 @@ fn check_safe_parent_opportunity(
             } else {
                 "module"
             };
-            let import_msg = if module_uses_safe_path(cx, module_local_def_id) {
+            let import_msg = if module_uses(cx, module_local_def_id, &SAFE_JOIN_TRAIT) {
                 String::new()
             } else {
                 format!("add `use safe_path::SafePath;` to the {} and ", file_or_module)
@@ -257,13 +322,14 @@ fn check_safe_join_misapplication(
     method_arg_span: Span,
 ) {
     let relaxed = match_def_path(cx, method_def_id, &RELAXED_SAFE_JOIN);
+    let strict = match_def_path(cx, method_def_id, &STRICT_SAFE_JOIN);
     if_chain! {
-        if relaxed || match_def_path(cx, method_def_id, &SAFE_JOIN);
+        if relaxed || strict || match_def_path(cx, method_def_id, &SAFE_JOIN);
         if let [_, arg] = args;
         if let Some(Constant::Str(path)) = constant_context(cx, cx.typeck_results()).expr(arg);
         if Path::new(".").check_join_safety(path.as_ref(), relaxed).is_err();
         then {
-            let (always_msg, root_msg) = if Path::new("/").check_join_safety(path.as_ref(), relaxed).is_err() {
+            let (always_msg, root_msg) = if strict || Path::new("/").check_join_safety(path.as_ref(), relaxed).is_err() {
                 (" always", "")
             } else {
                 ("", " if the receiver is not `/`")
@@ -280,12 +346,128 @@ fn check_safe_join_misapplication(
     }
 }
 
-fn module_uses_safe_path(cx: &LateContext<'_>, local_def_id: LocalDefId) -> bool {
+#[allow(clippy::too_many_arguments)]
+fn check_parent_chain(
+    cx: &LateContext<'_>,
+    expr: &Expr<'_>,
+    _method_name: Symbol,
+    _method_span: Span,
+    args: &[Expr<'_>],
+    method_def_id: DefId,
+    _method_arg_span: Span,
+) {
+    if_chain! {
+        if is_parent(cx, method_def_id);
+        if let [receiver] = args;
+        let receiver = peel_unwraps(cx, receiver);
+        if let ExprKind::MethodCall(..) = receiver.kind;
+        if let Some(receiver_def_id) = cx.typeck_results().type_dependent_def_id(receiver.hir_id);
+        if is_parent(cx, receiver_def_id);
+        then {
+            span_lint_and_help(
+                cx,
+                PARENT_CHAIN,
+                expr.span,
+                "chain of `parent` calls",
+                None,
+                "use `safe_parent()?` at each step so that the chain cannot ascend above its \
+                starting directory",
+            );
+        }
+    }
+    if (match_def_path(cx, method_def_id, &PATHBUF_POP)
+        || match_def_path(cx, method_def_id, &UTF8PATHBUF_POP))
+        && in_loop(cx, expr.hir_id)
+    {
+        span_lint_and_help(
+            cx,
+            PARENT_CHAIN,
+            expr.span,
+            "call to `pop` in a loop",
+            None,
+            "check each step with `safe_parent()?`",
+        );
+    }
+}
+
+fn is_parent(cx: &LateContext<'_>, def_id: DefId) -> bool {
+    match_def_path(cx, def_id, &PATH_PARENT) || match_def_path(cx, def_id, &UTF8PATH_PARENT)
+}
+
+fn peel_unwraps<'a, 'tcx>(cx: &LateContext<'tcx>, mut expr: &'a Expr<'tcx>) -> &'a Expr<'tcx> {
+    while let ExprKind::MethodCall(method_name, _, [receiver], _) = expr.kind {
+        let name = method_name.ident.name.as_str();
+        if (&*name != "unwrap" && &*name != "expect")
+            || cx.typeck_results().type_dependent_def_id(expr.hir_id).is_none()
+        {
+            break;
+        }
+        expr = receiver;
+    }
+    expr
+}
+
+fn in_loop(cx: &LateContext<'_>, hir_id: HirId) -> bool {
+    for (_, node) in cx.tcx.hir().parent_iter(hir_id) {
+        match node {
+            Node::Expr(Expr {
+                kind: ExprKind::Loop(..),
+                ..
+            }) => return true,
+            Node::Expr(Expr {
+                kind: ExprKind::Closure(..),
+                ..
+            })
+            | Node::Item(_)
+            | Node::ImplItem(_)
+            | Node::TraitItem(_) => return false,
+            _ => {}
+        }
+    }
+    false
+}
+
+fn check_safe_dir_bypass(cx: &LateContext<'_>, expr: &Expr<'_>) {
+    if_chain! {
+        if let Some(callee_def_id) = callee_def_id(cx, expr);
+        let def_path = cx.get_def_path(callee_def_id);
+        if let [krate, module, ..] = def_path.as_slice();
+        if &*krate.as_str() == "std" && &*module.as_str() == "fs";
+        if module_uses(cx, cx.tcx.parent_module(expr.hir_id), &SAFE_DIR);
+        then {
+            span_lint_and_help(
+                cx,
+                SAFE_DIR_BYPASS,
+                expr.span,
+                "filesystem access in a module that uses `SafeDir`",
+                None,
+                "access the filesystem through the `SafeDir`",
+            );
+        }
+    }
+}
+
+fn callee_def_id(cx: &LateContext<'_>, expr: &Expr<'_>) -> Option<DefId> {
+    match expr.kind {
+        ExprKind::Call(callee, _) => {
+            if let ExprKind::Path(ref qpath) = callee.kind {
+                if let Res::Def(_, def_id) = cx.qpath_res(qpath, callee.hir_id) {
+                    return Some(def_id);
+                }
+            }
+            None
+        }
+        ExprKind::MethodCall(..) => cx.typeck_results().type_dependent_def_id(expr.hir_id),
+        _ => None,
+    }
+}
+
+fn module_uses(cx: &LateContext<'_>, local_def_id: LocalDefId, path: &[&str]) -> bool {
     let module_items = cx.tcx.hir_module_items(local_def_id);
     module_items.items.iter().any(|item_id| {
         let item = cx.tcx.hir().item(*item_id);
-        if let ItemKind::Use(path, _) = item.kind {
-            match_path(path, &SAFE_JOIN_TRAIT)
+        if let ItemKind::Use(use_path, _) = item.kind {
+            match_path(use_path, path)
         } else {
             false
         }
@@ -0,0 +1,17 @@
+#![allow(safe_join_opportunity)]
+
+use std::path::{Path, PathBuf};
+
+fn main() {
+    let dir = Path::new("x");
+
+    let _ = dir.parent().unwrap().parent();
+
+    let _ = dir.parent().and_then(Path::parent);
+
+    let mut path_buf = PathBuf::from("x/y");
+    while path_buf.pop() {}
+
+    let mut path_buf = PathBuf::from("x/y");
+    path_buf.pop();
+}
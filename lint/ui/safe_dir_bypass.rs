@@ -0,0 +1,14 @@
+#![deny(safe_dir_bypass)]
+
+use safe_path::fs::SafeDir;
+use std::fs::{read_to_string, File};
+
+fn main() {
+    let uploads = SafeDir::new("uploads");
+
+    let _ = read_to_string(uploads.root().join("x"));
+
+    let _ = File::open("/etc/passwd");
+
+    let _ = uploads.join("x").and_then(read_to_string);
+}
@@ -35,6 +35,8 @@ fn main() {
     let _ = dir.relaxed_safe_join(PARENT_DIR);
     let _ = dir.relaxed_safe_join(NORMAL);
 
+    let _ = dir.strict_safe_join("/");
+
     let _ = foo().unwrap();
     let _ = bar().unwrap();
     let _ = baz().unwrap();
@@ -14,7 +14,7 @@ cargo readme > README.md
 
 # smoelius: Fix intra-doc links. This is a modification of:
 # https://github.com/livioribeiro/cargo-readme/issues/70#issuecomment-907867904
-sed -i 's/\[\(`SafePath::[^`]*`\)\]/\1/g' README.md
+sed -i 's/\[\(`\(SafePath::[^`]*\|Policy\|PathOps\|backend\)`\)\]/\1/g' README.md
 
 # smoelius: Fix reference-style links.
 sed -i 's,^\(\[components\]\): .*$,\1: https://doc.rust-lang.org/std/path/enum.Component.html,' README.md
@@ -0,0 +1,97 @@
+//! Generation of adversarial paths for fuzzing and property testing (requires the `arbitrary`,
+//! `proptest`, or `quickcheck` feature)
+//!
+//! An [`ArbitraryPath`] is a sequence of components drawn from [`COMPONENTS`], which includes
+//! `..`, prefixes, separators, and names that Windows treats specially. Such paths exercise the
+//! cases a path check gets wrong far more often than uniformly random strings do:
+//! ```
+//! # use safe_path::{adversarial::ArbitraryPath, SafePath};
+//! # use std::path::Path;
+//! let ArbitraryPath(path) = ArbitraryPath::from_indices(vec![0, 0, 1]);
+//! assert_eq!(path, Path::new("../../."));
+//! assert!(Path::new("www").safe_join(&path).is_err());
+//! ```
+//!
+//! With the `arbitrary` and `quickcheck` features, `ArbitraryPath` implements
+//! `arbitrary::Arbitrary` and `quickcheck::Arbitrary`, respectively. With the `proptest` feature,
+//! [`crate::strategies`] provides equivalent strategies.
+
+use std::path::PathBuf;
+
+/// The components from which an [`ArbitraryPath`] is built. An empty component produces a
+/// repeated separator, or a root directory at the start of a path.
+pub const COMPONENTS: &[&str] = &[
+    "..",
+    ".",
+    "",
+    "x",
+    "y",
+    ".x",
+    "..x",
+    "x..",
+    "...",
+    "C:",
+    "c:x",
+    r"\",
+    r"..\..",
+    r"\\server\share",
+    r"\\?\C:",
+    "CON",
+    "nul.txt",
+    "x:stream",
+    "x.",
+    "x ",
+    "PROGRA~1",
+    "$HOME",
+    "%APPDATA%",
+    "%2e%2e",
+    "\u{ff0e}\u{ff0e}",
+    "x\u{202e}y",
+    "x\u{200b}",
+    "x\ny",
+];
+
+/// The maximum number of components in an [`ArbitraryPath`]
+pub const MAX_COMPONENTS: usize = 8;
+
+/// A path built from up to [`MAX_COMPONENTS`] components drawn from [`COMPONENTS`], joined with
+/// `/`
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ArbitraryPath(pub PathBuf);
+
+impl ArbitraryPath {
+    /// Builds an `ArbitraryPath` from indices into [`COMPONENTS`]. Indices are reduced modulo the
+    /// number of components, and indices beyond the first [`MAX_COMPONENTS`] are ignored.
+    #[must_use]
+    pub fn from_indices<I: IntoIterator<Item = usize>>(indices: I) -> Self {
+        let components = indices
+            .into_iter()
+            .take(MAX_COMPONENTS)
+            .map(|index| COMPONENTS[index % COMPONENTS.len()])
+            .collect::<Vec<_>>();
+        Self(components.join("/").into())
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for ArbitraryPath {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let len = u.int_in_range(0..=MAX_COMPONENTS)?;
+        let mut indices = Vec::with_capacity(len);
+        for _ in 0..len {
+            indices.push(u.choose_index(COMPONENTS.len())?);
+        }
+        Ok(Self::from_indices(indices))
+    }
+}
+
+#[cfg(feature = "quickcheck")]
+impl quickcheck::Arbitrary for ArbitraryPath {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        let len = usize::arbitrary(g) % (MAX_COMPONENTS + 1);
+        let indices = (0..len)
+            .map(|_| usize::arbitrary(g) % COMPONENTS.len())
+            .collect::<Vec<_>>();
+        Self::from_indices(indices)
+    }
+}
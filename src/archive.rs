@@ -0,0 +1,230 @@
+//! Extraction of untrusted archives into a base directory
+//!
+//! Archive entries name files with arbitrary paths, so an entry named `../../.bashrc`, or a
+//! symbolic link entry followed by an entry that writes through it, can write outside of the
+//! directory into which the archive is extracted (a "Zip Slip"). [`entry_path`] checks an entry
+//! name from any archive format. With the `tar` and `zip` features, [`safe_unpack`] and
+//! [`safe_extract_all`] (respectively) check every entry before writing anything for it, and stop
+//! at the first entry that fails its check.
+
+#[cfg(any(feature = "tar", feature = "zip"))]
+use crate::fs::{safe_join_fs, SafeDir};
+use crate::{Error, SafePath, Violation};
+#[cfg(any(feature = "tar", feature = "zip"))]
+use std::{fs, io::Read};
+use std::{
+    io::Result,
+    path::{Path, PathBuf},
+};
+
+/// Checks the archive entry name `raw_name` and returns the path under `base` to which it refers,
+/// i.e., `base.safe_join(..)` of the name's components. The name is interpreted as archive formats
+/// store names, regardless of the host platform:
+///
+/// * A name that is not valid UTF-8 is decoded as Latin-1 (ISO 8859-1).
+/// * Both `/` and `\` are separators, since some archivers write `\`.
+/// * Empty and `.` components, e.g., in a leading `./` or a trailing `/`, are ignored.
+///
+/// Names that are absolute, that begin with a drive prefix (e.g., `C:`), or that contain `..` are
+/// rejected, even if they would refer to a file inside of `base`.
+/// ```
+/// # use safe_path::archive::entry_path;
+/// # use std::path::Path;
+/// assert_eq!(entry_path("out", "./a\\b/").unwrap(), Path::new("out/a/b"));
+/// assert_eq!(entry_path("out", b"caf\xe9").unwrap(), Path::new("out/caf\u{e9}"));
+/// assert!(entry_path("out", "a/../b").is_err());
+/// assert!(entry_path("out", "/etc/passwd").is_err());
+/// assert!(entry_path("out", "C:x").is_err());
+/// ```
+/// # Errors
+/// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if the check fails. The
+/// error payload is an [`Error`] whose contents are unstable and subject to change.
+pub fn entry_path<P: AsRef<Path>, N: ?Sized + AsRef<[u8]>>(
+    base: P,
+    raw_name: &N,
+) -> Result<PathBuf> {
+    base.as_ref()
+        .safe_join(relative_entry_path(raw_name.as_ref())?)
+}
+
+/// Unpacks `archive` into `base` (requires the `tar` feature). Each entry's name is checked with
+/// [`entry_path`], and the resulting path is checked with [`safe_join_fs`], so that no existing
+/// symbolic link along it leads outside of `base`. In addition:
+///
+/// * A symbolic link's target is checked with [`SafeDir::symlink_fs`], i.e., relative to the
+///   directory containing the link.
+/// * A hard link's target is checked with [`safe_join_fs`], i.e., relative to `base`.
+/// * Entries other than regular files, directories, and links (e.g., device files and FIFOs) are
+///   skipped.
+///
+/// Entries are unpacked in order, so an archive rejected partway through may have been partially
+/// unpacked. The checks cannot prevent races with another process modifying `base` concurrently.
+/// ```
+/// # use safe_path::archive::safe_unpack;
+/// # let tempdir = tempfile::tempdir().unwrap();
+/// let mut builder = tar::Builder::new(Vec::new());
+/// let mut header = tar::Header::new_gnu();
+/// header.set_size(0);
+/// // smoelius: `Builder::append_path` would reject this name.
+/// header.as_gnu_mut().unwrap().name[..13].copy_from_slice(b"../etc/passwd");
+/// header.set_cksum();
+/// builder.append(&header, std::io::empty()).unwrap();
+/// let data = builder.into_inner().unwrap();
+/// let mut archive = tar::Archive::new(data.as_slice());
+/// assert!(safe_unpack(&mut archive, tempdir.path()).is_err());
+/// ```
+/// # Errors
+/// Returns an error if the archive cannot be read, if a check fails, or if an entry cannot be
+/// unpacked. In the last case, the error payload is an [`Error`] whose source is the operating
+/// system's error.
+#[cfg(feature = "tar")]
+pub fn safe_unpack<R: Read, P: AsRef<Path>>(archive: &mut tar::Archive<R>, base: P) -> Result<()> {
+    let base = base.as_ref();
+    let dir = SafeDir::new(base);
+    let entries = archive
+        .entries()
+        .map_err(|error| Error::wrap_os_error("read failed", base, error))?;
+    for entry in entries {
+        let mut entry = entry.map_err(|error| Error::wrap_os_error("read failed", base, error))?;
+        let entry_type = entry.header().entry_type();
+        let path = relative_entry_path(&entry.path_bytes())?;
+        let link_name = || -> Result<_> {
+            match entry.link_name() {
+                Ok(Some(link_name)) => Ok(link_name.into_owned()),
+                Ok(None) => Err(Error::new("missing link name").with_path(&path).into()),
+                Err(error) => Err(Error::wrap_os_error("read failed", &path, error)),
+            }
+        };
+        if entry_type.is_symlink() {
+            let target = link_name()?;
+            create_parent(base, &path)?;
+            dir.symlink_fs(target, &path)?;
+            continue;
+        }
+        if entry_type.is_hard_link() {
+            let target = safe_join_fs(base, link_name()?)?;
+            let dst = create_parent(base, &path)?;
+            fs::hard_link(&target, &dst)
+                .map_err(|error| Error::wrap_os_error("hard_link failed", &dst, error))?;
+            continue;
+        }
+        if !(entry_type.is_file() || entry_type.is_contiguous() || entry_type.is_dir()) {
+            continue;
+        }
+        let dst = create_parent(base, &path)?;
+        entry
+            .unpack(&dst)
+            .map_err(|error| Error::wrap_os_error("unpack failed", &dst, error))?;
+    }
+    Ok(())
+}
+
+/// Checks `path` with [`safe_join_fs`], creates its parent directory, and returns the joined path.
+#[cfg(any(feature = "tar", feature = "zip"))]
+fn create_parent(base: &Path, path: &Path) -> Result<PathBuf> {
+    let dst = safe_join_fs(base, path)?;
+    if let Some(parent) = dst.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|error| Error::wrap_os_error("create_dir_all failed", parent, error))?;
+    }
+    Ok(dst)
+}
+
+/// Returns the path at which [`safe_extract_all`] would extract the zip entry named `name`, i.e.,
+/// `entry_path(base, name)` (requires the `zip` feature).
+/// # Errors
+/// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if the check fails. The
+/// error payload is an [`Error`] whose contents are unstable and subject to change.
+#[cfg(feature = "zip")]
+pub fn safe_entry_path<P: AsRef<Path>>(base: P, name: &str) -> Result<PathBuf> {
+    entry_path(base, name)
+}
+
+/// Extracts `zip` into `base` (requires the `zip` feature). Each entry's name is checked with
+/// [`entry_path`], and the resulting path is checked with [`safe_join_fs`], so that no
+/// existing symbolic link along it leads outside of `base`. A symbolic link's target is checked
+/// with [`SafeDir::symlink_fs`], i.e., relative to the directory containing the link. Permissions
+/// and modification times are not restored.
+///
+/// Entries are extracted in order, so an archive rejected partway through may have been
+/// partially extracted. The checks cannot prevent races with another process modifying `base`
+/// concurrently.
+/// # Errors
+/// Returns an error if the archive cannot be read, if a check fails, or if an entry cannot be
+/// extracted. In the last case, the error payload is an [`Error`] whose source is the operating
+/// system's error.
+#[cfg(feature = "zip")]
+pub fn safe_extract_all<R: Read + std::io::Seek, P: AsRef<Path>>(
+    zip: &mut zip::ZipArchive<R>,
+    base: P,
+) -> Result<()> {
+    let base = base.as_ref();
+    let dir = SafeDir::new(base);
+    for index in 0..zip.len() {
+        let mut file = zip
+            .by_index(index)
+            .map_err(|error| Error::wrap_os_error("read failed", base, error.into()))?;
+        let relative = relative_entry_path(file.name().as_bytes())?;
+        if file.is_symlink() {
+            let mut target = String::new();
+            file.read_to_string(&mut target)
+                .map_err(|error| Error::wrap_os_error("read failed", &relative, error))?;
+            create_parent(base, &relative)?;
+            dir.symlink_fs(target, &relative)?;
+            continue;
+        }
+        if file.is_dir() {
+            let dst = safe_join_fs(base, &relative)?;
+            fs::create_dir_all(&dst)
+                .map_err(|error| Error::wrap_os_error("create_dir_all failed", &dst, error))?;
+            continue;
+        }
+        let dst = create_parent(base, &relative)?;
+        let mut out = fs::File::create(&dst)
+            .map_err(|error| Error::wrap_os_error("create failed", &dst, error))?;
+        std::io::copy(&mut file, &mut out)
+            .map_err(|error| Error::wrap_os_error("write failed", &dst, error))?;
+    }
+    Ok(())
+}
+
+/// Converts the archive entry name `raw_name` to a relative path. See [`entry_path`].
+fn relative_entry_path(raw_name: &[u8]) -> Result<PathBuf> {
+    let name = match std::str::from_utf8(raw_name) {
+        Ok(name) => name.into(),
+        Err(_) => raw_name
+            .iter()
+            .map(|&byte| char::from(byte))
+            .collect::<String>(),
+    };
+    let err = |violation| {
+        Error::new("invalid entry name")
+            .with_violation(violation)
+            .with_path(&name)
+            .into()
+    };
+    let is_separator = |c| c == '/' || c == '\\';
+    if name.starts_with(is_separator) {
+        return Err(err(Violation::Absolute { index: 0 }));
+    }
+    if let [letter, b':', rest @ ..] = name.as_bytes() {
+        if letter.is_ascii_alphabetic() {
+            return Err(err(match rest.first() {
+                Some(b'/' | b'\\') => Violation::Absolute { index: 0 },
+                _ => Violation::DriveRelative { index: 0 },
+            }));
+        }
+    }
+    let mut path = PathBuf::new();
+    for (index, component) in name
+        .split(is_separator)
+        .filter(|component| !component.is_empty() && *component != ".")
+        .enumerate()
+    {
+        if component == ".." {
+            return Err(err(Violation::ParentDir { index }));
+        }
+        path.push(component);
+    }
+    Ok(path)
+}
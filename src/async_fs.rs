@@ -0,0 +1,367 @@
+//! Asynchronous filesystem operations confined to a base directory (requires the `tokio` or
+//! `async-std` feature)
+//!
+//! An [`AsyncSafeDir`] wraps a [`SafeDir`] and performs its operations with a [`Backend`], e.g.,
+//! `Tokio` or `AsyncStd`. Paths are checked exactly as the [`SafeDir`] checks them, and
+//! dry-run mode is respected:
+//! ```
+//! # #[cfg(feature = "tokio")]
+//! # {
+//! # use safe_path::{async_fs::{AsyncSafeDir, Tokio}, fs::SafeDir};
+//! # let tempdir = tempfile::tempdir().unwrap();
+//! # let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+//! # runtime.block_on(async {
+//! let dir = AsyncSafeDir::<Tokio>::new(SafeDir::new(tempdir.path()));
+//! dir.write("index.html", "<html></html>").await.unwrap();
+//! assert_eq!(dir.read("index.html").await.unwrap(), b"<html></html>");
+//! assert!(dir.read("../index.html").await.is_err());
+//! # });
+//! # }
+//! ```
+//!
+//! Checks are performed on the calling task. They consult the filesystem only if
+//! [`SafeDir::same_device`] is enabled. To use another runtime, implement [`Backend`].
+
+use crate::{
+    fs::{crosses_devices, Operation, SafeDir},
+    Error,
+};
+use std::{
+    fmt::{Debug, Formatter},
+    future::Future,
+    io::Result,
+    marker::PhantomData,
+    path::Path,
+    pin::Pin,
+};
+
+/// A boxed future returned by a [`Backend`]
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
+
+/// The filesystem operations an [`AsyncSafeDir`] performs, implemented for an async runtime
+///
+/// Each function corresponds to the like-named function in [`std::fs`], and should return the
+/// operating system's error unchanged.
+pub trait Backend {
+    /// The runtime's file type
+    type File;
+    /// The runtime's directory-iterator type
+    type ReadDir;
+    /// Opens `path` for reading.
+    fn open(path: &Path) -> BoxFuture<'_, Self::File>;
+    /// Opens `path` for writing, creating or truncating it.
+    fn create(path: &Path) -> BoxFuture<'_, Self::File>;
+    /// Reads the contents of `path`.
+    fn read(path: &Path) -> BoxFuture<'_, Vec<u8>>;
+    /// Replaces the contents of `path` with `contents`.
+    fn write<'a>(path: &'a Path, contents: &'a [u8]) -> BoxFuture<'a, ()>;
+    /// Returns an iterator over the entries of the directory `path`.
+    fn read_dir(path: &Path) -> BoxFuture<'_, Self::ReadDir>;
+    /// Creates the directory `path` and any missing ancestors.
+    fn create_dir_all(path: &Path) -> BoxFuture<'_, ()>;
+    /// Removes the file `path`.
+    fn remove_file(path: &Path) -> BoxFuture<'_, ()>;
+    /// Renames `from` to `to`.
+    fn rename<'a>(from: &'a Path, to: &'a Path) -> BoxFuture<'a, ()>;
+    /// Copies `from` to `to`, and returns the number of bytes copied.
+    fn copy<'a>(from: &'a Path, to: &'a Path) -> BoxFuture<'a, u64>;
+    /// Opens `path` for writing and syncs its contents and metadata to disk.
+    fn sync(path: &Path) -> BoxFuture<'_, ()>;
+}
+
+/// A [`Backend`] that uses [`tokio::fs`] (requires the `tokio` feature)
+#[cfg(feature = "tokio")]
+#[derive(Clone, Copy, Debug)]
+pub struct Tokio;
+
+#[cfg(feature = "tokio")]
+impl Backend for Tokio {
+    type File = tokio::fs::File;
+    type ReadDir = tokio::fs::ReadDir;
+    fn open(path: &Path) -> BoxFuture<'_, Self::File> {
+        Box::pin(tokio::fs::File::open(path))
+    }
+    fn create(path: &Path) -> BoxFuture<'_, Self::File> {
+        Box::pin(tokio::fs::File::create(path))
+    }
+    fn read(path: &Path) -> BoxFuture<'_, Vec<u8>> {
+        Box::pin(tokio::fs::read(path))
+    }
+    fn write<'a>(path: &'a Path, contents: &'a [u8]) -> BoxFuture<'a, ()> {
+        Box::pin(tokio::fs::write(path, contents))
+    }
+    fn read_dir(path: &Path) -> BoxFuture<'_, Self::ReadDir> {
+        Box::pin(tokio::fs::read_dir(path))
+    }
+    fn create_dir_all(path: &Path) -> BoxFuture<'_, ()> {
+        Box::pin(tokio::fs::create_dir_all(path))
+    }
+    fn remove_file(path: &Path) -> BoxFuture<'_, ()> {
+        Box::pin(tokio::fs::remove_file(path))
+    }
+    fn rename<'a>(from: &'a Path, to: &'a Path) -> BoxFuture<'a, ()> {
+        Box::pin(tokio::fs::rename(from, to))
+    }
+    fn copy<'a>(from: &'a Path, to: &'a Path) -> BoxFuture<'a, u64> {
+        Box::pin(tokio::fs::copy(from, to))
+    }
+    fn sync(path: &Path) -> BoxFuture<'_, ()> {
+        Box::pin(async move {
+            // smoelius: On Windows, `sync_all` requires write access.
+            let file = tokio::fs::OpenOptions::new().write(true).open(path).await?;
+            file.sync_all().await
+        })
+    }
+}
+
+/// A [`Backend`] that uses [`async_std::fs`] (requires the `async-std` feature)
+#[cfg(feature = "async-std")]
+#[derive(Clone, Copy, Debug)]
+pub struct AsyncStd;
+
+#[cfg(feature = "async-std")]
+impl Backend for AsyncStd {
+    type File = async_std::fs::File;
+    type ReadDir = async_std::fs::ReadDir;
+    fn open(path: &Path) -> BoxFuture<'_, Self::File> {
+        Box::pin(async_std::fs::File::open(path))
+    }
+    fn create(path: &Path) -> BoxFuture<'_, Self::File> {
+        Box::pin(async_std::fs::File::create(path))
+    }
+    fn read(path: &Path) -> BoxFuture<'_, Vec<u8>> {
+        Box::pin(async_std::fs::read(path))
+    }
+    fn write<'a>(path: &'a Path, contents: &'a [u8]) -> BoxFuture<'a, ()> {
+        Box::pin(async_std::fs::write(path, contents))
+    }
+    fn read_dir(path: &Path) -> BoxFuture<'_, Self::ReadDir> {
+        Box::pin(async_std::fs::read_dir(path))
+    }
+    fn create_dir_all(path: &Path) -> BoxFuture<'_, ()> {
+        Box::pin(async_std::fs::create_dir_all(path))
+    }
+    fn remove_file(path: &Path) -> BoxFuture<'_, ()> {
+        Box::pin(async_std::fs::remove_file(path))
+    }
+    fn rename<'a>(from: &'a Path, to: &'a Path) -> BoxFuture<'a, ()> {
+        Box::pin(async_std::fs::rename(from, to))
+    }
+    fn copy<'a>(from: &'a Path, to: &'a Path) -> BoxFuture<'a, u64> {
+        Box::pin(async_std::fs::copy(from, to))
+    }
+    fn sync(path: &Path) -> BoxFuture<'_, ()> {
+        Box::pin(async move {
+            // smoelius: On Windows, `sync_all` requires write access.
+            let file = async_std::fs::OpenOptions::new()
+                .write(true)
+                .open(path)
+                .await?;
+            file.sync_all().await
+        })
+    }
+}
+
+/// A [`SafeDir`] whose operations are performed asynchronously by the [`Backend`] `B`
+pub struct AsyncSafeDir<B> {
+    dir: SafeDir,
+    backend: PhantomData<fn() -> B>,
+}
+
+impl<B> Clone for AsyncSafeDir<B> {
+    fn clone(&self) -> Self {
+        Self {
+            dir: self.dir.clone(),
+            backend: PhantomData,
+        }
+    }
+}
+
+impl<B> Debug for AsyncSafeDir<B> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AsyncSafeDir")
+            .field("dir", &self.dir)
+            .field("backend", &std::any::type_name::<B>())
+            .finish()
+    }
+}
+
+impl<B> From<SafeDir> for AsyncSafeDir<B> {
+    fn from(dir: SafeDir) -> Self {
+        Self {
+            dir,
+            backend: PhantomData,
+        }
+    }
+}
+
+impl<B: Backend> AsyncSafeDir<B> {
+    /// Creates an `AsyncSafeDir` that checks paths with `dir`.
+    #[must_use]
+    pub fn new(dir: SafeDir) -> Self {
+        Self::from(dir)
+    }
+
+    /// The underlying [`SafeDir`]
+    #[must_use]
+    pub fn dir(&self) -> &SafeDir {
+        &self.dir
+    }
+
+    /// Like [`SafeDir::open`], but asynchronous. The `openat2` feature has no effect.
+    /// # Errors
+    /// Returns an error if the check fails, or if the file cannot be opened. In the latter case,
+    /// the error payload is an [`Error`] whose source is the operating system's error.
+    pub async fn open<P: AsRef<Path>>(&self, path: P) -> Result<B::File> {
+        let path = self.dir.join(path)?;
+        B::open(&path)
+            .await
+            .map_err(|error| Error::wrap_os_error("open failed", &path, error))
+    }
+
+    /// Like [`SafeDir::create`], but asynchronous. The `openat2` feature has no effect.
+    /// # Errors
+    /// Returns an error if the check fails, if the underlying [`SafeDir`] is in dry-run mode, or if
+    /// the file cannot be created. In the last case, the error payload is an [`Error`] whose
+    /// source is the operating system's error.
+    pub async fn create<P: AsRef<Path>>(&self, path: P) -> Result<B::File> {
+        let path = self.dir.join(path)?;
+        let path = match self.dir.record(Operation::Write { path }) {
+            Some(Operation::Write { path }) => path,
+            _ => return Err(Error::new("dry run").into()),
+        };
+        B::create(&path)
+            .await
+            .map_err(|error| Error::wrap_os_error("create failed", &path, error))
+    }
+
+    /// Like [`SafeDir::read`], but asynchronous.
+    /// # Errors
+    /// Returns an error if the check fails, or if the file cannot be read. In the latter case,
+    /// the error payload is an [`Error`] whose source is the operating system's error.
+    pub async fn read<P: AsRef<Path>>(&self, path: P) -> Result<Vec<u8>> {
+        let path = self.dir.join(path)?;
+        B::read(&path)
+            .await
+            .map_err(|error| Error::wrap_os_error("read failed", &path, error))
+    }
+
+    /// Like [`SafeDir::write`], but asynchronous.
+    /// # Errors
+    /// Returns an error if the check fails, or if the file cannot be written. In the latter case,
+    /// the error payload is an [`Error`] whose source is the operating system's error.
+    pub async fn write<P: AsRef<Path>, C: AsRef<[u8]>>(&self, path: P, contents: C) -> Result<()> {
+        let path = self.dir.join(path)?;
+        match self.dir.record(Operation::Write { path }) {
+            Some(Operation::Write { path }) => B::write(&path, contents.as_ref())
+                .await
+                .map_err(|error| Error::wrap_os_error("write failed", &path, error)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Like [`SafeDir::remove_file`], but asynchronous.
+    /// # Errors
+    /// Returns an error if the check fails, or if the file cannot be removed. In the latter case,
+    /// the error payload is an [`Error`] whose source is the operating system's error.
+    pub async fn remove_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = self.dir.join(path)?;
+        self.perform(Operation::RemoveFile { path }).await
+    }
+
+    /// Like [`SafeDir::create_dir_all`], but asynchronous.
+    /// # Errors
+    /// Returns an error if the check fails, or if a directory cannot be created. In the latter
+    /// case, the error payload is an [`Error`] whose source is the operating system's error.
+    pub async fn create_dir_all<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = self.dir.join(path)?;
+        self.perform(Operation::CreateDirAll { path }).await
+    }
+
+    /// Like [`SafeDir::read_dir`], but asynchronous.
+    /// # Errors
+    /// Returns an error if the check fails, or if the directory cannot be read. In the latter
+    /// case, the error payload is an [`Error`] whose source is the operating system's error.
+    pub async fn read_dir<P: AsRef<Path>>(&self, path: P) -> Result<B::ReadDir> {
+        let path = self.dir.join(path)?;
+        B::read_dir(&path)
+            .await
+            .map_err(|error| Error::wrap_os_error("read_dir failed", &path, error))
+    }
+
+    /// Like [`SafeDir::move_file`], but asynchronous.
+    /// # Errors
+    /// Returns an error if either check fails, or if an operating-system-level operation fails.
+    /// In the latter case, the error payload is an [`Error`] whose source is the operating
+    /// system's error.
+    pub async fn move_file<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        src: P,
+        dst_dir: &SafeDir,
+        dst: Q,
+    ) -> Result<()> {
+        let src = self.dir.join(src)?;
+        let dst = dst_dir.join(dst)?;
+        self.perform(Operation::Move { from: src, to: dst }).await
+    }
+
+    /// Like [`SafeDir::copy_file`], but asynchronous.
+    /// # Errors
+    /// Returns an error if either check fails, or if the copy fails. In the latter case, the error
+    /// payload is an [`Error`] whose source is the operating system's error.
+    pub async fn copy_file<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        src: P,
+        dst_dir: &SafeDir,
+        dst: Q,
+    ) -> Result<()> {
+        let src = self.dir.join(src)?;
+        let dst = dst_dir.join(dst)?;
+        self.perform(Operation::Copy { from: src, to: dst }).await
+    }
+
+    async fn perform(&self, operation: Operation) -> Result<()> {
+        let operation = match self.dir.record(operation) {
+            Some(operation) => operation,
+            None => return Ok(()),
+        };
+        match operation {
+            Operation::Move { from, to } => move_file::<B>(&from, &to).await,
+            Operation::Copy { from, to } => B::copy(&from, &to)
+                .await
+                .map(|_| ())
+                .map_err(|error| Error::wrap_os_error("copy failed", &from, error)),
+            Operation::CreateDirAll { path } => B::create_dir_all(&path)
+                .await
+                .map_err(|error| Error::wrap_os_error("create_dir_all failed", &path, error)),
+            Operation::RemoveFile { path } => B::remove_file(&path)
+                .await
+                .map_err(|error| Error::wrap_os_error("remove failed", &path, error)),
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Like `fs::move_file`, but asynchronous.
+async fn move_file<B: Backend>(src: &Path, dst: &Path) -> Result<()> {
+    match B::rename(src, dst).await {
+        Ok(()) => return Ok(()),
+        Err(error) if crosses_devices(&error) => {}
+        Err(error) => return Err(Error::wrap_os_error("rename failed", src, error)),
+    }
+    let copied = async {
+        B::copy(src, dst)
+            .await
+            .map_err(|error| Error::wrap_os_error("copy failed", src, error))?;
+        B::sync(dst)
+            .await
+            .map_err(|error| Error::wrap_os_error("sync failed", dst, error))
+    };
+    if let Err(error) = copied.await {
+        let _ = B::remove_file(dst).await;
+        return Err(error);
+    }
+    B::remove_file(src)
+        .await
+        .map_err(|error| Error::wrap_os_error("remove failed", src, error))
+}
@@ -0,0 +1,231 @@
+//! Path-type-independent implementations of the checks, for implementing [`crate::PathOps`] for
+//! path types other than `std::path::Path` and `camino::Utf8Path`
+//!
+//! Each function takes a path as a sequence of [`ComponentKind`]s. A backend maps its own
+//! components to `ComponentKind`s and forwards to these functions, e.g.:
+//! ```
+//! # use safe_path::backend::{self, ComponentKind};
+//! # use std::path::{Component, Path};
+//! fn kind(component: Component<'_>) -> ComponentKind {
+//!     match component {
+//!         Component::Prefix(_) => ComponentKind::Prefix,
+//!         Component::RootDir => ComponentKind::RootDir,
+//!         Component::CurDir => ComponentKind::CurDir,
+//!         Component::ParentDir => ComponentKind::ParentDir,
+//!         Component::Normal(_) => ComponentKind::Normal,
+//!     }
+//! }
+//! let path = Path::new("x/../../y");
+//! assert!(backend::check_join_safety(path.components().map(kind), false, || false).is_err());
+//! ```
+
+use crate::{Error, Violation};
+use std::io::Result;
+
+/// The kind of a path component, without its contents
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ComponentKind {
+    /// A Windows prefix, e.g., `C:`, other than a verbatim prefix
+    Prefix,
+    /// A Windows verbatim prefix, e.g., `\\?\C:` or `\\?\UNC\server\share`
+    VerbatimPrefix,
+    /// The root directory, e.g., `/`
+    RootDir,
+    /// A reference to the current directory, i.e., `.`
+    CurDir,
+    /// A reference to the parent directory, i.e., `..`
+    ParentDir,
+    /// A normal component, e.g., `a` in `/a/b`
+    Normal,
+}
+
+/// Implements [`crate::PathOps::check_join_safety`]. `self_is_root` should return
+/// `self.is_root()`; it is called only if `path` contains a root directory, a prefix, or an
+/// escaping `..`. Arguments containing a verbatim prefix, or a prefix not followed by a root
+/// directory (e.g., `C:x`), are rejected regardless of `self`.
+/// # Errors
+/// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if the check fails. The
+/// error payload is an [`Error`] whose contents are unstable and subject to change.
+pub fn check_join_safety<I, F>(path: I, relaxed: bool, mut self_is_root: F) -> Result<()>
+where
+    I: IntoIterator<Item = ComponentKind>,
+    I::IntoIter: Clone,
+    F: FnMut() -> bool,
+{
+    let path = path.into_iter();
+    // smoelius: Fast path for the common case of a single normal component, e.g., a file name.
+    let mut components = path.clone();
+    if let (Some(ComponentKind::Normal), None) = (components.next(), components.next()) {
+        return Ok(());
+    }
+    let original = path.clone();
+    let mut n = 0;
+    let mut path = path.peekable();
+    while let Some(component) = path.next() {
+        match component {
+            // smoelius: Windows does not normalize paths with verbatim prefixes, e.g., `..` is not
+            // resolved. An argument with one is rejected regardless of the receiver.
+            ComponentKind::VerbatimPrefix => return Err(join_error(original, self_is_root)),
+            // smoelius: A prefix without a root directory, e.g., `C:x`, is relative to the drive's
+            // current directory, which could be anywhere. It is rejected regardless of the
+            // receiver.
+            ComponentKind::Prefix if path.peek() != Some(&ComponentKind::RootDir) => {
+                return Err(join_error(original, self_is_root))
+            }
+            ComponentKind::Prefix | ComponentKind::RootDir => {
+                if !self_is_root() {
+                    return Err(join_error(original, self_is_root));
+                }
+                n = 0;
+            }
+            ComponentKind::CurDir => {}
+            ComponentKind::ParentDir => {
+                if n <= 0 {
+                    if !self_is_root() {
+                        return Err(join_error(original, self_is_root));
+                    }
+                    continue;
+                }
+                n -= 1;
+            }
+            ComponentKind::Normal => n += 1,
+        }
+    }
+    if n > 0 || (relaxed && n == 0) {
+        Ok(())
+    } else {
+        Err(join_error(original, self_is_root))
+    }
+}
+
+// smoelius: The error carries the first violation `explain_join_safety` finds. The explanation is
+// computed only once the check has failed, so that the check itself does not allocate.
+fn join_error<I, F>(path: I, self_is_root: F) -> std::io::Error
+where
+    I: Iterator<Item = ComponentKind>,
+    F: FnMut() -> bool,
+{
+    let error = Error::new("unsafe join operation");
+    match explain_join_safety(path, self_is_root).into_iter().next() {
+        Some(violation) => error.with_violation(violation).into(),
+        None => error.into(),
+    }
+}
+
+/// Implements [`crate::PathOps::explain_join_safety`]. See [`check_join_safety`].
+pub fn explain_join_safety<I, F>(path: I, mut self_is_root: F) -> Vec<Violation>
+where
+    I: IntoIterator<Item = ComponentKind>,
+    F: FnMut() -> bool,
+{
+    let mut violations = Vec::new();
+    let mut n = 0;
+    let mut path = path.into_iter().enumerate().peekable();
+    while let Some((index, component)) = path.next() {
+        match component {
+            ComponentKind::VerbatimPrefix => {
+                violations.push(Violation::VerbatimPrefix { index });
+                n = 0;
+            }
+            ComponentKind::Prefix
+                if path.peek().map(|&(_, next)| next) != Some(ComponentKind::RootDir) =>
+            {
+                violations.push(Violation::DriveRelative { index });
+                n = 0;
+            }
+            ComponentKind::Prefix | ComponentKind::RootDir => {
+                if !self_is_root() {
+                    violations.push(Violation::Absolute { index });
+                }
+                n = 0;
+            }
+            ComponentKind::CurDir => {}
+            ComponentKind::ParentDir => {
+                if n <= 0 {
+                    if !self_is_root() {
+                        violations.push(Violation::EscapingParentDir { index });
+                    }
+                    continue;
+                }
+                n -= 1;
+            }
+            ComponentKind::Normal => n += 1,
+        }
+    }
+    if violations.is_empty() && n <= 0 {
+        violations.push(Violation::SelfJoin);
+    }
+    violations
+}
+
+/// Implements [`crate::PathOps::check_parent_safety`]. `last` should be the kind of `self`'s last
+/// component, and `parent_is_root` should return `self.parent().map_or(true, PathOps::is_root)`.
+/// # Errors
+/// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if the check fails. The
+/// error payload is an [`Error`] whose contents are unstable and subject to change.
+pub fn check_parent_safety<F>(
+    last: Option<ComponentKind>,
+    relaxed: bool,
+    parent_is_root: F,
+) -> Result<()>
+where
+    F: FnOnce() -> bool,
+{
+    let err = Err(Error::new("unsafe parent operation").into());
+    match last {
+        None
+        | Some(
+            ComponentKind::Prefix
+            | ComponentKind::VerbatimPrefix
+            | ComponentKind::RootDir
+            | ComponentKind::CurDir,
+        ) => {
+            if relaxed {
+                Ok(())
+            } else {
+                err
+            }
+        }
+        Some(ComponentKind::ParentDir) => {
+            if relaxed && parent_is_root() {
+                Ok(())
+            } else {
+                err
+            }
+        }
+        Some(ComponentKind::Normal) => Ok(()),
+    }
+}
+
+/// Implements [`crate::PathOps::is_root`] for paths that have a root. A prefix not followed by a
+/// root directory is treated as a root, so a backend should first rule out drive-relative paths,
+/// e.g., `C:`.
+pub fn is_root<I>(path: I) -> bool
+where
+    I: IntoIterator<Item = ComponentKind>,
+{
+    let mut n: Option<i32> = None;
+    for component in path {
+        match component {
+            ComponentKind::Prefix | ComponentKind::VerbatimPrefix | ComponentKind::RootDir => {
+                n = Some(0);
+            }
+            ComponentKind::CurDir => {}
+            ComponentKind::ParentDir => n = n.map(|n| if n <= 0 { n } else { n - 1 }),
+            ComponentKind::Normal => n = n.map(|n| n + 1),
+        }
+    }
+    n == Some(0)
+}
+
+/// Implements [`crate::PathOps::is_file_name`].
+pub fn is_file_name<I>(path: I) -> bool
+where
+    I: IntoIterator<Item = ComponentKind>,
+{
+    let mut components = path.into_iter();
+    matches!(
+        (components.next(), components.next()),
+        (Some(ComponentKind::Normal), None)
+    )
+}
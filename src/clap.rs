@@ -0,0 +1,89 @@
+//! Parsing of command-line arguments that must refer to files inside of a directory (requires the
+//! `clap` feature)
+//!
+//! [`contained_in`] returns a [`TypedValueParser`] that joins an argument onto a base directory
+//! with [`SafePath::safe_join_with`]. An argument that fails the check is reported by `clap` like
+//! any other invalid value:
+//! ```
+//! # use clap::{Arg, Command};
+//! # use std::path::{Path, PathBuf};
+//! let command = Command::new("serve").arg(
+//!     Arg::new("page")
+//!         .long("page")
+//!         .value_parser(safe_path::clap::contained_in("www")),
+//! );
+//!
+//! let matches = command
+//!     .clone()
+//!     .try_get_matches_from(["serve", "--page", "docs/index.html"])
+//!     .unwrap();
+//! let page = matches.get_one::<PathBuf>("page").unwrap();
+//! assert_eq!(page, Path::new("www/docs/index.html"));
+//!
+//! assert!(command
+//!     .try_get_matches_from(["serve", "--page", "../secret"])
+//!     .is_err());
+//! ```
+
+use crate::{Policy, SafePath};
+use ::clap::{builder::TypedValueParser, error::ErrorKind, Arg, Command, Error};
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+
+/// Returns a [`ContainedIn`] that accepts arguments that [`SafePath::safe_join`] accepts when
+/// joining them onto `base`.
+pub fn contained_in<P: AsRef<Path>>(base: P) -> ContainedIn {
+    ContainedIn {
+        base: base.as_ref().to_path_buf(),
+        policy: Policy::default(),
+    }
+}
+
+/// A [`TypedValueParser`] whose values are arguments joined onto a base directory. See
+/// [`contained_in`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContainedIn {
+    base: PathBuf,
+    policy: Policy,
+}
+
+impl ContainedIn {
+    /// Checks arguments with `policy` rather than the default [`Policy`].
+    #[must_use]
+    pub fn policy(mut self, policy: Policy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// The directory onto which arguments are joined
+    #[must_use]
+    pub fn base(&self) -> &Path {
+        &self.base
+    }
+}
+
+impl TypedValueParser for ContainedIn {
+    type Value = PathBuf;
+
+    fn parse_ref(
+        &self,
+        cmd: &Command,
+        arg: Option<&Arg>,
+        value: &OsStr,
+    ) -> std::result::Result<Self::Value, Error> {
+        self.base
+            .safe_join_with(&self.policy, value)
+            .map_err(|error| {
+                let arg = arg.map_or_else(|| "...".to_owned(), ToString::to_string);
+                let message = format!(
+                    "invalid value '{}' for '{}': {}\n",
+                    value.to_string_lossy(),
+                    arg,
+                    error
+                );
+                Error::raw(ErrorKind::ValueValidation, message).with_cmd(cmd)
+            })
+    }
+}
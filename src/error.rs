@@ -0,0 +1,244 @@
+use crate::{hash::fnv1a_64, http::StatusTable, PolicyVersion, Violation};
+use std::{
+    fmt::{Display, Formatter},
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Payload of the [`std::io::Error`]s returned by this crate
+///
+/// Use [`Error::downcast_ref`] to recover an `Error` from a [`std::io::Error`]. If an operation
+/// passed the lexical checks but then failed at the operating-system level, the original
+/// [`std::io::Error`] is available via [`std::error::Error::source`], and the wrapping
+/// [`std::io::Error`] has the same [`std::io::ErrorKind`].
+#[derive(Debug)]
+pub struct Error {
+    context: &'static str,
+    violation: Option<Violation>,
+    policy_version: Option<PolicyVersion>,
+    path: Option<PathBuf>,
+    source: Option<io::Error>,
+}
+
+impl Error {
+    /// The maximum number of characters of a file name [`Error::redacted`] retains
+    pub const REDACTED_NAME_LEN: usize = 32;
+
+    pub(crate) fn new(context: &'static str) -> Self {
+        Self {
+            context,
+            violation: None,
+            policy_version: None,
+            path: None,
+            source: None,
+        }
+    }
+
+    pub(crate) fn with_violation(mut self, violation: Violation) -> Self {
+        self.violation = Some(violation);
+        self
+    }
+
+    #[cfg_attr(not(feature = "fs"), allow(dead_code))]
+    pub(crate) fn with_path<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    pub(crate) fn with_policy_version(mut self, policy_version: PolicyVersion) -> Self {
+        self.policy_version = Some(policy_version);
+        self
+    }
+
+    /// Applies `f` to `error`'s payload, if it has one.
+    pub(crate) fn map<F: FnOnce(Self) -> Self>(error: io::Error, f: F) -> io::Error {
+        if Self::downcast_ref(&error).is_none() {
+            return error;
+        }
+        match error
+            .into_inner()
+            .map(<dyn std::error::Error + Send + Sync>::downcast::<Self>)
+        {
+            Some(Ok(inner)) => f(*inner).into(),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Wraps `source`, an error the operating system returned while operating on `path`. The
+    /// result has `source`'s kind, and its payload is an `Error` whose source is `source`.
+    #[must_use]
+    pub fn wrap_os_error<P: AsRef<Path>>(
+        context: &'static str,
+        path: P,
+        source: io::Error,
+    ) -> io::Error {
+        Self {
+            context,
+            violation: None,
+            policy_version: None,
+            path: Some(path.as_ref().to_path_buf()),
+            source: Some(source),
+        }
+        .into()
+    }
+
+    /// Returns a copy of `self` that is safe to show to untrusted clients. The path, if any, is
+    /// replaced by a hash of the path followed by its file name (truncated to
+    /// [`REDACTED_NAME_LEN`](Self::REDACTED_NAME_LEN) characters), e.g., `[1a2b3c4d]/passwd`, so
+    /// that the on-disk layout is not revealed. The source, if any, is replaced by an error with
+    /// only the same kind and OS error code. Keep `self` for server-side logs.
+    /// ```
+    /// # use safe_path::Error;
+    /// # use std::io;
+    /// let error = Error::wrap_os_error("open", "/srv/www/secret", io::Error::from_raw_os_error(2));
+    /// let redacted = Error::downcast_ref(&error).unwrap().redacted();
+    /// assert!(!redacted.to_string().contains("/srv/www"));
+    /// assert!(redacted.to_string().ends_with("/secret"));
+    /// ```
+    #[must_use]
+    pub fn redacted(&self) -> Self {
+        Self {
+            context: self.context,
+            violation: self.violation.clone(),
+            policy_version: self.policy_version,
+            path: self.path.as_deref().map(redact_path),
+            source: self
+                .source
+                .as_ref()
+                .map(|source| match source.raw_os_error() {
+                    Some(code) => io::Error::from_raw_os_error(code),
+                    None => io::Error::from(source.kind()),
+                }),
+        }
+    }
+
+    /// Applies [`Error::redacted`] to `error`'s payload. Errors without an `Error` payload are
+    /// replaced by an error with only the same kind.
+    #[must_use]
+    pub fn redact(error: &io::Error) -> io::Error {
+        match Self::downcast_ref(error) {
+            Some(inner) => inner.redacted().into(),
+            None => io::Error::from(error.kind()),
+        }
+    }
+
+    /// Returns the `Error` payload of `error`, if it has one.
+    #[must_use]
+    pub fn downcast_ref(error: &io::Error) -> Option<&Self> {
+        error.get_ref().and_then(|inner| inner.downcast_ref())
+    }
+
+    /// Describes the operation that failed, e.g., "unsafe join operation".
+    #[must_use]
+    pub fn context(&self) -> &'static str {
+        self.context
+    }
+
+    /// The reason the check failed, if known
+    #[must_use]
+    pub fn violation(&self) -> Option<&Violation> {
+        self.violation.as_ref()
+    }
+
+    /// The version of the [`crate::Policy`] semantics that rejected the path, if the check was
+    /// performed by a [`crate::Policy`]
+    #[must_use]
+    pub fn policy_version(&self) -> Option<PolicyVersion> {
+        self.policy_version
+    }
+
+    /// The path the failed operation was applied to, if known
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// The HTTP status code [`crate::http::StatusTable::default`] assigns to `self`, e.g., 404 for
+    /// a traversal attempt.
+    #[must_use]
+    pub fn http_status(&self) -> u16 {
+        self.http_status_with(&StatusTable::default())
+    }
+
+    /// The HTTP status code `table` assigns to `self`.
+    #[must_use]
+    pub fn http_status_with(&self, table: &StatusTable) -> u16 {
+        table.status(self)
+    }
+
+    pub(crate) fn source_kind(&self) -> Option<io::ErrorKind> {
+        self.source.as_ref().map(io::Error::kind)
+    }
+
+    /// The OS error code of the wrapped error, if any. See [`std::io::Error::raw_os_error`].
+    #[must_use]
+    pub fn raw_os_error(&self) -> Option<i32> {
+        self.source.as_ref().and_then(io::Error::raw_os_error)
+    }
+}
+
+fn redact_path(path: &Path) -> PathBuf {
+    #[allow(clippy::cast_possible_truncation)]
+    let hash = fnv1a_64(path.to_string_lossy().as_bytes()) as u32;
+    let mut redacted = PathBuf::from(format!("[{:08x}]", hash));
+    if let Some(file_name) = path.file_name() {
+        let file_name: String = file_name
+            .to_string_lossy()
+            .chars()
+            .take(Error::REDACTED_NAME_LEN)
+            .collect();
+        redacted.push(file_name);
+    }
+    redacted
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.context)?;
+        if let Some(violation) = &self.violation {
+            write!(f, ": {}", violation)?;
+        }
+        if let Some(path) = &self.path {
+            write!(f, ": {}", path.display())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Error {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(f, "{=str}", self.context);
+        if let Some(violation) = &self.violation {
+            defmt::write!(f, ": {}", violation);
+        }
+        if let Some(path) = &self.path {
+            defmt::write!(
+                f,
+                ": {=str}",
+                crate::escape::escape(path.as_os_str()).as_str()
+            );
+        }
+        if let Some(code) = self.raw_os_error() {
+            defmt::write!(f, " (os error {=i32})", code);
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source
+            .as_ref()
+            .map(|source| source as &(dyn std::error::Error + 'static))
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(error: Error) -> Self {
+        let kind = error
+            .source
+            .as_ref()
+            .map_or(io::ErrorKind::Other, io::Error::kind);
+        io::Error::new(kind, error)
+    }
+}
@@ -0,0 +1,171 @@
+//! Lossless, printable representations of paths and their components
+//!
+//! [`Path::display`](std::path::Path::display) replaces parts of a path that are not valid Unicode
+//! with U+FFFD, so distinct adversarial names can display identically. [`escape`] instead produces
+//! a string from which [`unescape`] recovers the original exactly, which makes it suitable for
+//! logs, serialized records, and other output that may be inspected later:
+//! ```
+//! # use safe_path::escape::{escape, unescape};
+//! # use std::ffi::OsStr;
+//! assert_eq!(escape(OsStr::new("a\\b\nc")), "a\\\\b\\u{a}c");
+//! # #[cfg(unix)]
+//! # {
+//! use std::os::unix::ffi::OsStrExt;
+//! let name = OsStr::from_bytes(b"caf\xe9");
+//! assert_eq!(escape(name), "caf\\xe9");
+//! assert_eq!(unescape(&escape(name)).unwrap(), name);
+//! # }
+//! ```
+
+use crate::{Error, Violation};
+use std::{
+    ffi::{OsStr, OsString},
+    fmt::Write,
+    io::Result,
+};
+
+/// Converts `s` into a printable string:
+///
+/// * `\` is replaced with `\\`.
+/// * Control characters are replaced with `\u{…}` escapes, e.g., `\u{a}` for a newline.
+/// * On Unix, bytes that are not part of valid UTF-8 are replaced with `\x…` escapes, e.g., `\xe9`.
+/// * On Windows, unpaired surrogates are replaced with `\u{…}` escapes, e.g., `\u{d800}`.
+///
+/// Other characters are unchanged. On platforms other than Unix and Windows, `s` is converted
+/// lossily, and the result may not round-trip.
+#[must_use]
+pub fn escape(s: &OsStr) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        let mut bytes = s.as_bytes();
+        while !bytes.is_empty() {
+            let (valid, rest) = match std::str::from_utf8(bytes) {
+                Ok(valid) => (valid, &[][..]),
+                Err(error) => {
+                    let (valid, rest) = bytes.split_at(error.valid_up_to());
+                    // smoelius: `valid` was just validated.
+                    (std::str::from_utf8(valid).unwrap_or_default(), rest)
+                }
+            };
+            escape_str(&mut escaped, valid);
+            match rest.split_first() {
+                Some((&byte, rest)) => {
+                    // smoelius: Writing to a `String` cannot fail.
+                    let _ = write!(escaped, "\\x{:02x}", byte);
+                    bytes = rest;
+                }
+                None => break,
+            }
+        }
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::ffi::OsStrExt;
+        for c in std::char::decode_utf16(s.encode_wide()) {
+            match c {
+                Ok(c) => escape_char(&mut escaped, c),
+                Err(error) => {
+                    escaped.push_str(&format!("\\u{{{:x}}}", error.unpaired_surrogate()));
+                }
+            }
+        }
+    }
+    #[cfg(not(any(unix, windows)))]
+    escape_str(&mut escaped, &s.to_string_lossy());
+    escaped
+}
+
+/// Reverses [`escape`].
+/// # Errors
+/// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if `escaped` is not a
+/// possible result of [`escape`] on this platform. The error payload is an [`Error`] whose contents
+/// are unstable and subject to change.
+pub fn unescape(escaped: &str) -> Result<OsString> {
+    let err = || {
+        Error::new("invalid escaped string")
+            .with_violation(Violation::InvalidEncoding)
+            .into()
+    };
+    #[cfg(unix)]
+    let mut units: Vec<u8> = Vec::with_capacity(escaped.len());
+    #[cfg(not(unix))]
+    let mut units: Vec<u16> = Vec::with_capacity(escaped.len());
+    let mut chars = escaped.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            push_char(&mut units, c);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => push_char(&mut units, '\\'),
+            #[cfg(unix)]
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                units.push(u8::from_str_radix(&hex, 16).map_err(|_| err())?);
+            }
+            Some('u') => {
+                if chars.next() != Some('{') {
+                    return Err(err());
+                }
+                let hex: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                let code = u32::from_str_radix(&hex, 16).map_err(|_| err())?;
+                match std::char::from_u32(code) {
+                    Some(c) => push_char(&mut units, c),
+                    #[cfg(windows)]
+                    #[allow(clippy::cast_possible_truncation)]
+                    None if (0xd800..0xe000).contains(&code) => units.push(code as u16),
+                    None => return Err(err()),
+                }
+            }
+            _ => return Err(err()),
+        }
+    }
+    #[cfg(unix)]
+    let s = {
+        use std::os::unix::ffi::OsStringExt;
+        OsString::from_vec(units)
+    };
+    #[cfg(windows)]
+    let s = {
+        use std::os::windows::ffi::OsStringExt;
+        OsString::from_wide(&units)
+    };
+    #[cfg(not(any(unix, windows)))]
+    let s = OsString::from(String::from_utf16_lossy(&units));
+    // smoelius: Requiring the canonical escape makes `escape` and `unescape` inverses.
+    if escape(&s) != escaped {
+        return Err(err());
+    }
+    Ok(s)
+}
+
+#[cfg(not(windows))]
+fn escape_str(escaped: &mut String, s: &str) {
+    for c in s.chars() {
+        escape_char(escaped, c);
+    }
+}
+
+fn escape_char(escaped: &mut String, c: char) {
+    if c == '\\' {
+        escaped.push_str("\\\\");
+    } else if c.is_control() {
+        let _ = write!(escaped, "\\u{{{:x}}}", u32::from(c));
+    } else {
+        escaped.push(c);
+    }
+}
+
+#[cfg(unix)]
+fn push_char(units: &mut Vec<u8>, c: char) {
+    let mut buf = [0; 4];
+    units.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+}
+
+#[cfg(not(unix))]
+fn push_char(units: &mut Vec<u16>, c: char) {
+    let mut buf = [0; 2];
+    units.extend_from_slice(c.encode_utf16(&mut buf));
+}
@@ -0,0 +1,199 @@
+use crate::{Error, Violation};
+use std::{
+    convert::TryFrom,
+    ffi::{OsStr, OsString},
+    fmt::{Display, Formatter},
+    io::Result,
+    path::{Component, Path},
+    str::FromStr,
+};
+
+/// A path consisting of exactly one normal component, e.g., `file.txt`
+///
+/// Joining a `FileName` onto a directory always produces a path directly inside that directory:
+/// ```
+/// # use safe_path::FileName;
+/// # use std::path::Path;
+/// let name = FileName::new("file.txt").unwrap();
+/// assert_eq!(Path::new("dir").join(&name), Path::new("dir/file.txt"));
+///
+/// assert!(FileName::new("../file.txt").is_err());
+/// assert!(FileName::new("dir/file.txt").is_err());
+/// assert!(FileName::new("file.txt/").is_err());
+/// ```
+///
+/// With the `serde` feature, a `FileName` is checked when it is deserialized.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FileName(OsString);
+
+impl FileName {
+    /// Returns `Ok(FileName(name))` if `name` consists of exactly one normal component.
+    /// # Errors
+    /// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if the check fails. The
+    /// error payload is an [`Error`] whose contents are unstable and subject to change.
+    pub fn new<S: AsRef<OsStr>>(name: S) -> Result<Self> {
+        let name = name.as_ref();
+        // smoelius: Comparing the component to `name` rejects trailing separators, e.g., `x/`.
+        if !matches!(
+            Path::new(name).components().next(),
+            Some(Component::Normal(normal)) if normal == name
+        ) {
+            return Err(Error::new("invalid file name")
+                .with_violation(Violation::NotFileName)
+                .into());
+        }
+        Ok(Self(name.to_os_string()))
+    }
+
+    /// Converts `name` into a `FileName` using [`crate::sanitize::sanitize`]. Unlike
+    /// [`FileName::new`], this never fails.
+    /// ```
+    /// # use safe_path::FileName;
+    /// assert_eq!(FileName::sanitize("../secret").to_string(), ".._secret");
+    /// ```
+    #[must_use]
+    pub fn sanitize(name: &str) -> Self {
+        Self(crate::sanitize::sanitize(name).into())
+    }
+
+    /// Returns the name as an [`OsStr`].
+    #[must_use]
+    pub fn as_os_str(&self) -> &OsStr {
+        &self.0
+    }
+
+    /// Returns the name as a [`Path`].
+    #[must_use]
+    pub fn as_path(&self) -> &Path {
+        Path::new(&self.0)
+    }
+
+    /// Returns the name's bytes (Unix only).
+    #[cfg(unix)]
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        std::os::unix::ffi::OsStrExt::as_bytes(self.0.as_os_str())
+    }
+
+    /// Returns `FileName::new(OsStr::from_bytes(bytes))` (Unix only). The bytes need not be valid
+    /// UTF-8.
+    /// ```
+    /// # use safe_path::FileName;
+    /// let name = FileName::from_bytes(b"caf\xe9").unwrap();
+    /// assert_eq!(name.as_bytes(), b"caf\xe9");
+    /// assert!(FileName::from_bytes(b"..").is_err());
+    /// ```
+    /// # Errors
+    /// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if the check fails. The
+    /// error payload is an [`Error`] whose contents are unstable and subject to change.
+    #[cfg(unix)]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Self::new(<OsStr as std::os::unix::ffi::OsStrExt>::from_bytes(bytes))
+    }
+
+    /// Converts `self` into an [`OsString`].
+    #[must_use]
+    pub fn into_os_string(self) -> OsString {
+        self.0
+    }
+}
+
+impl AsRef<OsStr> for FileName {
+    fn as_ref(&self) -> &OsStr {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for FileName {
+    fn as_ref(&self) -> &Path {
+        self.as_path()
+    }
+}
+
+impl FromStr for FileName {
+    type Err = std::io::Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        Self::new(name)
+    }
+}
+
+impl TryFrom<&str> for FileName {
+    type Error = std::io::Error;
+
+    fn try_from(name: &str) -> Result<Self> {
+        Self::new(name)
+    }
+}
+
+/// Displays the name lossily. Use [`crate::escape::escape`] for a lossless representation.
+impl Display for FileName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_path().display())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for FileName {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        self.as_path().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for FileName {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let name = std::path::PathBuf::deserialize(deserializer)?;
+        Self::new(&name).map_err(|error| {
+            serde::de::Error::custom(format_args!(
+                "invalid file name `{}`: {}",
+                crate::escape::escape(name.as_os_str()),
+                error
+            ))
+        })
+    }
+}
+
+/// Describes a `FileName` as a non-empty string other than `.` and `..` with no `/` or `\`. The
+/// schema rejects `\` on all platforms, since it is a separator on Windows.
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for FileName {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "FileName".into()
+    }
+
+    fn json_schema(_generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "type": "string",
+            "minLength": 1,
+            "pattern": r"^[^/\\]+$",
+            "not": { "enum": [".", ".."] },
+            "description": "A file name consisting of exactly one path component, e.g., `file.txt`"
+        })
+    }
+}
+
+/// Generates a `FileName` from an arbitrary string. A string that is not a file name is converted
+/// with [`FileName::sanitize`].
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for FileName {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let name = <&str>::arbitrary(u)?;
+        Ok(Self::new(name).unwrap_or_else(|_| Self::sanitize(name)))
+    }
+}
+
+/// Generates a `FileName` from an arbitrary string, as the `arbitrary::Arbitrary` implementation
+/// does
+#[cfg(feature = "quickcheck")]
+impl quickcheck::Arbitrary for FileName {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        let name = String::arbitrary(g);
+        Self::new(&name).unwrap_or_else(|_| Self::sanitize(&name))
+    }
+}
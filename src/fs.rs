@@ -0,0 +1,1725 @@
+//! Filesystem operations confined to a base directory (requires the `fs` feature)
+
+use crate::{
+    hash::{fnv1a_64_update, FNV1A_64_OFFSET},
+    lexical::lexically_equal,
+    sanitize::sanitize,
+    Action, Error, Policy, SafePath, Violation,
+};
+use std::{
+    fmt::{Debug, Formatter},
+    fs::{self, File, FileType, OpenOptions},
+    io::{self, Read, Result},
+    path::{Component, Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::SystemTime,
+};
+
+type Hook = Arc<dyn Fn(&Path, &io::Error) + Send + Sync>;
+
+/// A base directory whose operations take untrusted relative paths and check them with
+/// [`SafePath::safe_join_with`] before touching the filesystem
+#[derive(Clone)]
+pub struct SafeDir {
+    root: PathBuf,
+    policy: Policy,
+    hook: Option<Hook>,
+    plan: Option<Arc<Mutex<Vec<Operation>>>>,
+    same_device: bool,
+}
+
+/// Another name for [`SafeDir`]. Its [`SafeDir::open`], [`SafeDir::create`], [`SafeDir::read`],
+/// [`SafeDir::write`], [`SafeDir::remove_file`], [`SafeDir::create_dir_all`], and
+/// [`SafeDir::read_dir`] methods take untrusted relative paths and check them before touching the
+/// filesystem:
+/// ```
+/// # use safe_path::fs::ScopedFs;
+/// # let tempdir = tempfile::tempdir().unwrap();
+/// let scoped = ScopedFs::new(tempdir.path());
+/// scoped.write("x", "contents").unwrap();
+/// assert_eq!(b"contents", scoped.read("x").unwrap().as_slice());
+/// assert!(scoped.read("../x").is_err());
+/// ```
+pub type ScopedFs = SafeDir;
+
+impl Debug for SafeDir {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SafeDir")
+            .field("root", &self.root)
+            .field("policy", &self.policy)
+            .field("hook", &self.hook.as_ref().map(|_| ".."))
+            .field("dry_run", &self.plan.is_some())
+            .field("same_device", &self.same_device)
+            .finish()
+    }
+}
+
+impl SafeDir {
+    /// Creates a `SafeDir` for `root` with the default [`Policy`]. The filesystem is not consulted.
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        Self::with_policy(root, Policy::default())
+    }
+
+    /// Creates a `SafeDir` for `root` with `policy`. The filesystem is not consulted.
+    pub fn with_policy<P: AsRef<Path>>(root: P, policy: Policy) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            policy,
+            hook: None,
+            plan: None,
+            same_device: false,
+        }
+    }
+
+    /// Enables or disables dry-run mode. In dry-run mode, operations that would modify the
+    /// filesystem check their arguments as usual, but record the [`Operation`]s they would perform
+    /// instead of performing them. Clones of a `SafeDir` in dry-run mode share one record.
+    /// ```
+    /// # use safe_path::fs::{Operation, SafeDir};
+    /// # use std::path::PathBuf;
+    /// let staging = SafeDir::new("staging").dry_run(true);
+    /// let published = SafeDir::new("published");
+    /// staging.move_file("x", &published, "y").unwrap();
+    /// assert!(staging.move_file("../x", &published, "y").is_err());
+    /// assert_eq!(
+    ///     vec![Operation::Move {
+    ///         from: PathBuf::from("staging/x"),
+    ///         to: PathBuf::from("published/y"),
+    ///     }],
+    ///     staging.planned_operations()
+    /// );
+    /// ```
+    #[must_use]
+    pub fn dry_run(mut self, enabled: bool) -> Self {
+        self.plan = if enabled {
+            Some(Arc::new(Mutex::new(Vec::new())))
+        } else {
+            None
+        };
+        self
+    }
+
+    /// Returns true if `self` is in dry-run mode.
+    #[must_use]
+    pub fn is_dry_run(&self) -> bool {
+        self.plan.is_some()
+    }
+
+    /// The operations recorded in dry-run mode, in the order they were planned. Returns an empty
+    /// vector if `self` is not in dry-run mode.
+    #[must_use]
+    pub fn planned_operations(&self) -> Vec<Operation> {
+        self.plan.as_ref().map_or_else(Vec::new, |plan| {
+            plan.lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner)
+                .clone()
+        })
+    }
+
+    /// Creates a `SafeDir` for the current working directory with the default [`Policy`].
+    ///
+    /// The working directory is captured once, when this function is called. Later changes to the
+    /// working directory (e.g., by another thread) do not affect the result. However, the captured
+    /// path may be renamed or replaced by a symbolic link after it is captured; use
+    /// [`SafeDir::current_canonicalized`] to at least capture a path without symbolic links.
+    /// # Errors
+    /// Returns an error if the working directory cannot be determined. The error payload is an
+    /// [`Error`] whose source is the operating system's error.
+    pub fn current() -> Result<Self> {
+        std::env::current_dir()
+            .map(Self::new)
+            .map_err(|error| Error::wrap_os_error("current_dir failed", Path::new("."), error))
+    }
+
+    /// Like [`SafeDir::current`], but canonicalizes the working directory with
+    /// [`std::fs::canonicalize`] before capturing it.
+    /// # Errors
+    /// Returns an error if the working directory cannot be determined or canonicalized. The error
+    /// payload is an [`Error`] whose source is the operating system's error.
+    pub fn current_canonicalized() -> Result<Self> {
+        let current = Self::current()?;
+        fs::canonicalize(&current.root)
+            .map(Self::new)
+            .map_err(|error| Error::wrap_os_error("canonicalize failed", &current.root, error))
+    }
+
+    /// Calls `hook` with the argument and the error whenever a check fails, including when the
+    /// policy's [`Action`] is [`Action::Divert`]. See also [`crate::report::RateLimiter`].
+    /// ```
+    /// # use safe_path::{fs::SafeDir, report::RateLimiter};
+    /// # use std::time::Duration;
+    /// let limiter = RateLimiter::new(10, Duration::from_secs(60));
+    /// let dir = SafeDir::new("www").on_rejection(move |path, error| {
+    ///     if limiter.allow("www".as_ref(), error) {
+    ///         eprintln!("rejected {:?}: {}", path, error);
+    ///     }
+    /// });
+    /// assert!(dir.join("../etc/passwd").is_err());
+    /// ```
+    #[must_use]
+    pub fn on_rejection<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&Path, &io::Error) + Send + Sync + 'static,
+    {
+        self.hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Enables or disables the device check. When enabled, [`SafeDir::join`] also rejects a path
+    /// whose existing part crosses onto a different filesystem than `self.root()`, e.g., because
+    /// a component is a mount point or a symbolic link to another filesystem. Filesystems are
+    /// compared by device number (`st_dev`). On platforms other than Unix, the check has no effect.
+    ///
+    /// The filesystem may change after a path is checked, so the check should not be relied upon
+    /// when an attacker can mount filesystems or create symbolic links concurrently.
+    #[must_use]
+    pub fn same_device(mut self, enabled: bool) -> Self {
+        self.same_device = enabled;
+        self
+    }
+
+    /// The base directory
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The policy paths are checked against
+    #[must_use]
+    pub fn policy(&self) -> &Policy {
+        &self.policy
+    }
+
+    /// Returns `self.root().safe_join_with(self.policy(), path)`, unless the check fails and the
+    /// policy's [`Action`] is [`Action::Divert`], in which case the diverted path is returned.
+    /// ```
+    /// # use safe_path::{fs::SafeDir, Action, Policy};
+    /// # use std::path::Path;
+    /// let policy = Policy::new().on_reject(Action::Divert("decoy".into()));
+    /// let dir = SafeDir::with_policy("www", policy);
+    /// assert_eq!(dir.join("index.html").unwrap(), Path::new("www/index.html"));
+    /// assert_eq!(dir.join("../etc/passwd").unwrap(), Path::new("decoy/.._etc_passwd"));
+    /// ```
+    /// # Errors
+    /// Returns an error if the check fails and the policy's [`Action`] is [`Action::Reject`]. See
+    /// [`SafePath::safe_join_with`].
+    pub fn join<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf> {
+        let path = path.as_ref();
+        self.root
+            .safe_join_with(&self.policy, path)
+            .and_then(|joined| {
+                if self.same_device {
+                    check_same_device(&self.root, path)?;
+                }
+                Ok(joined)
+            })
+            .or_else(|error| {
+                if let Some(hook) = &self.hook {
+                    hook(path, &error);
+                }
+                match self.policy.rejection_action() {
+                    Action::Divert(decoy) => Ok(decoy.join(sanitize(&path.to_string_lossy()))),
+                    Action::Reject => Err(error),
+                }
+            })
+    }
+
+    /// Moves `src` (relative to `self`) to `dst` (relative to `dst_dir`). Both paths are checked
+    /// before either is used. If the two locations are on different filesystems, the file is
+    /// copied, the copy is synced to disk, and then the original is removed. In dry-run mode, the
+    /// move is recorded instead.
+    /// # Errors
+    /// Returns an error if either check fails, or if an operating-system-level operation fails.
+    /// In the latter case, the error payload is an [`Error`] whose source is the operating
+    /// system's error.
+    pub fn move_file<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        src: P,
+        dst_dir: &SafeDir,
+        dst: Q,
+    ) -> Result<()> {
+        let src = self.join(src)?;
+        let dst = dst_dir.join(dst)?;
+        self.perform(Operation::Move { from: src, to: dst })
+    }
+
+    /// Copies `src` (relative to `self`) to `dst` (relative to `dst_dir`), as [`std::fs::copy`]
+    /// does. Both paths are checked before either is used. In dry-run mode, the copy is recorded
+    /// instead.
+    /// # Errors
+    /// Returns an error if either check fails, or if the copy fails. In the latter case, the error
+    /// payload is an [`Error`] whose source is the operating system's error.
+    pub fn copy_file<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        src: P,
+        dst_dir: &SafeDir,
+        dst: Q,
+    ) -> Result<()> {
+        let src = self.join(src)?;
+        let dst = dst_dir.join(dst)?;
+        self.perform(Operation::Copy { from: src, to: dst })
+    }
+
+    /// Moves each of `entries` from `self` to the same relative location in `dst_dir`. Each entry
+    /// is checked against both directories' policies independently, so that nothing `self`
+    /// guarantees is assumed to hold in `dst_dir`. One [`Transfer`] is returned per entry, in
+    /// order. In dry-run mode, the moves are recorded instead.
+    pub fn transfer<I>(&self, entries: I, dst_dir: &SafeDir) -> Vec<Transfer>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<Path>,
+    {
+        entries
+            .into_iter()
+            .map(|entry| {
+                let entry = entry.as_ref();
+                let result = self.join(entry).and_then(|src| {
+                    let dst = dst_dir.join(entry)?;
+                    self.perform(Operation::Move {
+                        from: src,
+                        to: dst.clone(),
+                    })?;
+                    Ok(dst)
+                });
+                Transfer {
+                    entry: entry.to_path_buf(),
+                    result,
+                }
+            })
+            .collect()
+    }
+
+    /// Lists the contents of `self.root()` recursively, without following symbolic links. Entries
+    /// whose relative paths fail `self.policy()`'s checks are omitted, as are their contents.
+    /// # Errors
+    /// Returns an error if an operating-system-level operation fails. The error payload is an
+    /// [`Error`] whose source is the operating system's error.
+    pub fn snapshot(&self) -> Result<Snapshot> {
+        self.snapshot_impl(false)
+    }
+
+    /// Like [`SafeDir::snapshot`], but also records a hash of each regular file's contents. The
+    /// hash (64-bit FNV-1a) detects accidental changes, not deliberate collisions.
+    /// # Errors
+    /// Returns an error if an operating-system-level operation fails. The error payload is an
+    /// [`Error`] whose source is the operating system's error.
+    pub fn snapshot_with_hashes(&self) -> Result<Snapshot> {
+        self.snapshot_impl(true)
+    }
+
+    /// Returns `Ok(())` if a new snapshot of `self` (with hashes, if `snapshot` has them) equals
+    /// `snapshot`.
+    /// ```
+    /// # use safe_path::fs::SafeDir;
+    /// # let tempdir = tempfile::tempdir().unwrap();
+    /// # std::fs::write(tempdir.path().join("x"), "x").unwrap();
+    /// let dir = SafeDir::new(tempdir.path());
+    /// let snapshot = dir.snapshot_with_hashes().unwrap();
+    /// assert!(dir.verify_unchanged(&snapshot).is_ok());
+    /// std::fs::write(dir.join("x").unwrap(), "y").unwrap();
+    /// assert!(dir.verify_unchanged(&snapshot).is_err());
+    /// ```
+    /// # Errors
+    /// Returns an error if the snapshots differ; the error payload's path is the first entry that
+    /// differs. Also returns an error if an operating-system-level operation fails.
+    pub fn verify_unchanged(&self, snapshot: &Snapshot) -> Result<()> {
+        let current = self.snapshot_impl(snapshot.hashed)?;
+        let (previous, current) = (&snapshot.entries, &current.entries);
+        let differing = previous
+            .iter()
+            .zip(current)
+            .find(|(x, y)| x != y)
+            .map(|(x, y)| x.path.as_path().min(y.path.as_path()))
+            .or_else(|| {
+                previous
+                    .get(current.len())
+                    .or_else(|| current.get(previous.len()))
+                    .map(|entry| entry.path.as_path())
+            });
+        match differing {
+            None => Ok(()),
+            Some(path) => Err(Error::new("directory changed")
+                .with_path(self.root.join(path))
+                .into()),
+        }
+    }
+
+    fn snapshot_impl(&self, hashed: bool) -> Result<Snapshot> {
+        let mut entries = Vec::new();
+        let mut dirs = vec![PathBuf::new()];
+        while let Some(dir) = dirs.pop() {
+            let path = self.root.join(&dir);
+            let read_dir = fs::read_dir(&path)
+                .map_err(|error| Error::wrap_os_error("read_dir failed", &path, error))?;
+            for entry in read_dir {
+                let entry =
+                    entry.map_err(|error| Error::wrap_os_error("read_dir failed", &path, error))?;
+                let relative = dir.join(entry.file_name());
+                let path = match self.root.safe_join_with(&self.policy, &relative) {
+                    Ok(path) => path,
+                    Err(_) => continue,
+                };
+                let metadata = fs::symlink_metadata(&path)
+                    .map_err(|error| Error::wrap_os_error("metadata failed", &path, error))?;
+                let file_type = metadata.file_type();
+                let hash = if hashed && file_type.is_file() {
+                    Some(hash_file(&path)?)
+                } else {
+                    None
+                };
+                if file_type.is_dir() && !is_link(&metadata) {
+                    dirs.push(relative.clone());
+                }
+                entries.push(SnapshotEntry {
+                    path: relative,
+                    file_type,
+                    len: metadata.len(),
+                    modified: metadata.modified().ok(),
+                    hash,
+                });
+            }
+        }
+        entries.sort_by(|x, y| x.path.cmp(&y.path));
+        Ok(Snapshot {
+            root: self.root.clone(),
+            entries,
+            hashed,
+        })
+    }
+
+    /// Returns the location of the object with content hash `hash` under `layout`, e.g.,
+    /// `root/ab/cd/abcdef…`.
+    /// # Errors
+    /// Returns an error if `hash` is invalid (see [`FanOut::relative_path`]), or if the result
+    /// fails `self.policy()`'s checks.
+    pub fn fan_out_path(&self, layout: &FanOut, hash: &str) -> Result<PathBuf> {
+        self.join(layout.relative_path(hash)?)
+    }
+
+    /// Like [`SafeDir::fan_out_path`], but also creates the intermediate directories. In dry-run
+    /// mode, their creation is recorded instead.
+    /// ```
+    /// # use safe_path::fs::{FanOut, SafeDir};
+    /// # let tempdir = tempfile::tempdir().unwrap();
+    /// let store = SafeDir::new(tempdir.path());
+    /// let path = store.create_fan_out_path(&FanOut::default(), "abcdef0123").unwrap();
+    /// assert_eq!(path, tempdir.path().join("ab/cd/abcdef0123"));
+    /// assert!(tempdir.path().join("ab/cd").is_dir());
+    /// ```
+    /// # Errors
+    /// Returns an error if [`SafeDir::fan_out_path`] would, or if an operating-system-level
+    /// operation fails.
+    pub fn create_fan_out_path(&self, layout: &FanOut, hash: &str) -> Result<PathBuf> {
+        let path = self.fan_out_path(layout, hash)?;
+        if let Some(parent) = path.parent() {
+            self.perform(Operation::CreateDirAll {
+                path: parent.to_path_buf(),
+            })?;
+        }
+        Ok(path)
+    }
+
+    /// Opens `path` for reading, as [`File::open`] does. With the `openat2` feature on Linux, the
+    /// file is opened relative to `self.root()` with [`open_beneath`].
+    /// ```
+    /// # use safe_path::fs::SafeDir;
+    /// # use std::io::Read;
+    /// # let tempdir = tempfile::tempdir().unwrap();
+    /// let dir = SafeDir::new(tempdir.path());
+    /// dir.write("index.html", "<html></html>").unwrap();
+    /// let mut contents = String::new();
+    /// dir.open("index.html").unwrap().read_to_string(&mut contents).unwrap();
+    /// assert_eq!(contents, "<html></html>");
+    /// assert!(dir.open("../index.html").is_err());
+    /// ```
+    /// # Errors
+    /// Returns an error if the check fails, or if the file cannot be opened. In the latter case,
+    /// the error payload is an [`Error`] whose source is the operating system's error.
+    pub fn open<P: AsRef<Path>>(&self, path: P) -> Result<File> {
+        let path = self.join(path)?;
+        #[cfg(all(feature = "openat2", target_os = "linux"))]
+        if let Some((dir, relative)) = self.beneath(&path)? {
+            return open_beneath(&dir, relative);
+        }
+        File::open(&path).map_err(|error| Error::wrap_os_error("open failed", &path, error))
+    }
+
+    /// Opens `path` for writing, as [`File::create`] does. With the `openat2` feature on Linux, the
+    /// file is opened relative to `self.root()` with [`create_beneath`]. In dry-run mode, the
+    /// creation is recorded as an [`Operation::Write`], and an error is returned because there is
+    /// no file to return.
+    /// # Errors
+    /// Returns an error if the check fails, if `self` is in dry-run mode, or if the file cannot be
+    /// created. In the last case, the error payload is an [`Error`] whose source is the operating
+    /// system's error.
+    pub fn create<P: AsRef<Path>>(&self, path: P) -> Result<File> {
+        let path = self.join(path)?;
+        let path = match self.record(Operation::Write { path }) {
+            Some(Operation::Write { path }) => path,
+            _ => return Err(Error::new("dry run").into()),
+        };
+        #[cfg(all(feature = "openat2", target_os = "linux"))]
+        if let Some((dir, relative)) = self.beneath(&path)? {
+            return create_beneath(&dir, relative);
+        }
+        File::create(&path).map_err(|error| Error::wrap_os_error("create failed", &path, error))
+    }
+
+    /// Reads the contents of `path`, as [`std::fs::read`] does.
+    /// # Errors
+    /// Returns an error if the check fails, or if the file cannot be read. In the latter case,
+    /// the error payload is an [`Error`] whose source is the operating system's error.
+    pub fn read<P: AsRef<Path>>(&self, path: P) -> Result<Vec<u8>> {
+        let path = path.as_ref();
+        let mut contents = Vec::new();
+        io::Read::read_to_end(&mut self.open(path)?, &mut contents)
+            .map_err(|error| Error::wrap_os_error("read failed", self.root.join(path), error))?;
+        Ok(contents)
+    }
+
+    /// Replaces the contents of `path` with `contents`, as [`std::fs::write`] does. In dry-run
+    /// mode, the write is recorded instead.
+    /// # Errors
+    /// Returns an error if the check fails, or if the file cannot be written. In the latter case,
+    /// the error payload is an [`Error`] whose source is the operating system's error.
+    pub fn write<P: AsRef<Path>, C: AsRef<[u8]>>(&self, path: P, contents: C) -> Result<()> {
+        let path = path.as_ref();
+        if self.is_dry_run() {
+            let path = self.join(path)?;
+            self.record(Operation::Write { path });
+            return Ok(());
+        }
+        io::Write::write_all(&mut self.create(path)?, contents.as_ref())
+            .map_err(|error| Error::wrap_os_error("write failed", self.root.join(path), error))
+    }
+
+    /// Removes the file `path`, as [`std::fs::remove_file`] does. In dry-run mode, the removal is
+    /// recorded instead.
+    /// # Errors
+    /// Returns an error if the check fails, or if the file cannot be removed. In the latter case,
+    /// the error payload is an [`Error`] whose source is the operating system's error.
+    pub fn remove_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = self.join(path)?;
+        self.perform(Operation::RemoveFile { path })
+    }
+
+    /// Removes the directory `path` and its contents, as [`std::fs::remove_dir_all`] does, or the
+    /// file `path` if it is not a directory. `path` must not refer to `self.root()` itself, and no
+    /// symbolic link in `path` may resolve outside of `self.root()` (see [`safe_join_fs`]).
+    /// Symbolic links (and, on Windows, junctions and other reparse points) encountered while
+    /// removing the contents are removed, not followed. In dry-run mode, the removal is recorded
+    /// instead.
+    ///
+    /// The filesystem may change while the contents are removed, so this function should not be
+    /// relied upon when an attacker can replace directories with symbolic links concurrently.
+    /// # Errors
+    /// Returns an error if a check fails, or if an operating-system-level operation fails. In the
+    /// latter case, the error payload is an [`Error`] whose source is the operating system's error.
+    pub fn remove_dir_all<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = self.join(path)?;
+        if lexically_equal(&path, &self.root) {
+            return Err(Error::new("unsafe remove operation")
+                .with_violation(Violation::SelfJoin)
+                .with_path(path)
+                .into());
+        }
+        if let Ok(relative) = path.strip_prefix(&self.root) {
+            safe_join_fs(&self.root, relative)?;
+        }
+        self.perform(Operation::RemoveDirAll { path })
+    }
+
+    /// Creates a symbolic link at `link` pointing to `target`. `target` is interpreted relative to
+    /// the directory containing `link`, as the operating system would interpret it, and the result
+    /// is checked against `self.policy()` like any other path. Hence, absolute targets and targets
+    /// that lead outside of `self.root()` are rejected. In dry-run mode, the creation is recorded
+    /// instead.
+    /// ```
+    /// # use safe_path::fs::SafeDir;
+    /// # let tempdir = tempfile::tempdir().unwrap();
+    /// let dir = SafeDir::new(tempdir.path());
+    /// dir.create_dir_all("x").unwrap();
+    /// dir.symlink("../y", "x/link").unwrap();
+    /// assert!(dir.symlink("../../y", "x/escape").is_err());
+    /// assert!(dir.symlink("/etc/passwd", "x/escape").is_err());
+    /// ```
+    /// # Errors
+    /// Returns an error if a check fails, or if the link cannot be created. In the latter case, the
+    /// error payload is an [`Error`] whose source is the operating system's error.
+    pub fn symlink<P: AsRef<Path>, Q: AsRef<Path>>(&self, target: P, link: Q) -> Result<()> {
+        self.symlink_impl(target.as_ref(), link.as_ref(), false)
+    }
+
+    /// Like [`SafeDir::symlink`], but also checks `link` and the resolved target with
+    /// [`safe_join_fs`], so that neither passes through an existing symbolic link that leads
+    /// outside of `self.root()`.
+    /// # Errors
+    /// Returns an error if a check fails, or if an operating-system-level operation fails. In the
+    /// latter case, the error payload is an [`Error`] whose source is the operating system's error.
+    pub fn symlink_fs<P: AsRef<Path>, Q: AsRef<Path>>(&self, target: P, link: Q) -> Result<()> {
+        self.symlink_impl(target.as_ref(), link.as_ref(), true)
+    }
+
+    fn symlink_impl(&self, target: &Path, link: &Path, fs: bool) -> Result<()> {
+        let joined = self.join(link)?;
+        // smoelius: If `link` was diverted, its target is checked as though `link` were at the top
+        // level.
+        let parent = joined
+            .strip_prefix(&self.root)
+            .ok()
+            .and_then(Path::parent)
+            .unwrap_or_else(|| Path::new(""));
+        let resolved = parent.join(target);
+        self.root.safe_join_with(&self.policy, &resolved)?;
+        if fs {
+            if let Ok(relative) = joined.strip_prefix(&self.root) {
+                safe_join_fs(&self.root, relative)?;
+            }
+            safe_join_fs(&self.root, &resolved)?;
+        }
+        self.perform(Operation::Symlink {
+            target: target.to_path_buf(),
+            link: joined,
+        })
+    }
+
+    /// Creates the directory `path` and any missing ancestors, as [`std::fs::create_dir_all`] does.
+    /// In dry-run mode, the creation is recorded instead.
+    /// # Errors
+    /// Returns an error if the check fails, or if a directory cannot be created. In the latter
+    /// case, the error payload is an [`Error`] whose source is the operating system's error.
+    pub fn create_dir_all<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = self.join(path)?;
+        self.perform(Operation::CreateDirAll { path })
+    }
+
+    /// Returns an iterator over the entries of the directory `path`, as [`std::fs::read_dir`] does.
+    /// The entries' paths include `self.root()`.
+    /// # Errors
+    /// Returns an error if the check fails, or if the directory cannot be read. In the latter
+    /// case, the error payload is an [`Error`] whose source is the operating system's error.
+    pub fn read_dir<P: AsRef<Path>>(&self, path: P) -> Result<fs::ReadDir> {
+        let path = self.join(path)?;
+        fs::read_dir(&path).map_err(|error| Error::wrap_os_error("read_dir failed", &path, error))
+    }
+
+    /// Begins a [`SafeDirTransaction`], creating its staging directory inside `self.root()`.
+    /// # Errors
+    /// Returns an error if the staging directory cannot be created. The error payload is an
+    /// [`Error`] whose source is the operating system's error.
+    pub fn transaction(&self) -> Result<SafeDirTransaction<'_>> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        loop {
+            let staging = self.root.join(format!(
+                ".safe_path-transaction-{}-{}",
+                std::process::id(),
+                COUNTER.fetch_add(1, Ordering::Relaxed)
+            ));
+            match fs::create_dir(&staging) {
+                Ok(()) => {
+                    return Ok(SafeDirTransaction {
+                        dir: self,
+                        staging,
+                        steps: Vec::new(),
+                    })
+                }
+                Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(error) => {
+                    return Err(Error::wrap_os_error("create_dir failed", &staging, error))
+                }
+            }
+        }
+    }
+
+    fn perform(&self, operation: Operation) -> Result<()> {
+        let operation = match self.record(operation) {
+            Some(operation) => operation,
+            None => return Ok(()),
+        };
+        match operation {
+            Operation::Move { from, to } => move_file(&from, &to),
+            Operation::Copy { from, to } => fs::copy(&from, &to)
+                .map(|_| ())
+                .map_err(|error| Error::wrap_os_error("copy failed", &from, error)),
+            Operation::CreateDirAll { path } => fs::create_dir_all(&path)
+                .map_err(|error| Error::wrap_os_error("create_dir_all failed", &path, error)),
+            Operation::RemoveFile { path } => fs::remove_file(&path)
+                .map_err(|error| Error::wrap_os_error("remove failed", &path, error)),
+            Operation::RemoveDirAll { path } => remove_dir_all(&path),
+            Operation::Symlink { target, link } => symlink(&target, &link)
+                .map_err(|error| Error::wrap_os_error("symlink failed", &link, error)),
+            // smoelius: Transactions apply their own steps, and only record them in dry-run mode.
+            Operation::CreateDir { .. } | Operation::Write { .. } => unreachable!(),
+        }
+    }
+
+    /// If `path` (a result of [`SafeDir::join`]) is inside `self.root()`, e.g., because it was not
+    /// diverted, returns the opened root and `path` relative to it.
+    #[cfg(all(feature = "openat2", target_os = "linux"))]
+    fn beneath<'a>(&self, path: &'a Path) -> Result<Option<(File, &'a Path)>> {
+        let relative = match path.strip_prefix(&self.root) {
+            Ok(relative) => relative,
+            Err(_) => return Ok(None),
+        };
+        let dir = File::open(&self.root)
+            .map_err(|error| Error::wrap_os_error("open failed", &self.root, error))?;
+        Ok(Some((dir, relative)))
+    }
+
+    /// Records `operation` and returns `None` if `self` is in dry-run mode. Otherwise, returns
+    /// `operation`.
+    pub(crate) fn record(&self, operation: Operation) -> Option<Operation> {
+        match &self.plan {
+            Some(plan) => {
+                plan.lock()
+                    .unwrap_or_else(std::sync::PoisonError::into_inner)
+                    .push(operation);
+                None
+            }
+            None => Some(operation),
+        }
+    }
+}
+
+/// Interoperation with [`cap_std`] (requires the `cap-std` feature)
+///
+/// A `SafeDir` checks paths lexically; a [`cap_std::fs::Dir`] confines operations on them at run
+/// time. Using both checks each path against the policy and then resolves it relative to the
+/// opened directory:
+/// ```
+/// # use safe_path::fs::SafeDir;
+/// # let tempdir = tempfile::tempdir().unwrap();
+/// let dir = SafeDir::new(tempdir.path());
+/// let cap_dir = dir.open_cap_dir().unwrap();
+/// cap_dir.write(dir.relative("index.html").unwrap(), "<html></html>").unwrap();
+/// assert!(dir.relative("../index.html").is_err());
+/// ```
+#[cfg(feature = "cap-std")]
+impl SafeDir {
+    /// Opens `self.root()` as a [`cap_std::fs::Dir`], using ambient authority.
+    /// # Errors
+    /// Returns an error if the directory cannot be opened. The error payload is an [`Error`] whose
+    /// source is the operating system's error.
+    pub fn open_cap_dir(&self) -> Result<cap_std::fs::Dir> {
+        cap_std::fs::Dir::open_ambient_dir(&self.root, cap_std::ambient_authority())
+            .map_err(|error| Error::wrap_os_error("open failed", &self.root, error))
+    }
+
+    /// Checks `path` as [`SafeDir::join`] does, and returns it unchanged for use with a
+    /// [`cap_std::fs::Dir`] opened on `self.root()`. The policy's [`Action`] is ignored, i.e., a
+    /// failed check is always an error.
+    /// # Errors
+    /// Returns an error if the check fails. See [`SafePath::safe_join_with`].
+    pub fn relative<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf> {
+        let path = path.as_ref();
+        match self.root.safe_join_with(&self.policy, path) {
+            Ok(_) => Ok(path.to_path_buf()),
+            Err(error) => {
+                if let Some(hook) = &self.hook {
+                    hook(path, &error);
+                }
+                Err(error)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "cap-std")]
+impl std::convert::TryFrom<&SafeDir> for cap_std::fs::Dir {
+    type Error = io::Error;
+
+    fn try_from(dir: &SafeDir) -> Result<Self> {
+        dir.open_cap_dir()
+    }
+}
+
+/// A batch of operations inside a [`SafeDir`] that are checked and staged as they are added, and
+/// applied together by [`SafeDirTransaction::commit`]
+///
+/// Written contents are staged in a hidden directory inside the root, so that committing a write
+/// is a rename. If an operation fails during the commit, the operations already applied are
+/// undone in reverse order, and files they replaced are restored. The commit is therefore atomic
+/// with respect to failures of individual operations, but not with respect to crashes or
+/// concurrent observers. Dropping an uncommitted transaction discards it.
+/// ```
+/// # use safe_path::fs::SafeDir;
+/// # let tempdir = tempfile::tempdir().unwrap();
+/// let dir = SafeDir::new(tempdir.path());
+/// let mut transaction = dir.transaction().unwrap();
+/// transaction.create_dir("bin").unwrap();
+/// transaction.write("bin/tool", b"#!/bin/sh\n").unwrap();
+/// assert!(transaction.write("../tool", b"").is_err());
+/// transaction.commit().unwrap();
+/// assert!(tempdir.path().join("bin/tool").exists());
+/// ```
+#[derive(Debug)]
+pub struct SafeDirTransaction<'a> {
+    dir: &'a SafeDir,
+    staging: PathBuf,
+    steps: Vec<Step>,
+}
+
+#[derive(Debug)]
+enum Step {
+    CreateDir(PathBuf),
+    Write { staged: PathBuf, path: PathBuf },
+    Rename { from: PathBuf, to: PathBuf },
+}
+
+#[derive(Debug)]
+enum Undo {
+    RemoveDir(PathBuf),
+    Restore {
+        path: PathBuf,
+        from: Option<PathBuf>,
+        backup: Option<PathBuf>,
+    },
+}
+
+impl<'a> SafeDirTransaction<'a> {
+    /// The directory the transaction operates on
+    #[must_use]
+    pub fn dir(&self) -> &'a SafeDir {
+        self.dir
+    }
+
+    /// Adds the creation of directory `path`, whose parent must exist when the step is applied.
+    /// # Errors
+    /// Returns an error if `path` fails the directory's checks.
+    pub fn create_dir<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let path = self.dir.join(path)?;
+        self.steps.push(Step::CreateDir(path));
+        Ok(())
+    }
+
+    /// Adds a write of `contents` to `path`, replacing any existing file. The contents are written
+    /// to the staging directory immediately.
+    /// # Errors
+    /// Returns an error if `path` fails the directory's checks, or if the contents cannot be
+    /// staged.
+    pub fn write<P: AsRef<Path>, C: AsRef<[u8]>>(&mut self, path: P, contents: C) -> Result<()> {
+        let path = self.dir.join(path)?;
+        let staged = self.staging.join(self.steps.len().to_string());
+        File::create(&staged)
+            .and_then(|mut file| {
+                io::Write::write_all(&mut file, contents.as_ref())?;
+                file.sync_all()
+            })
+            .map_err(|error| Error::wrap_os_error("write failed", &staged, error))?;
+        self.steps.push(Step::Write { staged, path });
+        Ok(())
+    }
+
+    /// Adds a rename of `from` to `to`, replacing any existing file at `to`.
+    /// # Errors
+    /// Returns an error if either path fails the directory's checks.
+    pub fn rename<P: AsRef<Path>, Q: AsRef<Path>>(&mut self, from: P, to: Q) -> Result<()> {
+        let from = self.dir.join(from)?;
+        let to = self.dir.join(to)?;
+        self.steps.push(Step::Rename { from, to });
+        Ok(())
+    }
+
+    /// Applies the staged operations in the order they were added. If the directory is in dry-run
+    /// mode, the operations are recorded instead (see [`SafeDir::dry_run`]), though written
+    /// contents are still staged until the transaction is dropped.
+    /// # Errors
+    /// Returns the error of the first operation that fails, after undoing the operations that
+    /// were applied. The error payload is an [`Error`] whose source is the operating system's
+    /// error.
+    pub fn commit(mut self) -> Result<()> {
+        let steps = std::mem::take(&mut self.steps);
+        if self.dir.is_dry_run() {
+            for step in steps {
+                self.dir.record(match step {
+                    Step::CreateDir(path) => Operation::CreateDir { path },
+                    Step::Write { path, .. } => Operation::Write { path },
+                    Step::Rename { from, to } => Operation::Move { from, to },
+                });
+            }
+            return Ok(());
+        }
+        let mut undos = Vec::new();
+        for (index, step) in steps.into_iter().enumerate() {
+            if let Err(error) = self.apply(index, step, &mut undos) {
+                for undo in undos.into_iter().rev() {
+                    undo.apply();
+                }
+                return Err(error);
+            }
+        }
+        Ok(())
+    }
+
+    /// Discards the staged operations. Equivalent to dropping `self`.
+    pub fn rollback(self) {}
+
+    fn apply(&self, index: usize, step: Step, undos: &mut Vec<Undo>) -> Result<()> {
+        match step {
+            Step::CreateDir(path) => {
+                create_dir(&path)?;
+                undos.push(Undo::RemoveDir(path));
+            }
+            Step::Write { staged, path } => {
+                let backup = self.back_up(index, &path)?;
+                if let Err(error) = rename(&staged, &path) {
+                    restore_backup(backup.as_deref(), &path);
+                    return Err(error);
+                }
+                undos.push(Undo::Restore {
+                    path,
+                    from: None,
+                    backup,
+                });
+            }
+            Step::Rename { from, to } => {
+                let backup = self.back_up(index, &to)?;
+                if let Err(error) = rename(&from, &to) {
+                    restore_backup(backup.as_deref(), &to);
+                    return Err(error);
+                }
+                undos.push(Undo::Restore {
+                    path: to,
+                    from: Some(from),
+                    backup,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn back_up(&self, index: usize, path: &Path) -> Result<Option<PathBuf>> {
+        if fs::symlink_metadata(path).is_err() {
+            return Ok(None);
+        }
+        let backup = self.staging.join(format!("{}.backup", index));
+        rename(path, &backup)?;
+        Ok(Some(backup))
+    }
+}
+
+impl Drop for SafeDirTransaction<'_> {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.staging);
+    }
+}
+
+impl Undo {
+    // smoelius: Undoing is best effort. The error that caused the rollback is the one reported.
+    fn apply(self) {
+        match self {
+            Self::RemoveDir(path) => {
+                let _ = fs::remove_dir(path);
+            }
+            Self::Restore { path, from, backup } => {
+                let _ = match from {
+                    Some(from) => fs::rename(&path, from),
+                    None => fs::remove_file(&path),
+                };
+                restore_backup(backup.as_deref(), &path);
+            }
+        }
+    }
+}
+
+fn restore_backup(backup: Option<&Path>, path: &Path) {
+    if let Some(backup) = backup {
+        let _ = fs::rename(backup, path);
+    }
+}
+
+fn create_dir(path: &Path) -> Result<()> {
+    fs::create_dir(path).map_err(|error| Error::wrap_os_error("create_dir failed", path, error))
+}
+
+fn rename(from: &Path, to: &Path) -> Result<()> {
+    fs::rename(from, to).map_err(|error| Error::wrap_os_error("rename failed", from, error))
+}
+
+/// A filesystem modification planned by a [`SafeDir`] in dry-run mode (see [`SafeDir::dry_run`]).
+/// Paths have been checked and include the base directory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Operation {
+    /// Move the file `from` to `to`
+    Move { from: PathBuf, to: PathBuf },
+    /// Copy the file `from` to `to`
+    Copy { from: PathBuf, to: PathBuf },
+    /// Create the directory `path` and any missing ancestors
+    CreateDirAll { path: PathBuf },
+    /// Create the directory `path`, whose parent exists
+    CreateDir { path: PathBuf },
+    /// Replace the contents of the file `path`, e.g., with contents staged by a
+    /// [`SafeDirTransaction`]
+    Write { path: PathBuf },
+    /// Remove the file `path`
+    RemoveFile { path: PathBuf },
+    /// Remove the directory `path` and its contents, without following symbolic links
+    RemoveDirAll { path: PathBuf },
+    /// Create a symbolic link at `link` pointing to `target`, which is relative to the directory
+    /// containing `link` (and so does not include the base directory)
+    Symlink { target: PathBuf, link: PathBuf },
+}
+
+/// Joins `path` onto the current working directory, i.e., returns
+/// `SafeDir::current()?.join(path)`. See [`SafeDir::current`] for caveats.
+/// ```
+/// # use safe_path::fs::safe_join_cwd;
+/// let path = safe_join_cwd("Cargo.toml").unwrap();
+/// assert_eq!(path, std::env::current_dir().unwrap().join("Cargo.toml"));
+/// assert!(safe_join_cwd("../x").is_err());
+/// ```
+/// # Errors
+/// Returns an error if the working directory cannot be determined, or if the check fails. See
+/// [`SafePath::safe_join_with`].
+pub fn safe_join_cwd<P: AsRef<Path>>(path: P) -> Result<PathBuf> {
+    SafeDir::current()?.join(path)
+}
+
+/// Reads the contents of `path` relative to `base`, i.e., returns
+/// `SafeDir::new(base).read(path)`.
+/// ```
+/// # use safe_path::fs::{safe_read, safe_write};
+/// # let tempdir = tempfile::tempdir().unwrap();
+/// safe_write(tempdir.path(), "x", "contents").unwrap();
+/// assert_eq!(safe_read(tempdir.path(), "x").unwrap(), b"contents");
+/// assert!(safe_read(tempdir.path(), "../x").is_err());
+/// ```
+/// # Errors
+/// Returns an error if the check fails, or if the file cannot be read. See [`SafeDir::read`].
+pub fn safe_read<P: AsRef<Path>, Q: AsRef<Path>>(base: P, path: Q) -> Result<Vec<u8>> {
+    SafeDir::new(base).read(path)
+}
+
+/// Replaces the contents of `path` relative to `base`, i.e., returns
+/// `SafeDir::new(base).write(path, contents)`.
+/// # Errors
+/// Returns an error if the check fails, or if the file cannot be written. See [`SafeDir::write`].
+pub fn safe_write<P: AsRef<Path>, Q: AsRef<Path>, C: AsRef<[u8]>>(
+    base: P,
+    path: Q,
+    contents: C,
+) -> Result<()> {
+    SafeDir::new(base).write(path, contents)
+}
+
+/// Creates the directory `path` relative to `base` and any missing ancestors, i.e., returns
+/// `SafeDir::new(base).create_dir_all(path)`.
+/// # Errors
+/// Returns an error if the check fails, or if a directory cannot be created. See
+/// [`SafeDir::create_dir_all`].
+pub fn safe_create_dir_all<P: AsRef<Path>, Q: AsRef<Path>>(base: P, path: Q) -> Result<()> {
+    SafeDir::new(base).create_dir_all(path)
+}
+
+/// Removes the file `path` relative to `base`, i.e., returns
+/// `SafeDir::new(base).remove_file(path)`.
+/// # Errors
+/// Returns an error if the check fails, or if the file cannot be removed. See
+/// [`SafeDir::remove_file`].
+pub fn safe_remove_file<P: AsRef<Path>, Q: AsRef<Path>>(base: P, path: Q) -> Result<()> {
+    SafeDir::new(base).remove_file(path)
+}
+
+/// Removes the directory `path` relative to `base` and its contents, i.e., returns
+/// `SafeDir::new(base).remove_dir_all(path)`.
+/// ```
+/// # use safe_path::fs::{safe_create_dir_all, safe_remove_dir_all};
+/// # let tempdir = tempfile::tempdir().unwrap();
+/// safe_create_dir_all(tempdir.path(), "cache/x").unwrap();
+/// assert!(safe_remove_dir_all(tempdir.path(), ".").is_err());
+/// assert!(safe_remove_dir_all(tempdir.path(), "cache/..").is_err());
+/// safe_remove_dir_all(tempdir.path(), "cache").unwrap();
+/// assert!(!tempdir.path().join("cache").exists());
+/// ```
+/// # Errors
+/// Returns an error if a check fails, or if the directory cannot be removed. See
+/// [`SafeDir::remove_dir_all`].
+pub fn safe_remove_dir_all<P: AsRef<Path>, Q: AsRef<Path>>(base: P, path: Q) -> Result<()> {
+    SafeDir::new(base).remove_dir_all(path)
+}
+
+/// Creates a symbolic link at `link` relative to `base` pointing to `target`, i.e., returns
+/// `SafeDir::new(base).symlink(target, link)`.
+/// # Errors
+/// Returns an error if a check fails, or if the link cannot be created. See [`SafeDir::symlink`].
+pub fn safe_symlink<P: AsRef<Path>, Q: AsRef<Path>, R: AsRef<Path>>(
+    base: P,
+    target: Q,
+    link: R,
+) -> Result<()> {
+    SafeDir::new(base).symlink(target, link)
+}
+
+/// Moves `from` to `to`, both relative to `base`, i.e., returns
+/// `SafeDir::new(base).move_file(from, &SafeDir::new(base), to)`. Use [`SafeDir::move_file`] to
+/// move between two base directories.
+/// ```
+/// # use safe_path::fs::{safe_rename, safe_write};
+/// # let tempdir = tempfile::tempdir().unwrap();
+/// safe_write(tempdir.path(), "upload.tmp", "contents").unwrap();
+/// assert!(safe_rename(tempdir.path(), "upload.tmp", "../upload").is_err());
+/// safe_rename(tempdir.path(), "upload.tmp", "upload").unwrap();
+/// assert!(tempdir.path().join("upload").exists());
+/// ```
+/// # Errors
+/// Returns an error if either check fails, or if the move fails. See [`SafeDir::move_file`].
+pub fn safe_rename<P: AsRef<Path>, Q: AsRef<Path>, R: AsRef<Path>>(
+    base: P,
+    from: Q,
+    to: R,
+) -> Result<()> {
+    let dir = SafeDir::new(base);
+    dir.move_file(from, &dir, to)
+}
+
+/// Copies `from` to `to`, both relative to `base`, i.e., returns
+/// `SafeDir::new(base).copy_file(from, &SafeDir::new(base), to)`. Use [`SafeDir::copy_file`] to
+/// copy between two base directories.
+/// # Errors
+/// Returns an error if either check fails, or if the copy fails. See [`SafeDir::copy_file`].
+pub fn safe_copy<P: AsRef<Path>, Q: AsRef<Path>, R: AsRef<Path>>(
+    base: P,
+    from: Q,
+    to: R,
+) -> Result<()> {
+    let dir = SafeDir::new(base);
+    dir.copy_file(from, &dir, to)
+}
+
+/// Like [`SafePath::safe_join`], but also consults the filesystem: the existing part of
+/// `dir.join(path)` is walked one component at a time, and each symbolic link (and each `..`) is
+/// resolved with [`std::fs::canonicalize`]. The join is rejected if any of them resolves to a file
+/// outside of `dir`, or cannot be resolved (e.g., because it is a dangling symbolic link). On
+/// Windows, junctions, mount points, and other reparse points are treated like symbolic links.
+///
+/// The filesystem may change after this function returns, so the result should not be trusted
+/// when an attacker can create symbolic links concurrently.
+/// # Errors
+/// Returns an error if the lexical check fails, if a symbolic link resolves outside of `dir`, or
+/// if an operating-system-level operation fails. In the last case, the error payload is an
+/// [`Error`] whose source is the operating system's error.
+pub fn safe_join_fs<P: AsRef<Path>, Q: AsRef<Path>>(dir: P, path: Q) -> Result<PathBuf> {
+    let dir = dir.as_ref();
+    let path = path.as_ref();
+    let result = dir.safe_join(path)?;
+    let base = fs::canonicalize(dir)
+        .map_err(|error| Error::wrap_os_error("canonicalize failed", dir, error))?;
+    let mut current = dir.to_path_buf();
+    for (index, component) in path.components().enumerate() {
+        current.push(component);
+        let metadata = match fs::symlink_metadata(&current) {
+            Ok(metadata) => metadata,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => break,
+            Err(error) => return Err(Error::wrap_os_error("metadata failed", &current, error)),
+        };
+        if !is_link(&metadata) && component != Component::ParentDir {
+            continue;
+        }
+        match fs::canonicalize(&current) {
+            Ok(resolved) if resolved.starts_with(&base) => {}
+            _ => {
+                return Err(Error::new("unsafe join operation")
+                    .with_violation(Violation::EscapingSymlink { index })
+                    .with_path(current)
+                    .into())
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Returns the canonical form of `dir.join(path)`, as computed by [`std::fs::canonicalize`],
+/// provided that `dir.safe_join(path)` succeeds and the canonical form is within the canonical
+/// form of `dir`. Unlike [`safe_join_fs`], the file must exist.
+/// ```
+/// # use safe_path::fs::safe_canonicalize;
+/// let path = safe_canonicalize(".", "src/lib.rs").unwrap();
+/// assert_eq!(path, std::fs::canonicalize("src/lib.rs").unwrap());
+/// assert!(safe_canonicalize("src", "../Cargo.toml").is_err());
+/// ```
+/// # Errors
+/// Returns an error if the lexical check fails, if the canonical form is outside of `dir`, or if
+/// canonicalization fails. In the last case, the error payload is an [`Error`] whose source is the
+/// operating system's error.
+pub fn safe_canonicalize<P: AsRef<Path>, Q: AsRef<Path>>(dir: P, path: Q) -> Result<PathBuf> {
+    let dir = dir.as_ref();
+    let result = dir.safe_join(path)?;
+    let base = fs::canonicalize(dir)
+        .map_err(|error| Error::wrap_os_error("canonicalize failed", dir, error))?;
+    let canonical = fs::canonicalize(&result)
+        .map_err(|error| Error::wrap_os_error("canonicalize failed", &result, error))?;
+    if !canonical.starts_with(&base) {
+        return Err(Error::new("unsafe canonicalize operation")
+            .with_path(canonical)
+            .into());
+    }
+    Ok(canonical)
+}
+
+/// Returns true if the canonical form of `path` is within the canonical form of `base` (or equal
+/// to it), as computed by [`std::fs::canonicalize`]. Both must exist. This is the filesystem
+/// counterpart of [`crate::lexical::lexically_starts_with`], and can serve as a post-check on a
+/// path produced by [`SafePath::safe_join`]. Note that `path` is not joined onto `base`.
+/// ```
+/// # use safe_path::fs::is_contained_fs;
+/// assert!(is_contained_fs(".", "src/lib.rs").unwrap());
+/// assert!(!is_contained_fs("src", "src/../Cargo.toml").unwrap());
+/// ```
+/// # Errors
+/// Returns an error if either canonicalization fails. The error payload is an [`Error`] whose
+/// source is the operating system's error.
+pub fn is_contained_fs<P: AsRef<Path>, Q: AsRef<Path>>(base: P, path: Q) -> Result<bool> {
+    let (base, path) = (base.as_ref(), path.as_ref());
+    let base = fs::canonicalize(base)
+        .map_err(|error| Error::wrap_os_error("canonicalize failed", base, error))?;
+    let path = fs::canonicalize(path)
+        .map_err(|error| Error::wrap_os_error("canonicalize failed", path, error))?;
+    Ok(path.starts_with(base))
+}
+
+/// Inspects the base directory `dir` and its ancestors for weaknesses that would let another user
+/// compromise paths joined onto `dir`, however carefully they were checked. Returns one
+/// [`Finding`] per weakness; an empty vector means none were found.
+///
+/// Each ancestor of `dir` as given is checked for being a symbolic link (on Windows, any reparse
+/// point). Then, on Unix, `dir`'s canonical form and each of its ancestors are checked for being
+/// writable by other users. If `owner` is provided, `dir` must be owned by `owner`, and each
+/// ancestor must be owned by `owner` or by root. `owner` is ignored on other platforms.
+/// # Errors
+/// Returns an error if an operating-system-level operation fails. The error payload is an
+/// [`Error`] whose source is the operating system's error.
+#[cfg_attr(not(unix), allow(unused_variables))]
+pub fn audit_base<P: AsRef<Path>>(dir: P, owner: Option<u32>) -> Result<Vec<Finding>> {
+    let dir = dir.as_ref();
+    let mut findings = Vec::new();
+    let mut ancestors = dir.ancestors().collect::<Vec<_>>();
+    ancestors.reverse();
+    for path in ancestors {
+        if path.as_os_str().is_empty() {
+            continue;
+        }
+        let metadata = fs::symlink_metadata(path)
+            .map_err(|error| Error::wrap_os_error("metadata failed", path, error))?;
+        if is_link(&metadata) {
+            findings.push(Finding::Symlink {
+                path: path.to_path_buf(),
+            });
+        }
+    }
+    let canonical = fs::canonicalize(dir)
+        .map_err(|error| Error::wrap_os_error("canonicalize failed", dir, error))?;
+    if !canonical.is_dir() {
+        findings.push(Finding::NotADirectory { path: canonical });
+        return Ok(findings);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        const S_IWGRP: u32 = 0o020;
+        const S_IWOTH: u32 = 0o002;
+        const S_ISVTX: u32 = 0o1000;
+        let mut ancestors = canonical.ancestors().collect::<Vec<_>>();
+        ancestors.reverse();
+        for path in ancestors {
+            let metadata = fs::metadata(path)
+                .map_err(|error| Error::wrap_os_error("metadata failed", path, error))?;
+            let mode = metadata.mode();
+            let is_base = path == canonical;
+            // smoelius: The sticky bit prevents other users from renaming or removing an
+            // ancestor's entries, but not from creating files in the base directory itself.
+            if mode & (S_IWGRP | S_IWOTH) != 0 && (is_base || mode & S_ISVTX == 0) {
+                findings.push(Finding::Writable {
+                    path: path.to_path_buf(),
+                    mode,
+                });
+            }
+            if let Some(owner) = owner {
+                let uid = metadata.uid();
+                if uid != owner && (is_base || uid != 0) {
+                    findings.push(Finding::UnexpectedOwner {
+                        path: path.to_path_buf(),
+                        uid,
+                    });
+                }
+            }
+        }
+    }
+    Ok(findings)
+}
+
+/// Opens `path` for reading, relative to `dir`, provided that `dir.safe_join(path)` succeeds
+/// (requires the `safe-open` feature). The lexically normalized path is opened one component at a
+/// time, without following symbolic links (on Windows, without following any reparse point).
+/// Hence, the file opened is the one that was checked, even if an attacker can create symbolic
+/// links concurrently. `dir` itself may be a symbolic link.
+///
+/// On Unix, each directory is opened relative to its parent with `openat(2)` and
+/// `O_NOFOLLOW | O_DIRECTORY`. On Windows, each directory is opened with
+/// `FILE_FLAG_OPEN_REPARSE_POINT`, and held open without `FILE_SHARE_DELETE` so that it cannot be
+/// renamed or removed until the file is opened. With the `openat2` feature on Linux, the path is
+/// instead opened relative to `dir` in one call, as [`open_beneath`] does, but with
+/// `RESOLVE_NO_SYMLINKS` in place of `RESOLVE_NO_MAGICLINKS`.
+/// ```
+/// # use safe_path::fs::safe_open;
+/// # use std::io::Read;
+/// let mut contents = String::new();
+/// safe_open(".", "src/lib.rs").unwrap().read_to_string(&mut contents).unwrap();
+/// assert!(safe_open("src", "../Cargo.toml").is_err());
+/// ```
+/// # Errors
+/// Returns an error if the lexical check fails, if `path` passes through a symbolic link, or if an
+/// open fails. In the latter two cases, the error payload is an [`Error`] whose source is the
+/// operating system's error.
+#[cfg(all(feature = "safe-open", any(unix, windows)))]
+pub fn safe_open<P: AsRef<Path>, Q: AsRef<Path>>(dir: P, path: Q) -> Result<File> {
+    let dir = dir.as_ref();
+    let path = path.as_ref();
+    dir.safe_join(path)?;
+    let normalized = crate::lexical::normalize(path);
+    let names = normalized
+        .components()
+        .iter()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(Path::new(name)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let (last, parents) = match names.split_last() {
+        Some(split) => split,
+        None => return Err(Error::new("unsafe open operation").with_path(path).into()),
+    };
+    open_components(dir, parents, last)
+}
+
+#[cfg(all(feature = "safe-open", feature = "openat2", target_os = "linux"))]
+fn open_components(dir: &Path, parents: &[&Path], last: &Path) -> Result<File> {
+    use rustix::fs::{OFlags, ResolveFlags};
+    let dir = File::open(dir).map_err(|error| Error::wrap_os_error("open failed", dir, error))?;
+    let path = parents.iter().chain(&[last]).collect::<PathBuf>();
+    openat2_beneath(&dir, &path, OFlags::RDONLY, ResolveFlags::NO_SYMLINKS)
+}
+
+#[cfg(all(
+    feature = "safe-open",
+    unix,
+    not(all(feature = "openat2", target_os = "linux"))
+))]
+fn open_components(dir: &Path, parents: &[&Path], last: &Path) -> Result<File> {
+    use rustix::fs::{open, openat, Mode, OFlags};
+    let wrap = |path: &Path| {
+        let path = path.to_path_buf();
+        move |errno| Error::wrap_os_error("open failed", path, io::Error::from(errno))
+    };
+    let mut current = open(
+        dir,
+        OFlags::RDONLY | OFlags::DIRECTORY | OFlags::CLOEXEC,
+        Mode::empty(),
+    )
+    .map_err(wrap(dir))?;
+    let mut path = dir.to_path_buf();
+    for name in parents {
+        path.push(name);
+        current = openat(
+            &current,
+            *name,
+            OFlags::RDONLY | OFlags::DIRECTORY | OFlags::NOFOLLOW | OFlags::CLOEXEC,
+            Mode::empty(),
+        )
+        .map_err(wrap(&path))?;
+    }
+    path.push(last);
+    openat(
+        &current,
+        last,
+        OFlags::RDONLY | OFlags::NOFOLLOW | OFlags::CLOEXEC,
+        Mode::empty(),
+    )
+    .map(File::from)
+    .map_err(wrap(&path))
+}
+
+#[cfg(all(feature = "safe-open", windows))]
+fn open_components(dir: &Path, parents: &[&Path], last: &Path) -> Result<File> {
+    use std::os::windows::fs::OpenOptionsExt;
+    const FILE_SHARE_READ: u32 = 0x1;
+    const FILE_SHARE_WRITE: u32 = 0x2;
+    const FILE_FLAG_OPEN_REPARSE_POINT: u32 = 0x0020_0000;
+    const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x0200_0000;
+    fn open(path: &Path, flags: u32) -> Result<File> {
+        let file = OpenOptions::new()
+            .read(true)
+            .share_mode(FILE_SHARE_READ | FILE_SHARE_WRITE)
+            .custom_flags(flags)
+            .open(path)
+            .map_err(|error| Error::wrap_os_error("open failed", path, error))?;
+        let metadata = file
+            .metadata()
+            .map_err(|error| Error::wrap_os_error("metadata failed", path, error))?;
+        if is_link(&metadata) {
+            return Err(Error::new("unsafe open operation").with_path(path).into());
+        }
+        Ok(file)
+    }
+    // smoelius: The handles are held until the file is opened.
+    let mut handles = Vec::with_capacity(parents.len());
+    let mut path = dir.to_path_buf();
+    for name in parents {
+        path.push(name);
+        handles.push(open(
+            &path,
+            FILE_FLAG_OPEN_REPARSE_POINT | FILE_FLAG_BACKUP_SEMANTICS,
+        )?);
+    }
+    path.push(last);
+    open(&path, FILE_FLAG_OPEN_REPARSE_POINT)
+}
+
+/// Opens `path` for reading, relative to the directory `dir`, with `openat2(2)` and
+/// `RESOLVE_BENEATH | RESOLVE_NO_MAGICLINKS` (requires the `openat2` feature and Linux 5.6 or
+/// later). The kernel fails the open if resolving `path`, including any symbolic links and `..`
+/// components, would leave `dir`, so the check cannot be raced by changes to the filesystem.
+/// # Errors
+/// Returns an error if the open fails, e.g., with `EXDEV` if `path` leaves `dir`. The error
+/// payload is an [`Error`] whose source is the operating system's error.
+#[cfg(all(feature = "openat2", target_os = "linux"))]
+pub fn open_beneath<P: AsRef<Path>>(dir: &File, path: P) -> Result<File> {
+    use rustix::fs::{OFlags, ResolveFlags};
+    openat2_beneath(
+        dir,
+        path.as_ref(),
+        OFlags::RDONLY,
+        ResolveFlags::NO_MAGICLINKS,
+    )
+}
+
+/// Like [`open_beneath`], but opens `path` for writing, creating it if it does not exist and
+/// truncating it if it does, as [`File::create`] does.
+/// # Errors
+/// Returns an error if the open fails. See [`open_beneath`].
+#[cfg(all(feature = "openat2", target_os = "linux"))]
+pub fn create_beneath<P: AsRef<Path>>(dir: &File, path: P) -> Result<File> {
+    use rustix::fs::{OFlags, ResolveFlags};
+    openat2_beneath(
+        dir,
+        path.as_ref(),
+        OFlags::WRONLY | OFlags::CREATE | OFlags::TRUNC,
+        ResolveFlags::NO_MAGICLINKS,
+    )
+}
+
+/// Opens `path` relative to `dir` with `openat2(2)`, `RESOLVE_BENEATH`, and `resolve`.
+#[cfg(all(feature = "openat2", target_os = "linux"))]
+fn openat2_beneath(
+    dir: &File,
+    path: &Path,
+    oflags: rustix::fs::OFlags,
+    resolve: rustix::fs::ResolveFlags,
+) -> Result<File> {
+    use rustix::{
+        fs::{openat2, Mode, OFlags, ResolveFlags},
+        io::Errno,
+    };
+    let resolve = ResolveFlags::BENEATH | resolve;
+    // smoelius: Unlike `openat`, `openat2` rejects a nonzero mode without `O_CREAT`.
+    let mode = if oflags.contains(OFlags::CREATE) {
+        Mode::from_raw_mode(0o666)
+    } else {
+        Mode::empty()
+    };
+    loop {
+        // smoelius: `openat2` fails with `EAGAIN` if a rename elsewhere on the system might have
+        // affected resolution. Retrying is the documented remedy.
+        match openat2(dir, path, oflags | OFlags::CLOEXEC, mode, resolve) {
+            Ok(fd) => return Ok(File::from(fd)),
+            Err(Errno::AGAIN | Errno::INTR) => {}
+            Err(errno) => {
+                return Err(Error::wrap_os_error(
+                    "openat2 failed",
+                    path,
+                    io::Error::from(errno),
+                ))
+            }
+        }
+    }
+}
+
+/// A fan-out directory layout for content-addressed storage, e.g., `ab/cd/abcdef…`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FanOut {
+    levels: usize,
+    width: usize,
+}
+
+impl Default for FanOut {
+    /// Two levels of two characters each, e.g., `ab/cd/abcdef…`
+    fn default() -> Self {
+        Self::new(2, 2)
+    }
+}
+
+impl FanOut {
+    /// Creates a layout with `levels` intermediate directories, each named with the next `width`
+    /// characters of the hash.
+    #[must_use]
+    pub fn new(levels: usize, width: usize) -> Self {
+        Self { levels, width }
+    }
+
+    /// Returns the location of the object with content hash `hash`, relative to the store's root.
+    /// # Errors
+    /// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if `hash` contains a
+    /// character that is not an ASCII letter or digit, or if it is not longer than the intermediate
+    /// directory names combined. The error payload is an [`Error`] whose contents are unstable and
+    /// subject to change.
+    pub fn relative_path(&self, hash: &str) -> Result<PathBuf> {
+        let prefix_len = self.levels.saturating_mul(self.width);
+        if hash.len() <= prefix_len || !hash.bytes().all(|byte| byte.is_ascii_alphanumeric()) {
+            return Err(Error::new("invalid content hash")
+                .with_violation(Violation::InvalidContentHash)
+                .into());
+        }
+        let mut path = PathBuf::new();
+        for level in 0..self.levels {
+            path.push(&hash[level * self.width..(level + 1) * self.width]);
+        }
+        path.push(hash);
+        Ok(path)
+    }
+}
+
+/// A listing of a [`SafeDir`] produced by [`SafeDir::snapshot`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    root: PathBuf,
+    entries: Vec<SnapshotEntry>,
+    hashed: bool,
+}
+
+impl Snapshot {
+    /// The directory the snapshot was taken of
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The entries, sorted by path
+    #[must_use]
+    pub fn entries(&self) -> &[SnapshotEntry] {
+        &self.entries
+    }
+
+    /// Returns the entry for `path` (relative to the root), if there is one.
+    #[must_use]
+    pub fn get<P: AsRef<Path>>(&self, path: P) -> Option<&SnapshotEntry> {
+        let path = path.as_ref();
+        self.entries
+            .binary_search_by(|entry| entry.path.as_path().cmp(path))
+            .ok()
+            .map(|index| &self.entries[index])
+    }
+}
+
+/// One entry of a [`Snapshot`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SnapshotEntry {
+    /// The entry's path, relative to the snapshot's root
+    pub path: PathBuf,
+    /// The entry's type. Symbolic links are not followed.
+    pub file_type: FileType,
+    /// The entry's size in bytes
+    pub len: u64,
+    /// The entry's modification time, if the platform provides one
+    pub modified: Option<SystemTime>,
+    /// A hash of the entry's contents, if the snapshot has hashes and the entry is a regular file
+    pub hash: Option<u64>,
+}
+
+/// The outcome of transferring one entry with [`SafeDir::transfer`]
+#[derive(Debug)]
+pub struct Transfer {
+    /// The entry, as passed to [`SafeDir::transfer`]
+    pub entry: PathBuf,
+    /// The entry's new location, or the error that prevented the transfer
+    pub result: Result<PathBuf>,
+}
+
+/// A weakness in a base directory or one of its ancestors, as reported by [`audit_base`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Finding {
+    /// `path` is a symbolic link (on Windows, a reparse point), which whoever controls its parent
+    /// could replace
+    Symlink { path: PathBuf },
+    /// `path`, the canonical base directory, is not a directory
+    NotADirectory { path: PathBuf },
+    /// `path` is writable by its group or by other users, and either is the base directory or
+    /// lacks the sticky bit; `mode` is its `st_mode`
+    Writable { path: PathBuf, mode: u32 },
+    /// `path` is owned by `uid` rather than by the expected owner (or, for an ancestor, by root)
+    UnexpectedOwner { path: PathBuf, uid: u32 },
+}
+
+fn hash_file(path: &Path) -> Result<u64> {
+    let mut file =
+        File::open(path).map_err(|error| Error::wrap_os_error("open failed", path, error))?;
+    let mut buf = [0; 8192];
+    let mut hash = FNV1A_64_OFFSET;
+    loop {
+        match file.read(&mut buf) {
+            Ok(0) => return Ok(hash),
+            Ok(n) => hash = fnv1a_64_update(hash, &buf[..n]),
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(Error::wrap_os_error("read failed", path, error)),
+        }
+    }
+}
+
+fn move_file(src: &Path, dst: &Path) -> Result<()> {
+    match fs::rename(src, dst) {
+        Ok(()) => Ok(()),
+        Err(error) if crosses_devices(&error) => copy_sync_remove(src, dst),
+        Err(error) => Err(Error::wrap_os_error("rename failed", src, error)),
+    }
+}
+
+fn copy_sync_remove(src: &Path, dst: &Path) -> Result<()> {
+    let copied = fs::copy(src, dst)
+        .map_err(|error| Error::wrap_os_error("copy failed", src, error))
+        .and_then(|_| {
+            // smoelius: On Windows, `sync_all` requires write access.
+            OpenOptions::new()
+                .write(true)
+                .open(dst)
+                .and_then(|file| file.sync_all())
+                .map_err(|error| Error::wrap_os_error("sync failed", dst, error))
+        });
+    if let Err(error) = copied {
+        let _ = fs::remove_file(dst);
+        return Err(error);
+    }
+    fs::remove_file(src).map_err(|error| Error::wrap_os_error("remove failed", src, error))
+}
+
+/// Checks that each existing prefix of `dir.join(path)` is on the same device as `dir`.
+#[cfg(unix)]
+fn check_same_device(dir: &Path, path: &Path) -> Result<()> {
+    use std::os::unix::fs::MetadataExt;
+    let device = fs::metadata(dir)
+        .map_err(|error| Error::wrap_os_error("metadata failed", dir, error))?
+        .dev();
+    let mut current = dir.to_path_buf();
+    for (index, component) in path.components().enumerate() {
+        current.push(component);
+        match fs::metadata(&current) {
+            Ok(metadata) if metadata.dev() == device => {}
+            Ok(_) => {
+                return Err(Error::new("unsafe join operation")
+                    .with_violation(Violation::DeviceBoundary { index })
+                    .with_path(current)
+                    .into())
+            }
+            Err(error) if error.kind() == io::ErrorKind::NotFound => break,
+            Err(error) => return Err(Error::wrap_os_error("metadata failed", &current, error)),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn check_same_device(_dir: &Path, _path: &Path) -> Result<()> {
+    Ok(())
+}
+
+fn remove_dir_all(path: &Path) -> Result<()> {
+    let metadata = fs::symlink_metadata(path)
+        .map_err(|error| Error::wrap_os_error("metadata failed", path, error))?;
+    // smoelius: `symlink_metadata` does not follow symbolic links, so a link to a directory is
+    // removed, not recursed into.
+    if is_link(&metadata) {
+        return remove_link(path, &metadata);
+    }
+    if !metadata.is_dir() {
+        return fs::remove_file(path)
+            .map_err(|error| Error::wrap_os_error("remove failed", path, error));
+    }
+    let entries =
+        fs::read_dir(path).map_err(|error| Error::wrap_os_error("read_dir failed", path, error))?;
+    for entry in entries {
+        let entry = entry.map_err(|error| Error::wrap_os_error("read_dir failed", path, error))?;
+        remove_dir_all(&entry.path())?;
+    }
+    fs::remove_dir(path).map_err(|error| Error::wrap_os_error("remove_dir failed", path, error))
+}
+
+/// Returns true if `metadata` (as returned by [`std::fs::symlink_metadata`]) describes a symbolic
+/// link or, on Windows, any other reparse point, e.g., a junction or a mount point. Reparse points
+/// can redirect a path anywhere, so they are treated like symbolic links.
+fn is_link(metadata: &fs::Metadata) -> bool {
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        const FILE_ATTRIBUTE_REPARSE_POINT: u32 = 0x400;
+        if metadata.file_attributes() & FILE_ATTRIBUTE_REPARSE_POINT != 0 {
+            return true;
+        }
+    }
+    metadata.file_type().is_symlink()
+}
+
+/// Removes the link `path` (see [`is_link`]) without following it.
+fn remove_link(path: &Path, metadata: &fs::Metadata) -> Result<()> {
+    // smoelius: On Windows, a link to a directory (including a junction) must be removed with
+    // `remove_dir`.
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        const FILE_ATTRIBUTE_DIRECTORY: u32 = 0x10;
+        if metadata.file_attributes() & FILE_ATTRIBUTE_DIRECTORY != 0 {
+            return fs::remove_dir(path)
+                .map_err(|error| Error::wrap_os_error("remove_dir failed", path, error));
+        }
+    }
+    #[cfg(not(windows))]
+    let _ = metadata;
+    fs::remove_file(path).map_err(|error| Error::wrap_os_error("remove failed", path, error))
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+// smoelius: Windows distinguishes links to directories from links to files. A link whose target
+// does not exist is created as a link to a file.
+#[cfg(windows)]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    let resolved = link.parent().unwrap_or_else(|| Path::new("")).join(target);
+    if resolved.is_dir() {
+        std::os::windows::fs::symlink_dir(target, link)
+    } else {
+        std::os::windows::fs::symlink_file(target, link)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn symlink(_target: &Path, _link: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Other,
+        "symbolic links are not supported on this platform",
+    ))
+}
+
+pub(crate) fn crosses_devices(error: &io::Error) -> bool {
+    #[cfg(unix)]
+    const EXDEV: i32 = 18;
+    #[cfg(windows)]
+    const EXDEV: i32 = 17; // smoelius: `ERROR_NOT_SAME_DEVICE`
+    #[cfg(any(unix, windows))]
+    return error.raw_os_error() == Some(EXDEV);
+    #[cfg(not(any(unix, windows)))]
+    return false;
+}
@@ -0,0 +1,13 @@
+pub(crate) const FNV1A_64_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// Returns the 64-bit FNV-1a hash of `bytes`.
+pub(crate) fn fnv1a_64(bytes: &[u8]) -> u64 {
+    fnv1a_64_update(FNV1A_64_OFFSET, bytes)
+}
+
+/// Continues the 64-bit FNV-1a hash `hash` with `bytes`.
+pub(crate) fn fnv1a_64_update(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
@@ -0,0 +1,307 @@
+//! Mapping of errors to HTTP status codes
+//!
+//! [`Error::http_status`] maps an error to a status code using [`StatusTable::default`], which
+//! responds to traversal attempts and forbidden names with 404 so that a response does not reveal
+//! why a path was rejected. Use [`Error::http_status_with`] to supply a different table.
+//!
+//! With the `axum` feature, [`Rejection`] converts a [`std::io::Error`] into an `axum` response,
+//! and [`SafeRequestPath`] extracts a request's path as a checked path under a document root. With
+//! the `warp` feature, [`safe_tail`] does the same for `warp`. With the `tower` feature,
+//! [`ValidatePathLayer`] rejects requests whose paths fail the check before an inner service runs.
+
+use crate::{Error, Violation};
+use std::io;
+
+/// A mapping from kinds of errors to HTTP status codes
+///
+/// An error is classified by its [`Error::violation`]. To change an entry, start from
+/// [`StatusTable::default`] and assign to the entry's field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct StatusTable {
+    /// For paths that refer outside of the base directory, e.g., `../x` or `/x`, and for errors
+    /// without a [`Violation`] (default: 404)
+    pub traversal: u16,
+    /// For paths with a component forbidden by a [`crate::Policy`], e.g., a hidden, denied, or
+    /// reserved component, and for paths rejected by a user-supplied check (default: 404)
+    pub forbidden_name: u16,
+    /// For paths that are malformed according to a [`crate::Policy`], e.g., too long or with a
+    /// disallowed character, extension, or environment-variable reference, and for other invalid
+    /// input, e.g., an invalid file name or encoding (default: 400)
+    pub malformed: u16,
+    /// For operating-system errors of kind [`std::io::ErrorKind::NotFound`] (default: 404)
+    pub not_found: u16,
+    /// For other operating-system errors (default: 500)
+    pub os_error: u16,
+}
+
+impl Default for StatusTable {
+    fn default() -> Self {
+        Self {
+            traversal: 404,
+            forbidden_name: 404,
+            malformed: 400,
+            not_found: 404,
+            os_error: 500,
+        }
+    }
+}
+
+impl StatusTable {
+    /// Returns the status code `self` assigns to `error`.
+    #[must_use]
+    pub fn status(&self, error: &Error) -> u16 {
+        if let Some(kind) = error.source_kind() {
+            return if kind == io::ErrorKind::NotFound {
+                self.not_found
+            } else {
+                self.os_error
+            };
+        }
+        match error.violation() {
+            Some(
+                Violation::DeniedComponent { .. }
+                | Violation::HiddenComponent { .. }
+                | Violation::ShortName { .. }
+                | Violation::ReservedName { .. }
+                | Violation::Custom { .. },
+            ) => self.forbidden_name,
+            Some(
+                Violation::TooLong
+                | Violation::TooDeep
+                | Violation::ComponentTooLong { .. }
+                | Violation::DisallowedChar { .. }
+                | Violation::ControlChar { .. }
+                | Violation::InvisibleChar { .. }
+                | Violation::Stream { .. }
+                | Violation::DisallowedExtension
+                | Violation::TrailingDotOrSpace { .. }
+                | Violation::EnvVar { .. }
+                | Violation::Tilde { .. }
+                | Violation::RedundantSeparator
+                | Violation::NotFileName
+                | Violation::InvalidExtension
+                | Violation::InvalidEncoding
+                | Violation::InvalidContentHash,
+            ) => self.malformed,
+            _ => self.traversal,
+        }
+    }
+
+    /// Returns the status code `self` assigns to `error`. Errors without an [`Error`] payload are
+    /// assigned `self.os_error`.
+    #[must_use]
+    pub fn io_status(&self, error: &io::Error) -> u16 {
+        Error::downcast_ref(error).map_or(self.os_error, |error| self.status(error))
+    }
+}
+
+/// A [`std::io::Error`] that can be returned from an `axum` handler or used as a `warp` rejection
+/// (requires the `axum` or `warp` feature)
+///
+/// The `axum` response consists of the status [`Rejection::status`] returns and the status's
+/// canonical reason phrase. The error's contents are not included in the response.
+#[cfg(any(feature = "axum", feature = "warp"))]
+#[derive(Debug)]
+pub struct Rejection(pub io::Error);
+
+#[cfg(any(feature = "axum", feature = "warp"))]
+impl Rejection {
+    /// Returns the status [`StatusTable::default`] assigns to the error.
+    #[must_use]
+    pub fn status(&self) -> u16 {
+        StatusTable::default().io_status(&self.0)
+    }
+}
+
+#[cfg(any(feature = "axum", feature = "warp"))]
+impl From<io::Error> for Rejection {
+    fn from(error: io::Error) -> Self {
+        Self(error)
+    }
+}
+
+#[cfg(feature = "axum")]
+impl axum_core::response::IntoResponse for Rejection {
+    fn into_response(self) -> axum_core::response::Response {
+        let status = ::http::StatusCode::from_u16(self.status())
+            .unwrap_or(::http::StatusCode::INTERNAL_SERVER_ERROR);
+        (status, status.canonical_reason().unwrap_or_default()).into_response()
+    }
+}
+
+/// An `axum` extractor that maps a request's path to a file under a [`crate::url::Docroot`]
+/// (requires the `axum` feature)
+///
+/// The `Docroot` is obtained from the router's state with `axum_core::extract::FromRef`. Its
+/// prefix should be the part of the route before the wildcard segment, e.g., `/static` for a route
+/// `/static/{*path}`. A request whose path fails [`crate::url::Docroot::resolve`] is rejected with
+/// a [`Rejection`], i.e., with 400 or 404.
+///
+/// With the `tower` feature, [`ValidatePath`] inserts a `SafeRequestPath` into the extensions of
+/// each request it passes on.
+#[cfg(any(feature = "axum", feature = "tower"))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SafeRequestPath(pub std::path::PathBuf);
+
+#[cfg(feature = "axum")]
+impl<S> axum_core::extract::FromRequestParts<S> for SafeRequestPath
+where
+    S: Send + Sync,
+    crate::url::Docroot: axum_core::extract::FromRef<S>,
+{
+    type Rejection = Rejection;
+
+    async fn from_request_parts(
+        parts: &mut ::http::request::Parts,
+        state: &S,
+    ) -> std::result::Result<Self, Self::Rejection> {
+        let docroot = <crate::url::Docroot as axum_core::extract::FromRef<S>>::from_ref(state);
+        docroot
+            .resolve(parts.uri.path())
+            .map(Self)
+            .map_err(Rejection)
+    }
+}
+
+#[cfg(feature = "warp")]
+impl ::warp::reject::Reject for Rejection {}
+
+/// A `warp` filter that matches the rest of the request's path and maps it to a file under
+/// `docroot` (requires the `warp` feature)
+///
+/// `docroot`'s prefix is ignored, since filters preceding this one match the route's prefix, e.g.,
+/// `warp::path("static").and(safe_tail(docroot))`. A request whose path fails the check is rejected
+/// with a [`Rejection`]. A `recover` handler can find it with `warp::Rejection::find` and respond
+/// with [`Rejection::status`].
+#[cfg(feature = "warp")]
+#[must_use]
+pub fn safe_tail(
+    docroot: crate::url::Docroot,
+) -> impl ::warp::Filter<Extract = (std::path::PathBuf,), Error = ::warp::Rejection> + Clone {
+    use ::warp::Filter;
+    ::warp::path::tail().and_then(move |tail: ::warp::path::Tail| {
+        let result = docroot
+            .resolve_unprefixed(tail.as_str())
+            .map_err(|error| ::warp::reject::custom(Rejection(error)));
+        std::future::ready(result)
+    })
+}
+
+/// A `tower` layer that applies [`ValidatePath`] (requires the `tower` feature)
+#[cfg(feature = "tower")]
+#[derive(Clone, Debug)]
+pub struct ValidatePathLayer {
+    docroot: crate::url::Docroot,
+}
+
+#[cfg(feature = "tower")]
+impl ValidatePathLayer {
+    /// Creates a layer that checks request paths with `docroot`.
+    #[must_use]
+    pub fn new(docroot: crate::url::Docroot) -> Self {
+        Self { docroot }
+    }
+}
+
+#[cfg(feature = "tower")]
+impl<S> tower_layer::Layer<S> for ValidatePathLayer {
+    type Service = ValidatePath<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ValidatePath {
+            inner,
+            docroot: self.docroot.clone(),
+        }
+    }
+}
+
+/// A `tower` service that checks each request's path with [`crate::url::Docroot::resolve`] before
+/// passing the request to an inner service, e.g., `tower_http::services::ServeDir` (requires the
+/// `tower` feature)
+///
+/// A request whose path fails the check is answered with the status [`StatusTable::default`]
+/// assigns and an empty body, and the inner service is not called. Otherwise, the resolved path is
+/// inserted into the request's extensions as a [`SafeRequestPath`].
+#[cfg(feature = "tower")]
+#[derive(Clone, Debug)]
+pub struct ValidatePath<S> {
+    inner: S,
+    docroot: crate::url::Docroot,
+}
+
+#[cfg(feature = "tower")]
+impl<S, ReqBody, ResBody> tower_service::Service<::http::Request<ReqBody>> for ValidatePath<S>
+where
+    S: tower_service::Service<::http::Request<ReqBody>, Response = ::http::Response<ResBody>>,
+    ResBody: Default,
+{
+    type Response = ::http::Response<ResBody>;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future, ResBody>;
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::result::Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: ::http::Request<ReqBody>) -> Self::Future {
+        match self.docroot.resolve(request.uri().path()) {
+            Ok(path) => {
+                request.extensions_mut().insert(SafeRequestPath(path));
+                ResponseFuture::Inner {
+                    future: self.inner.call(request),
+                }
+            }
+            Err(error) => {
+                let mut response = ::http::Response::new(ResBody::default());
+                *response.status_mut() =
+                    ::http::StatusCode::from_u16(StatusTable::default().io_status(&error))
+                        .unwrap_or(::http::StatusCode::INTERNAL_SERVER_ERROR);
+                ResponseFuture::Rejected {
+                    response: Some(response),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "tower")]
+pin_project_lite::pin_project! {
+    /// The future [`ValidatePath`] returns (requires the `tower` feature)
+    #[project = ResponseFutureProj]
+    pub enum ResponseFuture<F, B> {
+        /// The inner service's future
+        Inner {
+            #[pin]
+            future: F,
+        },
+        /// A response to a request whose path failed the check
+        Rejected {
+            response: Option<::http::Response<B>>,
+        },
+    }
+}
+
+#[cfg(feature = "tower")]
+impl<F, B, E> std::future::Future for ResponseFuture<F, B>
+where
+    F: std::future::Future<Output = std::result::Result<::http::Response<B>, E>>,
+{
+    type Output = F::Output;
+
+    fn poll(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        match self.project() {
+            ResponseFutureProj::Inner { future } => future.poll(cx),
+            ResponseFutureProj::Rejected { response } => {
+                // smoelius: `response` is `None` only if the future is polled after completion.
+                std::task::Poll::Ready(Ok(response.take().expect("polled after completion")))
+            }
+        }
+    }
+}
@@ -0,0 +1,313 @@
+//! Lexical normalization, i.e., normalization that does not consult the filesystem
+
+use smallvec::SmallVec;
+use std::{
+    borrow::Cow,
+    path::{Component, Path, PathBuf},
+};
+
+/// The number of components [`normalize`] can store without allocating
+pub const INLINE_COMPONENTS: usize = 16;
+
+/// A path's components after lexical normalization
+///
+/// Normalization removes `.` components and resolves each `..` component that follows a normal
+/// component. A `..` that immediately follows a root directory is removed (because `/..` is `/`).
+/// Other `..` components are kept, e.g., `x/../../y` normalizes to `../y`.
+///
+/// Up to `N` components are stored inline; longer paths spill onto the heap.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Normalized<'a, const N: usize> {
+    components: SmallVec<[Component<'a>; N]>,
+}
+
+impl<'a, const N: usize> Normalized<'a, N> {
+    /// Normalizes `path`.
+    #[must_use]
+    pub fn new(path: &'a Path) -> Self {
+        let mut components = SmallVec::<[Component<'a>; N]>::new();
+        for component in path.components() {
+            match component {
+                Component::Prefix(_) => {
+                    components.clear();
+                    components.push(component);
+                }
+                Component::RootDir => {
+                    let prefix_len = match components.first() {
+                        Some(Component::Prefix(_)) => 1,
+                        _ => 0,
+                    };
+                    components.truncate(prefix_len);
+                    components.push(component);
+                }
+                Component::CurDir => {}
+                Component::ParentDir => match components.last() {
+                    Some(Component::Normal(_)) => {
+                        components.pop();
+                    }
+                    Some(Component::RootDir) => {}
+                    _ => components.push(component),
+                },
+                Component::Normal(_) => components.push(component),
+            }
+        }
+        Self { components }
+    }
+
+    /// The normalized components
+    #[must_use]
+    pub fn components(&self) -> &[Component<'a>] {
+        &self.components
+    }
+
+    /// Returns true if the components did not fit in `N` inline slots.
+    #[must_use]
+    pub fn spilled(&self) -> bool {
+        self.components.spilled()
+    }
+
+    /// Collects the normalized components into a [`PathBuf`]. The empty path normalizes to `.`.
+    #[must_use]
+    pub fn to_path_buf(&self) -> PathBuf {
+        if self.components.is_empty() {
+            return PathBuf::from(Component::CurDir.as_os_str());
+        }
+        self.components.iter().collect()
+    }
+}
+
+/// Normalizes `path` using a buffer of [`INLINE_COMPONENTS`] components.
+/// ```
+/// # use safe_path::lexical::normalize;
+/// # use std::path::Path;
+/// assert_eq!(normalize(Path::new("./x/y/../z")).to_path_buf(), Path::new("x/z"));
+/// assert_eq!(normalize(Path::new("x/../../y")).to_path_buf(), Path::new("../y"));
+/// assert_eq!(normalize(Path::new("/../y")).to_path_buf(), Path::new("/y"));
+/// ```
+#[must_use]
+pub fn normalize(path: &Path) -> Normalized<'_, INLINE_COMPONENTS> {
+    Normalized::new(path)
+}
+
+/// Rewrites a Windows path's prefix into a canonical spelling, so that equivalent spellings of
+/// the same root compare equal. Specifically:
+///
+/// * `\\?\C:`, `\\.\C:`, and `c:` become `C:`.
+/// * `\\?\UNC\server\share` becomes `\\server\share`.
+///
+/// The rest of the path is unchanged. Other prefixes (e.g., `\\?\Volume{…}`) are unchanged, as
+/// are verbatim paths whose remainder contains `/` (which a verbatim path does not treat as a
+/// separator). On platforms other than Windows, paths have no prefixes, and `path` is returned
+/// unchanged.
+///
+/// [`LexicalKey`], [`lexically_equal`], and [`lexically_starts_with`] apply this function before
+/// normalizing.
+#[must_use]
+pub fn normalize_prefix(path: &Path) -> Cow<'_, Path> {
+    #[cfg(windows)]
+    {
+        let mut components = path.components();
+        let prefix = match components.next() {
+            Some(Component::Prefix(prefix)) => prefix,
+            _ => return Cow::Borrowed(path),
+        };
+        let rest = components.as_path();
+        if prefix.kind().is_verbatim() && rest.as_os_str().to_string_lossy().contains('/') {
+            return Cow::Borrowed(path);
+        }
+        let canonical = match crate::prefix::canonical(&prefix) {
+            Some(canonical) => canonical,
+            None => return Cow::Borrowed(path),
+        };
+        if canonical.as_os_str() == prefix.as_os_str() {
+            return Cow::Borrowed(path);
+        }
+        Cow::Owned(Path::new(&canonical).join(rest))
+    }
+    #[cfg(not(windows))]
+    Cow::Borrowed(path)
+}
+
+/// The length of Windows' legacy path buffers, in UTF-16 code units, including a terminating NUL
+pub const MAX_PATH: usize = 260;
+
+/// Returns `path` with the `\\?\` long-path prefix if `path` is too long for Windows' legacy
+/// path buffers (see [`MAX_PATH`]), e.g., for the result of a join. Because Windows does not
+/// normalize paths with this prefix, the result is normalized lexically and uses `\` as its only
+/// separator.
+///
+/// Only absolute paths on a drive or share can be prefixed, e.g., `C:\…` becomes `\\?\C:\…`
+/// and `\\server\share\…` becomes `\\?\UNC\server\share\…`. Other paths, and paths that
+/// are not too long, are returned unchanged. On platforms other than Windows, `path` is returned
+/// unchanged.
+#[must_use]
+pub fn long_path(path: &Path) -> Cow<'_, Path> {
+    #[cfg(windows)]
+    {
+        use std::{ffi::OsString, os::windows::ffi::OsStrExt, path::Prefix};
+        if path.as_os_str().encode_wide().count() < MAX_PATH {
+            return Cow::Borrowed(path);
+        }
+        let normalized = normalize(path);
+        let mut components = normalized.components().iter();
+        let mut long = match (components.next(), components.next()) {
+            (Some(Component::Prefix(prefix)), Some(Component::RootDir)) => match prefix.kind() {
+                Prefix::Disk(_) => {
+                    let mut long = OsString::from(r"\\?\");
+                    long.push(prefix.as_os_str());
+                    long
+                }
+                Prefix::UNC(server, share) => {
+                    let mut long = OsString::from(r"\\?\UNC\");
+                    long.push(server);
+                    long.push(r"\");
+                    long.push(share);
+                    long
+                }
+                _ => return Cow::Borrowed(path),
+            },
+            _ => return Cow::Borrowed(path),
+        };
+        long.push(r"\");
+        for (i, component) in components.enumerate() {
+            if i > 0 {
+                long.push(r"\");
+            }
+            long.push(component.as_os_str());
+        }
+        Cow::Owned(PathBuf::from(long))
+    }
+    #[cfg(not(windows))]
+    Cow::Borrowed(path)
+}
+
+/// Returns `path` without its `\\?\` verbatim prefix if the result means the same thing to
+/// Windows' legacy path handling, e.g., for a result of [`std::fs::canonicalize`] that will be
+/// passed to a tool that does not understand the prefix. This is the inverse of [`long_path`].
+///
+/// `\\?\C:\…` becomes `C:\…` and `\\?\UNC\server\share\…` becomes `\\server\share\…`,
+/// provided that the result is shorter than [`MAX_PATH`] and that no component is `.` or `..`, is
+/// a Windows reserved name, ends with a dot or a space, or contains a character that legacy paths
+/// treat specially (`/`, `:`, `<`, `>`, `"`, `|`, `?`, `*`, or a control character). Otherwise,
+/// and on platforms other than Windows, `path` is returned unchanged.
+#[must_use]
+pub fn simplified(path: &Path) -> Cow<'_, Path> {
+    #[cfg(windows)]
+    {
+        use std::{ffi::OsString, os::windows::ffi::OsStrExt, path::Prefix};
+        let mut components = path.components();
+        let mut simplified = match (components.next(), components.next()) {
+            (Some(Component::Prefix(prefix)), Some(Component::RootDir)) => match prefix.kind() {
+                Prefix::VerbatimDisk(drive) => OsString::from(format!("{}:", char::from(drive))),
+                Prefix::VerbatimUNC(server, share) => {
+                    let mut simplified = OsString::from(r"\\");
+                    simplified.push(server);
+                    simplified.push(r"\");
+                    simplified.push(share);
+                    simplified
+                }
+                _ => return Cow::Borrowed(path),
+            },
+            _ => return Cow::Borrowed(path),
+        };
+        simplified.push(r"\");
+        for (i, component) in components.enumerate() {
+            let name = match component {
+                Component::Normal(name) => name,
+                _ => return Cow::Borrowed(path),
+            };
+            let lossy = name.to_string_lossy();
+            if crate::sanitize::is_windows_reserved(&lossy)
+                || lossy.ends_with(['.', ' '].as_ref())
+                || lossy.chars().any(|c| {
+                    matches!(c, '/' | ':' | '<' | '>' | '"' | '|' | '?' | '*') || c.is_control()
+                })
+            {
+                return Cow::Borrowed(path);
+            }
+            if i > 0 {
+                simplified.push(r"\");
+            }
+            simplified.push(name);
+        }
+        if simplified.encode_wide().count() >= MAX_PATH {
+            return Cow::Borrowed(path);
+        }
+        Cow::Owned(PathBuf::from(simplified))
+    }
+    #[cfg(not(windows))]
+    Cow::Borrowed(path)
+}
+
+/// A normalized path, for use as a key in maps and sets
+///
+/// Paths that normalize to the same path produce equal keys:
+/// ```
+/// # use safe_path::lexical::LexicalKey;
+/// # use std::collections::HashSet;
+/// let keys: HashSet<_> = ["a", "./a", "a/b/..", "a/"].iter().map(LexicalKey::new).collect();
+/// assert_eq!(keys.len(), 1);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LexicalKey(PathBuf);
+
+impl LexicalKey {
+    /// Creates a key from `path` by normalizing it.
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self(normalize(&normalize_prefix(path.as_ref())).to_path_buf())
+    }
+
+    /// The normalized path
+    #[must_use]
+    pub fn as_path(&self) -> &Path {
+        &self.0
+    }
+
+    /// Converts `self` into the normalized path.
+    #[must_use]
+    pub fn into_path_buf(self) -> PathBuf {
+        self.0
+    }
+}
+
+impl AsRef<Path> for LexicalKey {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl From<&Path> for LexicalKey {
+    fn from(path: &Path) -> Self {
+        Self::new(path)
+    }
+}
+
+impl From<PathBuf> for LexicalKey {
+    fn from(path: PathBuf) -> Self {
+        Self::new(path)
+    }
+}
+
+/// Returns true if `a` and `b` are equal after normalization.
+/// ```
+/// # use safe_path::lexical::lexically_equal;
+/// assert!(lexically_equal("a/b/../c", "a/./c"));
+/// assert!(!lexically_equal("a/../../c", "c"));
+/// ```
+pub fn lexically_equal<P: AsRef<Path>, Q: AsRef<Path>>(a: P, b: Q) -> bool {
+    let (a, b) = (normalize_prefix(a.as_ref()), normalize_prefix(b.as_ref()));
+    let (a, b) = (normalize(&a), normalize(&b));
+    a == b
+}
+
+/// Returns true if, after normalization, `b`'s components are a prefix of `a`'s.
+/// ```
+/// # use safe_path::lexical::lexically_starts_with;
+/// assert!(lexically_starts_with("a/b/../c/d", "a/c"));
+/// assert!(!lexically_starts_with("a/c/../../d", "a"));
+/// ```
+pub fn lexically_starts_with<P: AsRef<Path>, Q: AsRef<Path>>(a: P, b: Q) -> bool {
+    let (a, b) = (normalize_prefix(a.as_ref()), normalize_prefix(b.as_ref()));
+    let (a, b) = (normalize(&a), normalize(&b));
+    a.components().starts_with(b.components())
+}
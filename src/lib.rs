@@ -28,6 +28,15 @@
 //! assert!(Path::new("/").relaxed_safe_parent().is_ok());
 //! ```
 //!
+//! For finer control, [`SafePath::safe_join_with`] checks a join against a [`Policy`]:
+//! ```
+//! # use safe_path::{Policy, SafePath};
+//! # let home_dir = std::path::PathBuf::new();
+//! let policy = Policy::new().relaxed(true).no_parent(true);
+//! assert!(home_dir.join("Documents").safe_join_with(&policy, ".").is_ok());
+//! assert!(home_dir.join("Documents").safe_join_with(&policy, "x/../y").is_err());
+//! ```
+//!
 //! ## Detailed explanation
 //!
 //! We'll explain `relaxed_safe_join` in detail since its requirements are slightly simpler than
@@ -117,7 +126,9 @@
 //! to be applicable in such situations. So we have chosen to adopt a simple semantics that
 //! considers only a path's [components].
 //!
-//! A similar crate that *does* consult the filesystem is [`canonical_path`].
+//! With the `fs` feature, `fs::safe_join_fs` additionally rejects joins that pass through a
+//! symlink to a file outside of `dir`. A similar crate that *does* consult the filesystem is
+//! [`canonical_path`].
 //!
 //! ## Performance
 //!
@@ -138,7 +149,24 @@
 //! `safe_path` optionally supports [`camino::Utf8Path`]. To take advantage of this feature, enable
 //! it on your `Cargo.toml` file's `safe_path` dependency:
 //! ```toml
-//! safe_path = { version = "0.1", features = ["camino"] }
+//! safe_path = { version = "0.2", features = ["camino"] }
+//! ```
+//!
+//! Similarly, `safe_path`'s support for `std::path::Path` is provided by the `std-path` feature,
+//! which is enabled by default. The checks themselves, [`Policy`], and the error types do not
+//! depend on either feature. To implement [`PathOps`] for another path type, disable default
+//! features and use the functions in the [`backend`] module.
+//!
+//! **Note:** Before version 0.2, the `std::path::Path` implementation was unconditional. A
+//! dependency that sets `default-features = false` must now list `std-path` to keep it:
+//! ```toml
+//! safe_path = { version = "0.2", default-features = false, features = ["std-path"] }
+//! ```
+//!
+//! Features that integrate with another crate's I/O (`async-std`, `cap-std`, `tar`, `tokio`,
+//! and `zip`) must be enabled together with the `fs` feature:
+//! ```toml
+//! safe_path = { version = "0.2", features = ["fs", "tokio"] }
 //! ```
 //!
 //! ## Linting
@@ -151,6 +179,8 @@
 //!   be used
 //! * calls to [`SafePath::safe_join`]/[`SafePath::relaxed_safe_join`] that are likely erroneous because
 //!   they return an error under normal circumstances, e.g., `safe_join("..")`
+//! * chains of [`Path::parent`] calls, and calls to `PathBuf::pop` in loops
+//! * (opt-in, via `safe_dir_bypass`) `std::fs` calls in modules that use `safe_path::fs::SafeDir`
 //!
 //! To use the library:
 //!
@@ -192,9 +222,125 @@
 //! [`path_clean::PathClean::clean`]: https://docs.rs/path-clean/0.1.0/path_clean/trait.PathClean.html#tymethod.clean
 //! [README]: https://github.com/trailofbits/dylint/blob/master/README.md
 
-use std::io::{Error, ErrorKind, Result};
+// smoelius: The suggestions of these lints require a newer toolchain than the lint crate's.
+#![allow(clippy::manual_let_else, clippy::uninlined_format_args)]
+
+use std::io::Result;
+
+// smoelius: A feature named after an optional dependency cannot enable other features without the
+// `dep:` syntax, which the lint crate's toolchain does not support. So such features' requirements
+// are checked here instead.
+#[cfg(all(
+    any(
+        feature = "async-std",
+        feature = "cap-std",
+        feature = "tar",
+        feature = "tokio",
+        feature = "zip"
+    ),
+    not(feature = "fs")
+))]
+compile_error!(
+    "the `async-std`, `cap-std`, `tar`, `tokio`, and `zip` features require the `fs` feature"
+);
+
+#[cfg(all(
+    any(
+        feature = "arbitrary",
+        feature = "clap",
+        feature = "proptest",
+        feature = "quickcheck",
+        feature = "schemars",
+        feature = "toml"
+    ),
+    not(feature = "std-path")
+))]
+compile_error!(
+    "the `arbitrary`, `clap`, `proptest`, `quickcheck`, `schemars`, and `toml` features require the `std-path` feature"
+);
+
+#[cfg(all(any(feature = "schemars", feature = "toml"), not(feature = "serde")))]
+compile_error!("the `schemars` and `toml` features require the `serde` feature");
+
+#[cfg(all(feature = "warp", not(feature = "url")))]
+compile_error!("the `warp` feature requires the `url` feature");
+
+#[cfg(any(feature = "arbitrary", feature = "proptest", feature = "quickcheck"))]
+pub mod adversarial;
+
+#[cfg(feature = "std-path")]
+pub mod archive;
+
+#[cfg(any(feature = "async-std", feature = "tokio"))]
+pub mod async_fs;
+
+pub mod backend;
+#[cfg(any(feature = "std-path", feature = "camino"))]
+use backend::ComponentKind;
+
+#[cfg(feature = "clap")]
+pub mod clap;
+
+mod error;
+pub use error::Error;
+
+pub mod escape;
+
+#[cfg(feature = "std-path")]
+mod file_name;
+#[cfg(feature = "std-path")]
+pub use file_name::FileName;
+
+mod hash;
+
+pub mod http;
+
+#[cfg(feature = "std-path")]
+pub mod lexical;
+
+mod policy;
+#[cfg(feature = "unicode-normalization")]
+pub use policy::NormalizationForm;
+pub use policy::{Action, ComponentCheck, PathCheck, Policy, PolicyVersion, Separators};
+
+mod prefix;
+
+#[cfg(feature = "std-path")]
+mod relative_path;
+#[cfg(feature = "std-path")]
+pub use relative_path::SafeRelativePath;
+
+pub mod report;
+
+pub mod sanitize;
+
+#[cfg(feature = "std-path")]
+pub mod tenant;
+
+mod violation;
+pub use violation::Violation;
+
+#[cfg(feature = "fs")]
+pub mod fs;
+
+#[cfg(feature = "receipt")]
+pub mod receipt;
+
+#[cfg(feature = "url")]
+pub mod url;
+
+#[cfg(feature = "proptest")]
+pub mod strategies;
+
+#[cfg(feature = "test-vectors")]
+pub mod test_vectors;
+
+#[cfg(feature = "typed-path")]
+pub mod windows;
 
 /// Abstracts the necessary operations of `std::path::Path` and `camino::Utf8Path`
+///
+/// Implementations for other path types can forward the checks to the [`backend`] module.
 pub trait PathOps: std::fmt::Debug {
     /// Type returned by [`PathOps::join`], e.g., [`std::path::PathBuf`]
     type PathBuf: AsRef<Self> + Clone;
@@ -205,6 +351,29 @@ pub trait PathOps: std::fmt::Debug {
     /// Parent operation, e.g., [`std::path::Path::parent`]
     fn parent(&self) -> Option<&Self>;
 
+    /// File name replacement operation, e.g., [`std::path::Path::with_file_name`]. The default
+    /// implementation joins `file_name` onto `self.parent()`, or onto `self` if it has no parent.
+    fn with_file_name<P: AsRef<Self>>(&self, file_name: P) -> Self::PathBuf {
+        match self.parent() {
+            Some(parent) => parent.join(file_name),
+            None => self.join(file_name),
+        }
+    }
+
+    /// Extension replacement operation, e.g., [`std::path::Path::with_extension`], if `Self` has
+    /// one. The default implementation returns `None`, in which case
+    /// [`SafePath::safe_with_extension`] rejects every argument.
+    fn with_extension<P: AsRef<Self>>(&self, _extension: P) -> Option<Self::PathBuf> {
+        None
+    }
+
+    /// Conversion to [`std::path::Path`], e.g., [`camino::Utf8Path::as_std_path`](https://docs.rs/camino/1.0.5/camino/struct.Utf8Path.html#method.as_std_path),
+    /// if `Self` has one. The default implementation returns `None`, in which case
+    /// [`SafePath::safe_join_with`] and [`SafePath::safe_parent_n_within`] reject every argument.
+    fn as_std_path(&self) -> Option<&std::path::Path> {
+        None
+    }
+
     /// "Starts with" operation, e.g., [`std::path::Path::starts_with`]
     ///
     /// Note that `base` must implement `AsRef<std::path::Path>`, not `AsRef<Self>`. This is to be
@@ -215,18 +384,89 @@ pub trait PathOps: std::fmt::Debug {
     /// file within `self`, and `relaxed` is true or `self.join(path)` is not `self`.
     /// # Errors
     /// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if the check fails. The
-    /// error payload is unstable and subject to change.
+    /// error payload is an [`Error`] whose contents are unstable and subject to change.
     fn check_join_safety(&self, path: &Self, relaxed: bool) -> Result<()>;
 
+    /// Like [`PathOps::check_join_safety`], but uses `self_is_root` in place of `self.is_root()`.
+    /// Useful when checking many paths against the same `self`. The default implementation calls
+    /// [`PathOps::check_join_safety`] if `self_is_root` agrees with `self.is_root()`, and fails
+    /// otherwise.
+    /// # Errors
+    /// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if the check fails. The
+    /// error payload is an [`Error`] whose contents are unstable and subject to change.
+    fn check_join_safety_with_root(
+        &self,
+        path: &Self,
+        relaxed: bool,
+        self_is_root: bool,
+    ) -> Result<()> {
+        // smoelius: Treating a root as a non-root (e.g., in `strict_safe_join`) cannot be expressed
+        // with `check_join_safety`, so the default implementation rejects conservatively.
+        if self_is_root != self.is_root() {
+            return Err(Error::new("unsafe join operation").into());
+        }
+        self.check_join_safety(path, relaxed)
+    }
+
+    /// Returns every [`Violation`] that would cause [`PathOps::check_join_safety`] to fail with
+    /// `relaxed` set to false. The result is empty if-and-only-if the check would succeed. The
+    /// default implementation returns only the violation that [`PathOps::check_join_safety`]'s
+    /// error carries.
+    fn explain_join_safety(&self, path: &Self) -> Vec<Violation> {
+        match self.check_join_safety(path, false) {
+            Ok(()) => Vec::new(),
+            Err(error) => vec![Error::downcast_ref(&error)
+                .and_then(Error::violation)
+                .cloned()
+                .unwrap_or(Violation::Custom {
+                    index: None,
+                    reason: "unsafe join operation",
+                })],
+        }
+    }
+
     /// Returns `Ok(())` if `self.parent()` refers to a file outside of `self`, and `relaxed` is
     /// true or `self.parent()` is not `Some(self)`.
     /// # Errors
     /// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if the check fails. The
-    /// error payload is unstable and subject to change.
+    /// error payload is an [`Error`] whose contents are unstable and subject to change.
     fn check_parent_safety(&self, relaxed: bool) -> Result<()>;
 
-    /// Returns true if `self` normalizes to `/`.
+    /// Returns true if `self` normalizes to its root, i.e., to [`PathOps::root_of`]. On Windows,
+    /// `C:\`, `\\?\C:\`, and `\\server\share\` are roots, but `C:` is not.
     fn is_root(&self) -> bool;
+
+    /// Returns the root under which `self` normalizes, e.g., `/` for `/x/../y`, `C:\` for `C:\x`,
+    /// or `\\server\share\` for `\\server\share\x`. Returns `None` if `self` is relative,
+    /// including if `self` is drive-relative, e.g., `C:x`. The default implementation returns
+    /// `self`'s last ancestor, i.e., the last of its repeated parents, if that ancestor is a root.
+    fn root_of(&self) -> Option<&Self> {
+        let mut ancestor = self;
+        while let Some(parent) = ancestor.parent() {
+            ancestor = parent;
+        }
+        if ancestor.is_root() {
+            Some(ancestor)
+        } else {
+            None
+        }
+    }
+
+    /// Returns true if `self` consists of exactly one normal component, e.g., `file.txt`.
+    fn is_file_name(&self) -> bool {
+        // smoelius: `self` has exactly one component if its parent is empty, i.e., has no parent
+        // and is not a root. The join check then rules out `.` and `..`.
+        matches!(self.parent(), Some(parent) if parent.parent().is_none() && !parent.is_root())
+            && self.check_join_safety(self, false).is_ok()
+    }
+
+    /// Returns `self` without repeated separators, `.` components other than a leading one, or a
+    /// trailing separator, e.g., `a/b` for `a//./b/`, if `Self` supports it. The default
+    /// implementation returns `None`, in which case [`SafePath::safe_join_with`] rejects every
+    /// argument when the policy normalizes separators.
+    fn collapse(&self) -> Option<Self::PathBuf> {
+        None
+    }
 }
 
 /// Trait encapsulating `safe_join` and `safe_parent`. See [`crate`] documentation for details.
@@ -235,7 +475,7 @@ pub trait SafePath: PathOps {
     /// refers to a file within `self`, and `self.join(path)` is not `self`.
     /// # Errors
     /// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if the check fails. The
-    /// error payload is unstable and subject to change.
+    /// error payload is an [`Error`] whose contents are unstable and subject to change.
     fn safe_join<P: AsRef<Self>>(&self, path: P) -> Result<Self::PathBuf> {
         self.check_join_safety(path.as_ref(), false)?;
         Ok(self.join(path))
@@ -244,17 +484,176 @@ pub trait SafePath: PathOps {
     /// Like `SafePath::safe_join` but without the requirement that `self.join(path)` is not `self`.
     /// # Errors
     /// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if the check fails. The
-    /// error payload is unstable and subject to change.
+    /// error payload is an [`Error`] whose contents are unstable and subject to change.
     fn relaxed_safe_join<P: AsRef<Self>>(&self, path: P) -> Result<Self::PathBuf> {
         self.check_join_safety(path.as_ref(), true)?;
         Ok(self.join(path))
     }
 
+    /// Like [`SafePath::safe_join`], but treats `self` as though it were not `/`. In particular,
+    /// `path` may not contain a root directory or prefix, even if `self` is `/`.
+    ///
+    /// ```
+    /// # use safe_path::SafePath;
+    /// # use std::path::Path;
+    /// assert!(Path::new("/").safe_join("/etc/passwd").is_ok());
+    /// assert!(Path::new("/").strict_safe_join("/etc/passwd").is_err());
+    /// ```
+    /// # Errors
+    /// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if the check fails. The
+    /// error payload is an [`Error`] whose contents are unstable and subject to change.
+    fn strict_safe_join<P: AsRef<Self>>(&self, path: P) -> Result<Self::PathBuf> {
+        self.check_join_safety_with_root(path.as_ref(), false, false)?;
+        Ok(self.join(path))
+    }
+
+    /// Like [`SafePath::safe_join`], but also returns an error if `path` contains any `..`
+    /// component, even one that does not refer to a file outside of `self`, e.g., `x/../y`.
+    /// # Errors
+    /// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if the check fails. The
+    /// error payload is an [`Error`] whose contents are unstable and subject to change.
+    fn no_parent_safe_join<P: AsRef<Self>>(&self, path: P) -> Result<Self::PathBuf> {
+        self.safe_join_with(&Policy::new().no_parent(true), path)
+    }
+
+    /// Returns `Ok(self.join(path))` if `self.join(path)` satisfies `policy`'s requirements. See
+    /// [`Policy`]. If `policy` normalizes separators, the result is collapsed with
+    /// [`PathOps::collapse`].
+    /// # Errors
+    /// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if the check fails. The
+    /// error payload is an [`Error`] whose contents are unstable and subject to change.
+    fn safe_join_with<P: AsRef<Self>>(&self, policy: &Policy, path: P) -> Result<Self::PathBuf> {
+        policy.check(self, path.as_ref())?;
+        let result = self.join(path);
+        if policy.separator_handling() == Separators::Normalize {
+            result
+                .as_ref()
+                .collapse()
+                .ok_or_else(|| Error::new("path type has no separator normalization").into())
+        } else {
+            Ok(result)
+        }
+    }
+
+    /// Returns `Ok(self.join(path))` if `path` consists of exactly one normal component, e.g.,
+    /// `file.txt`. This is stricter than [`SafePath::safe_join`]. See also [`FileName`].
+    /// # Errors
+    /// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if the check fails. The
+    /// error payload is an [`Error`] whose contents are unstable and subject to change.
+    fn safe_join_component<P: AsRef<Self>>(&self, path: P) -> Result<Self::PathBuf> {
+        if !path.as_ref().is_file_name() {
+            return Err(Error::new("unsafe join operation")
+                .with_violation(Violation::NotFileName)
+                .into());
+        }
+        Ok(self.join(path))
+    }
+
+    /// Returns `Ok(self.with_file_name(file_name))` if `file_name` consists of exactly one normal
+    /// component. Unlike [`std::path::Path::with_file_name`], this rejects values like `../x` that
+    /// would move the result out of `self`'s directory.
+    ///
+    /// ```
+    /// # use safe_path::SafePath;
+    /// # use std::path::Path;
+    /// assert!(Path::new("www/index.html").safe_with_file_name("about.html").is_ok());
+    /// assert!(Path::new("www/index.html").safe_with_file_name("../.env").is_err());
+    /// ```
+    /// # Errors
+    /// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if the check fails. The
+    /// error payload is an [`Error`] whose contents are unstable and subject to change.
+    fn safe_with_file_name<P: AsRef<Self>>(&self, file_name: P) -> Result<Self::PathBuf> {
+        if !file_name.as_ref().is_file_name() {
+            return Err(Error::new("invalid file name")
+                .with_violation(Violation::NotFileName)
+                .into());
+        }
+        Ok(self.with_file_name(file_name))
+    }
+
+    /// Returns `Ok(self.with_extension(extension))` if `extension` is empty or consists of exactly
+    /// one normal component. Unlike [`std::path::Path::with_extension`], this rejects values like
+    /// `txt/../../x` that would move the result out of `self`'s directory.
+    ///
+    /// ```
+    /// # use safe_path::SafePath;
+    /// # use std::path::Path;
+    /// assert!(Path::new("www/index.html").safe_with_extension("bak").is_ok());
+    /// assert!(Path::new("www/index.html").safe_with_extension("/../../x").is_err());
+    /// ```
+    /// # Errors
+    /// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if the check fails. The
+    /// error payload is an [`Error`] whose contents are unstable and subject to change.
+    fn safe_with_extension<P: AsRef<Self>>(&self, extension: P) -> Result<Self::PathBuf> {
+        let ext = extension.as_ref();
+        if !(ext.is_file_name()
+            || matches!(ext.as_std_path(), Some(ext) if ext.as_os_str().is_empty()))
+        {
+            return Err(Error::new("invalid extension")
+                .with_violation(Violation::InvalidExtension)
+                .into());
+        }
+        self.with_extension(extension)
+            .ok_or_else(|| Error::new("path type has no extension replacement").into())
+    }
+
+    /// Applies [`SafePath::safe_join`] to each of `paths`, computing the properties of `self` that
+    /// the checks require only once.
+    fn safe_join_all<I>(&self, paths: I) -> Vec<Result<Self::PathBuf>>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<Self>,
+    {
+        let self_is_root = self.is_root();
+        paths
+            .into_iter()
+            .map(|path| {
+                self.check_join_safety_with_root(path.as_ref(), false, self_is_root)?;
+                Ok(self.join(path))
+            })
+            .collect()
+    }
+
+    /// Like [`SafePath::safe_join_all`], but stops at the first path whose check fails.
+    /// # Errors
+    /// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if any check fails. The
+    /// error payload is an [`Error`] whose contents are unstable and subject to change.
+    fn try_safe_join_all<I>(&self, paths: I) -> Result<Vec<Self::PathBuf>>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<Self>,
+    {
+        let self_is_root = self.is_root();
+        paths
+            .into_iter()
+            .map(|path| {
+                self.check_join_safety_with_root(path.as_ref(), false, self_is_root)?;
+                Ok(self.join(path))
+            })
+            .collect()
+    }
+
+    /// Performs the checks of [`SafePath::safe_join`] without joining, and returns every
+    /// [`Violation`] found. An empty result means `safe_join` would succeed. For
+    /// [`SafePath::relaxed_safe_join`], ignore [`Violation::SelfJoin`].
+    ///
+    /// ```
+    /// # use safe_path::{SafePath, Violation};
+    /// # use std::path::Path;
+    /// assert_eq!(
+    ///     Path::new("x").explain_join("y/../../z"),
+    ///     vec![Violation::EscapingParentDir { index: 2 }]
+    /// );
+    /// ```
+    fn explain_join<P: AsRef<Self>>(&self, path: P) -> Vec<Violation> {
+        self.explain_join_safety(path.as_ref())
+    }
+
     /// Returns `Ok(self.parent())` if `self.parent()` refers to a file outside of `self`, and
     /// `self.parent()` is not `Some(self)`.
     /// # Errors
     /// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if the check fails. The
-    /// error payload is unstable and subject to change.
+    /// error payload is an [`Error`] whose contents are unstable and subject to change.
     fn safe_parent(&self) -> Result<Option<&Self>> {
         self.check_parent_safety(false)?;
         Ok(self.parent())
@@ -264,15 +663,119 @@ pub trait SafePath: PathOps {
     /// `Some(self)`.
     /// # Errors
     /// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if the check fails. The
-    /// error payload is unstable and subject to change.
+    /// error payload is an [`Error`] whose contents are unstable and subject to change.
     fn relaxed_safe_parent(&self) -> Result<Option<&Self>> {
         self.check_parent_safety(true)?;
         Ok(self.parent())
     }
+
+    /// Applies [`SafePath::safe_parent`] `n` times, checking at each step. `n == 0` returns `self`.
+    ///
+    /// ```
+    /// # use safe_path::SafePath;
+    /// # use std::path::Path;
+    /// assert_eq!(Path::new("w/x/y/z").safe_parent_n(2).unwrap(), Path::new("w/x"));
+    /// assert!(Path::new("x/y").safe_parent_n(3).is_err());
+    /// ```
+    /// # Errors
+    /// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if any check fails. The
+    /// error payload is an [`Error`] whose contents are unstable and subject to change.
+    fn safe_parent_n(&self, n: usize) -> Result<&Self> {
+        let mut dir = self;
+        for _ in 0..n {
+            dir = dir
+                .safe_parent()?
+                .ok_or_else(|| Error::new("unsafe parent operation"))?;
+        }
+        Ok(dir)
+    }
+
+    /// Like [`SafePath::safe_parent_n`], but also returns an error if any step leaves `base`, i.e.,
+    /// produces a path that does not start with `base`. Paths are compared lexically, so `self` and
+    /// `base` should be normalized.
+    ///
+    /// ```
+    /// # use safe_path::SafePath;
+    /// # use std::path::Path;
+    /// let workspace = Path::new("/ws");
+    /// let manifest_dir = Path::new("/ws/crates/x");
+    /// assert_eq!(manifest_dir.safe_parent_n_within(2, workspace).unwrap(), workspace);
+    /// assert!(manifest_dir.safe_parent_n_within(3, workspace).is_err());
+    /// ```
+    /// # Errors
+    /// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if any check fails. The
+    /// error payload is an [`Error`] whose contents are unstable and subject to change.
+    fn safe_parent_n_within(&self, n: usize, base: &Self) -> Result<&Self> {
+        let mut dir = self;
+        for _ in 0..n {
+            dir = dir
+                .safe_parent()?
+                .ok_or_else(|| Error::new("unsafe parent operation"))?;
+            if !matches!(base.as_std_path(), Some(base) if dir.starts_with(base)) {
+                return Err(Error::new("unsafe parent operation").into());
+            }
+        }
+        Ok(dir)
+    }
 }
 
 impl<P: ?Sized + PathOps> SafePath for P {}
 
+/// Like [`PathOps::check_join_safety`] with `relaxed` set to false, but for a path received as raw
+/// bytes, e.g., by an FTP or `WebDAV` server. On Unix, `raw` is converted to a path without
+/// interpretation, so it need not be valid UTF-8. On other platforms, `raw` must be valid UTF-8.
+/// ```
+/// # use safe_path::check_join_safety_bytes;
+/// # use std::path::Path;
+/// assert!(check_join_safety_bytes(Path::new("srv"), b"x/y").is_ok());
+/// assert!(check_join_safety_bytes(Path::new("srv"), b"x/../../y").is_err());
+/// # #[cfg(unix)]
+/// assert!(check_join_safety_bytes(Path::new("srv"), b"caf\xe9").is_ok());
+/// ```
+/// # Errors
+/// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if the check fails. The
+/// error payload is an [`Error`] whose contents are unstable and subject to change.
+#[cfg(feature = "std-path")]
+pub fn check_join_safety_bytes(base: &std::path::Path, raw: &[u8]) -> Result<()> {
+    #[cfg(unix)]
+    let path = <std::ffi::OsStr as std::os::unix::ffi::OsStrExt>::from_bytes(raw);
+    #[cfg(not(unix))]
+    let path = std::str::from_utf8(raw).map_err(|_| {
+        Error::new("invalid request path").with_violation(Violation::InvalidEncoding)
+    })?;
+    base.check_join_safety(std::path::Path::new(path), false)
+}
+
+#[cfg(any(feature = "std-path", feature = "camino"))]
+macro_rules! component_kinds {
+    {$component_ty: path, $components: expr} => {{
+        use $component_ty as Component;
+        $components.map(|component| match component {
+            Component::Prefix(prefix) if prefix.kind().is_verbatim() => {
+                ComponentKind::VerbatimPrefix
+            }
+            Component::Prefix(_) => ComponentKind::Prefix,
+            Component::RootDir => ComponentKind::RootDir,
+            Component::CurDir => ComponentKind::CurDir,
+            Component::ParentDir => ComponentKind::ParentDir,
+            Component::Normal(_) => ComponentKind::Normal,
+        })
+    }};
+}
+
+// smoelius: The backend does not see prefixes' contents, so an argument's prefix is compared to the
+// receiver's here.
+#[cfg(any(feature = "std-path", feature = "camino"))]
+fn check_prefix(dir: &std::path::Path, path: &std::path::Path) -> Result<()> {
+    match prefix::mismatch(dir, path) {
+        Some(index) => Err(Error::new("unsafe join operation")
+            .with_violation(Violation::PrefixMismatch { index })
+            .into()),
+        None => Ok(()),
+    }
+}
+
+#[cfg(any(feature = "std-path", feature = "camino"))]
 macro_rules! impl_body {
     {$component_ty: path} => {
         fn join<P: AsRef<Self>>(&self, path: P) -> Self::PathBuf {
@@ -283,91 +786,95 @@ macro_rules! impl_body {
             Self::parent(self)
         }
 
+        fn with_file_name<P: AsRef<Self>>(&self, file_name: P) -> Self::PathBuf {
+            Self::with_file_name(self, file_name.as_ref())
+        }
+
+        fn with_extension<P: AsRef<Self>>(&self, extension: P) -> Option<Self::PathBuf> {
+            Some(Self::with_extension(self, extension.as_ref()))
+        }
+
         fn starts_with<P: AsRef<std::path::Path>>(&self, base: P) -> bool {
             Self::starts_with(self, base)
         }
 
         fn check_join_safety(&self, path: &Self, relaxed: bool) -> Result<()> {
-            use $component_ty as Component;
-            let err = Err(Error::new(
-                ErrorKind::Other,
-                String::from("unsafe join operation"),
-            ));
-            let mut n = 0;
-            for component in path.components() {
-                match component {
-                    Component::Prefix(_) | Component::RootDir => {
-                        if !self.is_root() {
-                            return err;
-                        }
-                        n = 0;
-                    }
-                    Component::CurDir => {}
-                    Component::ParentDir => {
-                        if n <= 0 {
-                            if !self.is_root() {
-                                return err;
-                            }
-                            continue;
-                        }
-                        n -= 1;
-                    }
-                    Component::Normal(_) => n += 1,
-                }
-            }
-            if n > 0 || (relaxed && n == 0) {
-                Ok(())
-            } else {
-                err
+            check_prefix(self.as_ref(), path.as_ref())?;
+            backend::check_join_safety(
+                component_kinds! {$component_ty, path.components()},
+                relaxed,
+                || self.is_root(),
+            )
+        }
+
+        fn check_join_safety_with_root(
+            &self,
+            path: &Self,
+            relaxed: bool,
+            self_is_root: bool,
+        ) -> Result<()> {
+            check_prefix(self.as_ref(), path.as_ref())?;
+            backend::check_join_safety(
+                component_kinds! {$component_ty, path.components()},
+                relaxed,
+                || self_is_root,
+            )
+        }
+
+        fn explain_join_safety(&self, path: &Self) -> Vec<Violation> {
+            let mut violations = backend::explain_join_safety(
+                component_kinds! {$component_ty, path.components()},
+                || self.is_root(),
+            );
+            if let Some(index) = prefix::mismatch(self.as_ref(), path.as_ref()) {
+                violations.retain(|violation| *violation != Violation::SelfJoin);
+                violations.insert(0, Violation::PrefixMismatch { index });
             }
+            violations
         }
 
         fn check_parent_safety(&self, relaxed: bool) -> Result<()> {
-            use $component_ty as Component;
-            let err = Err(Error::new(
-                ErrorKind::Other,
-                String::from("unsafe parent operation"),
-            ));
-            match self.components().next_back() {
-                None | Some(Component::Prefix(_) | Component::RootDir | Component::CurDir) => {
-                    if relaxed {
-                        Ok(())
-                    } else {
-                        err
-                    }
-                }
-                Some(Component::ParentDir) => {
-                    if relaxed && self.parent().map_or(true, |parent| parent.is_root()) {
-                        Ok(())
-                    } else {
-                        err
-                    }
-                }
-                Some(Component::Normal(_)) => Ok(()),
-            }
+            backend::check_parent_safety(
+                component_kinds! {$component_ty, self.components().next_back()},
+                relaxed,
+                || self.parent().map_or(true, |parent| parent.is_root()),
+            )
         }
 
         fn is_root(&self) -> bool {
-            use $component_ty as Component;
-            let mut n: Option<i32> = None;
-            for component in self.components() {
-                match component {
-                    Component::Prefix(_) | Component::RootDir => {
-                        n = Some(0);
-                    }
-                    Component::CurDir => {}
-                    Component::ParentDir => n = n.map(|n| if n <= 0 { n } else { n - 1 }),
-                    Component::Normal(_) => n = n.map(|n| n + 1),
-                }
+            // smoelius: The backend cannot distinguish `C:`, which is drive-relative, from
+            // `\\server\share`, which is not. `root_of` can.
+            self.root_of().is_some()
+                && backend::is_root(component_kinds! {$component_ty, self.components()})
+        }
+
+        fn root_of(&self) -> Option<&Self> {
+            if self.has_root() {
+                self.ancestors().last()
+            } else {
+                None
             }
-            n == Some(0)
+        }
+
+        fn is_file_name(&self) -> bool {
+            backend::is_file_name(component_kinds! {$component_ty, self.components()})
+        }
+
+        fn collapse(&self) -> Option<Self::PathBuf> {
+            // smoelius: `components` already drops the separators and `.` components in question.
+            Some(self.components().collect())
         }
     }
 }
 
+#[cfg(feature = "std-path")]
 impl PathOps for std::path::Path {
     type PathBuf = std::path::PathBuf;
 
+    fn as_std_path(&self) -> Option<&std::path::Path> {
+        Some(self)
+    }
+
     impl_body! {std::path::Component}
 }
 
@@ -375,5 +882,9 @@ impl PathOps for std::path::Path {
 impl PathOps for camino::Utf8Path {
     type PathBuf = camino::Utf8PathBuf;
 
+    fn as_std_path(&self) -> Option<&std::path::Path> {
+        Some(Self::as_std_path(self))
+    }
+
     impl_body! {camino::Utf8Component}
 }
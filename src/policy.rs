@@ -0,0 +1,1093 @@
+use crate::{sanitize::is_windows_reserved, Error, PathOps, Violation};
+use std::{
+    borrow::Cow,
+    collections::BTreeSet,
+    ffi::{OsStr, OsString},
+    fmt::{Debug, Display, Formatter},
+    io::Result,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+#[cfg(feature = "typed-path")]
+use typed_path::{Utf8WindowsComponent, Utf8WindowsPath};
+#[cfg(feature = "unicode-normalization")]
+use unicode_normalization::UnicodeNormalization;
+
+#[cfg(feature = "serde")]
+mod config;
+
+/// Options for [`crate::SafePath::safe_join_with`]
+///
+/// `Policy::new()` (or `Policy::default()`) imposes the same requirements as
+/// [`crate::SafePath::safe_join`]. Each of the builder methods adjusts one requirement:
+/// ```
+/// # use safe_path::{Policy, SafePath};
+/// # use std::path::Path;
+/// let policy = Policy::new().relaxed(true).no_parent(true);
+/// assert!(Path::new("x").safe_join_with(&policy, ".").is_ok());
+/// assert!(Path::new("x").safe_join_with(&policy, "y/../z").is_err());
+/// ```
+///
+/// With the `serde` feature, a `Policy` implements `Serialize` and `Deserialize`, so that it can be
+/// embedded in a service's configuration. Keys are named after the builder methods, e.g.,
+/// `no_hidden`, except that lists are plural (`deny`, `allow_extensions`, and `deny_extensions`
+/// are lists) and a decoy directory is given by `divert`. Unknown keys and conflicting rules are
+/// rejected. A `Policy` with user-supplied checks cannot be serialized.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Policy {
+    relaxed: bool,
+    strict: bool,
+    allow_absolute_self: bool,
+    no_parent: bool,
+    no_hidden: bool,
+    no_invisible: bool,
+    no_control: bool,
+    no_env_vars: bool,
+    no_tilde: bool,
+    no_windows_reserved: bool,
+    no_streams: bool,
+    no_trailing_dot_or_space: bool,
+    no_short_names: bool,
+    case_insensitive: bool,
+    #[cfg(feature = "unicode-normalization")]
+    normalization: Option<NormalizationForm>,
+    denied: Vec<OsString>,
+    max_len: Option<usize>,
+    max_depth: Option<usize>,
+    max_component_len: Option<usize>,
+    allowed_chars: Option<BTreeSet<char>>,
+    allowed_extensions: Vec<OsString>,
+    denied_extensions: Vec<OsString>,
+    action: Action,
+    separators: Separators,
+    component_checks: Checks<dyn ComponentCheck>,
+    path_checks: Checks<dyn PathCheck>,
+}
+
+/// A user-supplied rule applied to each component of an argument. See [`Policy::component_check`].
+pub trait ComponentCheck: Debug + Send + Sync {
+    /// Returns `Err(reason)` if `component`, at `index` in the argument, should be rejected.
+    /// # Errors
+    /// Returns `reason` if the component should be rejected.
+    fn check_component(
+        &self,
+        index: usize,
+        component: Component<'_>,
+    ) -> std::result::Result<(), &'static str>;
+}
+
+/// A user-supplied rule applied to the result of a join. See [`Policy::path_check`].
+pub trait PathCheck: Debug + Send + Sync {
+    /// Returns `Err(reason)` if `dir.join(path)`, which is passed as `result`, should be rejected.
+    /// # Errors
+    /// Returns `reason` if the result should be rejected.
+    fn check_path(&self, dir: &Path, result: &Path) -> std::result::Result<(), &'static str>;
+}
+
+// smoelius: Checks are compared by identity, so that `Policy` can continue to implement `Eq`.
+#[derive(Debug)]
+struct Checks<T: ?Sized>(Vec<Arc<T>>);
+
+impl<T: ?Sized> Clone for Checks<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T: ?Sized> Default for Checks<T> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<T: ?Sized> PartialEq for Checks<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len() && self.0.iter().zip(&other.0).all(|(x, y)| Arc::ptr_eq(x, y))
+    }
+}
+
+impl<T: ?Sized> Eq for Checks<T> {}
+
+impl Policy {
+    /// Creates a `Policy` that imposes the same requirements as [`crate::SafePath::safe_join`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a `Policy` that additionally rejects components containing control characters or
+    /// invisible characters, i.e., `Policy::new().no_control(true).no_invisible(true)`.
+    #[must_use]
+    pub fn hardened() -> Self {
+        Self::new().no_control(true).no_invisible(true)
+    }
+
+    /// Drops the requirement that the result is not the receiver. See
+    /// [`crate::SafePath::relaxed_safe_join`].
+    #[must_use]
+    pub fn relaxed(mut self, relaxed: bool) -> Self {
+        self.relaxed = relaxed;
+        self
+    }
+
+    /// Treats the receiver as though it were not `/`. See [`crate::SafePath::strict_safe_join`].
+    #[must_use]
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Accepts an absolute argument whose components, ignoring `.` components, are the same as the
+    /// receiver's, e.g., `/srv/www/.` when the receiver is `/srv/www`. The result of the join is
+    /// then the argument, which refers to the receiver, as with [`Policy::relaxed`]. Other checks
+    /// are skipped for such arguments. Arguments containing `..` are not accepted this way.
+    /// ```
+    /// # use safe_path::{Policy, SafePath};
+    /// # use std::path::Path;
+    /// let policy = Policy::new().allow_absolute_self(true);
+    /// let dir = Path::new("/srv/www");
+    /// assert_eq!(dir.safe_join_with(&policy, "/srv/www/").unwrap(), Path::new("/srv/www"));
+    /// assert!(dir.safe_join_with(&policy, "/srv/www/x").is_err());
+    /// assert!(dir.safe_join_with(&policy, "/srv/www/x/..").is_err());
+    /// ```
+    #[must_use]
+    pub fn allow_absolute_self(mut self, allow_absolute_self: bool) -> Self {
+        self.allow_absolute_self = allow_absolute_self;
+        self
+    }
+
+    /// Rejects arguments containing any `..` component. See
+    /// [`crate::SafePath::no_parent_safe_join`].
+    #[must_use]
+    pub fn no_parent(mut self, no_parent: bool) -> Self {
+        self.no_parent = no_parent;
+        self
+    }
+
+    /// Rejects arguments containing a normal component that begins with `.`, e.g., `.env` or
+    /// `x/.htpasswd`. `.` and `..` components are not affected.
+    /// ```
+    /// # use safe_path::{Policy, SafePath};
+    /// # use std::path::Path;
+    /// let policy = Policy::new().no_hidden(true);
+    /// assert!(Path::new("x").safe_join_with(&policy, "./y").is_ok());
+    /// assert!(Path::new("x").safe_join_with(&policy, "y/.env").is_err());
+    /// ```
+    #[must_use]
+    pub fn no_hidden(mut self, no_hidden: bool) -> Self {
+        self.no_hidden = no_hidden;
+        self
+    }
+
+    /// Rejects arguments containing a normal component with a Unicode bidirectional control (e.g.,
+    /// U+202E RIGHT-TO-LEFT OVERRIDE) or a zero-width character (e.g., U+200B ZERO WIDTH SPACE).
+    /// Such characters can make a name display as something other than what it is, e.g., in logs.
+    /// ```
+    /// # use safe_path::{Policy, SafePath};
+    /// # use std::path::Path;
+    /// let policy = Policy::new().no_invisible(true);
+    /// assert!(Path::new("x").safe_join_with(&policy, "report.pdf").is_ok());
+    /// assert!(Path::new("x").safe_join_with(&policy, "report\u{202e}fdp.exe").is_err());
+    /// assert!(Path::new("x").safe_join_with(&policy, ".\u{200b}./y").is_err());
+    /// ```
+    #[must_use]
+    pub fn no_invisible(mut self, no_invisible: bool) -> Self {
+        self.no_invisible = no_invisible;
+        self
+    }
+
+    /// Rejects arguments containing a normal component with an ASCII control character, i.e., NUL,
+    /// another C0 control character (e.g., a newline), or DEL. Such characters can truncate a path
+    /// passed to C code, or inject content into logs and line-based protocols.
+    /// ```
+    /// # use safe_path::{Policy, SafePath};
+    /// # use std::path::Path;
+    /// let policy = Policy::new().no_control(true);
+    /// assert!(Path::new("x").safe_join_with(&policy, "y.txt").is_ok());
+    /// assert!(Path::new("x").safe_join_with(&policy, "y.txt\0.png").is_err());
+    /// assert!(Path::new("x").safe_join_with(&policy, "y\r\nz").is_err());
+    /// ```
+    #[must_use]
+    pub fn no_control(mut self, no_control: bool) -> Self {
+        self.no_control = no_control;
+        self
+    }
+
+    /// Rejects arguments containing a normal component that looks like it references an
+    /// environment variable, i.e., contains `$` followed by `{` or a name, or a name enclosed in
+    /// `%`s. Such components could be expanded if the path is later interpolated by a shell, a
+    /// batch file, or a configuration processor.
+    /// ```
+    /// # use safe_path::{Policy, SafePath};
+    /// # use std::path::Path;
+    /// let policy = Policy::new().no_env_vars(true);
+    /// assert!(Path::new("x").safe_join_with(&policy, "$5 off/100%.txt").is_ok());
+    /// assert!(Path::new("x").safe_join_with(&policy, "$HOME/.ssh").is_err());
+    /// assert!(Path::new("x").safe_join_with(&policy, "y/${HOME}").is_err());
+    /// assert!(Path::new("x").safe_join_with(&policy, "%APPDATA%").is_err());
+    /// ```
+    #[must_use]
+    pub fn no_env_vars(mut self, no_env_vars: bool) -> Self {
+        self.no_env_vars = no_env_vars;
+        self
+    }
+
+    /// Rejects arguments containing a normal component that begins with `~`, e.g., `~` or `~user`.
+    /// Such components could be expanded to a home directory if the path is later interpolated by a
+    /// shell.
+    /// ```
+    /// # use safe_path::{Policy, SafePath};
+    /// # use std::path::Path;
+    /// let policy = Policy::new().no_tilde(true);
+    /// assert!(Path::new("x").safe_join_with(&policy, "y~/z.txt~").is_ok());
+    /// assert!(Path::new("x").safe_join_with(&policy, "~/.ssh").is_err());
+    /// assert!(Path::new("x").safe_join_with(&policy, "y/~root").is_err());
+    /// ```
+    #[must_use]
+    pub fn no_tilde(mut self, no_tilde: bool) -> Self {
+        self.no_tilde = no_tilde;
+        self
+    }
+
+    /// Rejects arguments containing a normal component whose stem (the part before the first `.`)
+    /// is a name Windows reserves for a device, i.e., `CON`, `PRN`, `AUX`, `NUL`, `COM1` through
+    /// `COM9`, or `LPT1` through `LPT9`, in any case. On Windows, opening such a path opens the
+    /// device rather than a file inside the receiver.
+    /// ```
+    /// # use safe_path::{Policy, SafePath};
+    /// # use std::path::Path;
+    /// let policy = Policy::new().no_windows_reserved(true);
+    /// assert!(Path::new("x").safe_join_with(&policy, "console.txt").is_ok());
+    /// assert!(Path::new("x").safe_join_with(&policy, "y/con").is_err());
+    /// assert!(Path::new("x").safe_join_with(&policy, "Nul.txt").is_err());
+    /// assert!(Path::new("x").safe_join_with(&policy, "com1.tar.gz").is_err());
+    /// ```
+    #[must_use]
+    pub fn no_windows_reserved(mut self, no_windows_reserved: bool) -> Self {
+        self.no_windows_reserved = no_windows_reserved;
+        self
+    }
+
+    /// Rejects arguments containing a normal component with a `:`, on all platforms. On Windows,
+    /// `file.txt:hidden` names an alternate data stream of `file.txt`, and a leading `x:` can be
+    /// parsed as a drive.
+    /// ```
+    /// # use safe_path::{Policy, SafePath};
+    /// # use std::path::Path;
+    /// let policy = Policy::new().no_streams(true);
+    /// assert!(Path::new("x").safe_join_with(&policy, "file.txt").is_ok());
+    /// assert!(Path::new("x").safe_join_with(&policy, "file.txt:hidden").is_err());
+    /// assert!(Path::new("x").safe_join_with(&policy, "y/file.txt::$DATA").is_err());
+    /// ```
+    #[must_use]
+    pub fn no_streams(mut self, no_streams: bool) -> Self {
+        self.no_streams = no_streams;
+        self
+    }
+
+    /// Rejects arguments containing a normal component that ends with `.` or a space, e.g., `x.` or
+    /// `x.txt `. Windows strips such characters when opening a file, so the file opened would not
+    /// be the file that was checked, e.g., `.git.` would open `.git`.
+    /// ```
+    /// # use safe_path::{Policy, SafePath};
+    /// # use std::path::Path;
+    /// let policy = Policy::new().no_trailing_dot_or_space(true);
+    /// assert!(Path::new("x").safe_join_with(&policy, "./y/../z").is_ok());
+    /// assert!(Path::new("x").safe_join_with(&policy, "y.").is_err());
+    /// assert!(Path::new("x").safe_join_with(&policy, "y.txt /z").is_err());
+    /// ```
+    #[must_use]
+    pub fn no_trailing_dot_or_space(mut self, no_trailing_dot_or_space: bool) -> Self {
+        self.no_trailing_dot_or_space = no_trailing_dot_or_space;
+        self
+    }
+
+    /// Rejects arguments containing a normal component that looks like a Windows 8.3 short name,
+    /// i.e., a name of at most eight characters ending in `~` and a number, optionally followed by
+    /// an extension of at most three characters (e.g., `PROGRA~1` or `GIT~1.TXT`). On Windows, a
+    /// short name can refer to a file with a different long name, bypassing a deny-list.
+    /// ```
+    /// # use safe_path::{Policy, SafePath};
+    /// # use std::path::Path;
+    /// let policy = Policy::new().no_short_names(true);
+    /// assert!(Path::new("x").safe_join_with(&policy, "backup~").is_ok());
+    /// assert!(Path::new("x").safe_join_with(&policy, "PROGRA~1/y").is_err());
+    /// assert!(Path::new("x").safe_join_with(&policy, "y/GIT~1.TXT").is_err());
+    /// ```
+    #[must_use]
+    pub fn no_short_names(mut self, no_short_names: bool) -> Self {
+        self.no_short_names = no_short_names;
+        self
+    }
+
+    /// Compares components to the deny-list case-insensitively, as on the default Windows and macOS
+    /// filesystems. Names that are valid UTF-8 are compared after converting them to lowercase;
+    /// other names are compared exactly. (Containment checks are unaffected by case, and
+    /// extensions are always compared case-insensitively.)
+    /// ```
+    /// # use safe_path::{Policy, SafePath};
+    /// # use std::path::Path;
+    /// let policy = Policy::new().deny(".git").case_insensitive(true);
+    /// assert!(Path::new("repo").safe_join_with(&policy, ".GIT/config").is_err());
+    /// ```
+    #[must_use]
+    pub fn case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive = case_insensitive;
+        self
+    }
+
+    /// Normalizes each normal component that is valid UTF-8 to `form` before examining it, and
+    /// compares components to the deny-list after normalizing both (requires the
+    /// `unicode-normalization` feature). Filesystems such as HFS+ normalize names, so without this
+    /// option, a decomposed spelling of a denied name could reach the same file. The result of the
+    /// join is not normalized.
+    /// ```
+    /// # use safe_path::{NormalizationForm, Policy, SafePath};
+    /// # use std::path::Path;
+    /// let policy = Policy::new()
+    ///     .deny("caf\u{e9}")
+    ///     .normalization(NormalizationForm::Nfc);
+    /// assert!(Path::new("x").safe_join_with(&policy, "cafe\u{301}").is_err());
+    /// ```
+    #[cfg(feature = "unicode-normalization")]
+    #[must_use]
+    pub fn normalization(mut self, form: NormalizationForm) -> Self {
+        self.normalization = Some(form);
+        self
+    }
+
+    /// Adds `name` to the deny-list. Arguments with a component equal to `name` are rejected,
+    /// regardless of where the component appears, e.g., `.git`, `x/.git`, and `.git/../x` are all
+    /// rejected if `.git` is denied.
+    /// ```
+    /// # use safe_path::{Policy, SafePath};
+    /// # use std::path::Path;
+    /// let policy = Policy::new().deny(".git").deny(".ssh");
+    /// assert!(Path::new("repo").safe_join_with(&policy, "src/main.rs").is_ok());
+    /// assert!(Path::new("repo").safe_join_with(&policy, ".git/config").is_err());
+    /// ```
+    #[must_use]
+    pub fn deny<S: AsRef<OsStr>>(mut self, name: S) -> Self {
+        self.denied.push(name.as_ref().to_os_string());
+        self
+    }
+
+    /// Rejects arguments longer than `max_len` bytes. The length is checked before the argument's
+    /// components are examined.
+    /// ```
+    /// # use safe_path::{Policy, SafePath};
+    /// # use std::path::Path;
+    /// let policy = Policy::new().max_len(8).max_depth(2).max_component_len(4);
+    /// assert!(Path::new("x").safe_join_with(&policy, "y/z").is_ok());
+    /// assert!(Path::new("x").safe_join_with(&policy, "y/z/w").is_err());
+    /// assert!(Path::new("x").safe_join_with(&policy, "yyyyy").is_err());
+    /// assert!(Path::new("x").safe_join_with(&policy, "yyyy/zzzz").is_err());
+    /// ```
+    #[must_use]
+    pub fn max_len(mut self, max_len: usize) -> Self {
+        self.max_len = Some(max_len);
+        self
+    }
+
+    /// Rejects arguments with more than `max_depth` components. Examination stops at the first
+    /// component beyond the limit.
+    #[must_use]
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Rejects arguments with a normal component longer than `max_component_len` bytes.
+    #[must_use]
+    pub fn max_component_len(mut self, max_component_len: usize) -> Self {
+        self.max_component_len = Some(max_component_len);
+        self
+    }
+
+    /// Adds `chars` to the set of characters that normal components may contain. Once this method
+    /// has been called, components containing any other character, or that are not valid UTF-8,
+    /// are rejected.
+    /// ```
+    /// # use safe_path::{Policy, SafePath};
+    /// # use std::path::Path;
+    /// let policy = Policy::new()
+    ///     .allowed_chars('A'..='Z')
+    ///     .allowed_chars('a'..='z')
+    ///     .allowed_chars('0'..='9')
+    ///     .allowed_chars("._-".chars());
+    /// assert!(Path::new("x").safe_join_with(&policy, "src/main.rs").is_ok());
+    /// assert!(Path::new("x").safe_join_with(&policy, "my file").is_err());
+    /// assert!(Path::new("x").safe_join_with(&policy, "$(id)").is_err());
+    /// ```
+    #[must_use]
+    pub fn allowed_chars<I: IntoIterator<Item = char>>(mut self, chars: I) -> Self {
+        self.allowed_chars
+            .get_or_insert_with(BTreeSet::new)
+            .extend(chars);
+        self
+    }
+
+    /// Adds `extension` (without the leading `.`) to the extension allow-list. Once this method has
+    /// been called, arguments whose [extension](std::path::Path::extension) is not on the list, or
+    /// that have no extension, are rejected. Extensions are compared ASCII-case-insensitively.
+    /// ```
+    /// # use safe_path::{Policy, SafePath};
+    /// # use std::path::Path;
+    /// let policy = Policy::new().allow_extension("png").allow_extension("jpg");
+    /// assert!(Path::new("x").safe_join_with(&policy, "y/z.PNG").is_ok());
+    /// assert!(Path::new("x").safe_join_with(&policy, "y/z.png.exe").is_err());
+    /// assert!(Path::new("x").safe_join_with(&policy, "y/z").is_err());
+    /// ```
+    #[must_use]
+    pub fn allow_extension<S: AsRef<OsStr>>(mut self, extension: S) -> Self {
+        self.allowed_extensions
+            .push(extension.as_ref().to_os_string());
+        self
+    }
+
+    /// Adds `extension` (without the leading `.`) to the extension deny-list. Arguments whose
+    /// [extension](std::path::Path::extension) is on the list are rejected. Extensions are compared
+    /// ASCII-case-insensitively.
+    #[must_use]
+    pub fn deny_extension<S: AsRef<OsStr>>(mut self, extension: S) -> Self {
+        self.denied_extensions
+            .push(extension.as_ref().to_os_string());
+        self
+    }
+
+    /// Adds `check`, which is applied to each component of an argument after the built-in rules.
+    /// ```
+    /// # use safe_path::{ComponentCheck, Policy, SafePath};
+    /// # use std::path::{Component, Path};
+    /// #[derive(Debug)]
+    /// struct Lowercase;
+    ///
+    /// impl ComponentCheck for Lowercase {
+    ///     fn check_component(&self, _: usize, component: Component<'_>) -> Result<(), &'static str> {
+    ///         match component.as_os_str().to_str() {
+    ///             Some(s) if s == s.to_lowercase() => Ok(()),
+    ///             _ => Err("not lowercase"),
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let policy = Policy::new().component_check(Lowercase);
+    /// assert!(Path::new("x").safe_join_with(&policy, "y/z").is_ok());
+    /// assert!(Path::new("x").safe_join_with(&policy, "y/Z").is_err());
+    /// ```
+    #[must_use]
+    pub fn component_check<C: ComponentCheck + 'static>(mut self, check: C) -> Self {
+        self.component_checks.0.push(Arc::new(check));
+        self
+    }
+
+    /// Adds `check`, which is applied to the result of a join after all other checks pass.
+    #[must_use]
+    pub fn path_check<C: PathCheck + 'static>(mut self, check: C) -> Self {
+        self.path_checks.0.push(Arc::new(check));
+        self
+    }
+
+    /// Sets what happens when a check fails. The default is [`Action::Reject`]. The action is
+    /// honored by `fs::SafeDir` (requires the `fs` feature); [`Policy::check`] and
+    /// [`crate::SafePath::safe_join_with`] always return an error.
+    #[must_use]
+    pub fn on_reject(mut self, action: Action) -> Self {
+        self.action = action;
+        self
+    }
+
+    /// What happens when a check fails. See [`Policy::on_reject`].
+    #[must_use]
+    pub fn rejection_action(&self) -> &Action {
+        &self.action
+    }
+
+    /// Sets how repeated separators (e.g., `a//b`), `.` components other than a leading one (e.g.,
+    /// `a/./b`), and trailing separators in the argument are treated. The default is
+    /// [`Separators::Preserve`].
+    /// ```
+    /// # use safe_path::{Policy, SafePath, Separators};
+    /// # use std::path::Path;
+    /// let policy = Policy::new().separators(Separators::Normalize);
+    /// assert_eq!(Path::new("x").safe_join_with(&policy, "y//./z/").unwrap(), Path::new("x/y/z"));
+    /// let policy = Policy::new().separators(Separators::Reject);
+    /// assert!(Path::new("x").safe_join_with(&policy, "y/").is_err());
+    /// ```
+    #[must_use]
+    pub fn separators(mut self, separators: Separators) -> Self {
+        self.separators = separators;
+        self
+    }
+
+    /// How redundant separators are treated. See [`Policy::separators`].
+    #[must_use]
+    pub fn separator_handling(&self) -> Separators {
+        self.separators
+    }
+
+    /// The version of the semantics [`Policy::check`] implements. Errors returned by
+    /// [`Policy::check`] report this version via [`Error::policy_version`]. See [`PolicyVersion`].
+    #[must_use]
+    pub fn version(&self) -> PolicyVersion {
+        PolicyVersion::CURRENT
+    }
+
+    /// Returns `Ok(())` if `dir.join(path)` satisfies `self`'s requirements.
+    /// # Errors
+    /// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if the check fails. The
+    /// error payload is an [`Error`] whose contents are unstable and subject to change. The check
+    /// always fails if [`PathOps::as_std_path`] returns `None` for `dir` or `path`.
+    pub fn check<P: ?Sized + PathOps>(&self, dir: &P, path: &P) -> Result<()> {
+        self.check_unversioned(dir, path)
+            .map_err(|error| Error::map(error, |error| error.with_policy_version(self.version())))
+    }
+
+    /// Like [`Policy::check`], but interprets `dir` and `path` as Windows paths. See
+    /// [`crate::windows::safe_join_with`].
+    #[cfg(feature = "typed-path")]
+    pub(crate) fn check_windows(
+        &self,
+        dir: &Utf8WindowsPath,
+        path: &Utf8WindowsPath,
+    ) -> Result<()> {
+        self.check_windows_unversioned(dir, path)
+            .map_err(|error| Error::map(error, |error| error.with_policy_version(self.version())))
+    }
+
+    fn check_unversioned<P: ?Sized + PathOps>(&self, dir: &P, path: &P) -> Result<()> {
+        let (dir_std, path_std) = match (dir.as_std_path(), path.as_std_path()) {
+            (Some(dir_std), Some(path_std)) => (dir_std, path_std),
+            _ => return Err(Error::new("path type has no `std::path::Path` conversion").into()),
+        };
+        self.check_limits(path_std.as_os_str().len(), path_std.components())?;
+        if self.allow_absolute_self
+            && path_std.is_absolute()
+            && significant_components(path_std).eq(significant_components(dir_std))
+        {
+            return Ok(());
+        }
+        if self.separators == Separators::Reject {
+            let lossy = path_std.to_string_lossy();
+            let prefix_len = match path_std.components().next() {
+                Some(Component::Prefix(prefix)) => prefix.as_os_str().to_string_lossy().len(),
+                _ => 0,
+            };
+            let rest = lossy.get(prefix_len..).unwrap_or_default();
+            if has_redundant_separator(rest, std::path::is_separator) {
+                return Err(rejection(Violation::RedundantSeparator));
+            }
+        }
+        self.check_components(path_std.components())?;
+        if self.strict {
+            dir.check_join_safety_with_root(path, self.relaxed, false)?;
+        } else {
+            dir.check_join_safety(path, self.relaxed)?;
+        }
+        self.check_result(dir_std, || dir_std.join(path_std))
+    }
+
+    #[cfg(feature = "typed-path")]
+    fn check_windows_unversioned(
+        &self,
+        dir: &Utf8WindowsPath,
+        path: &Utf8WindowsPath,
+    ) -> Result<()> {
+        self.check_limits(
+            path.as_str().len(),
+            path.components().map(crate::windows::std_component),
+        )?;
+        if self.allow_absolute_self
+            && path.is_absolute()
+            && significant_windows_components(path).eq(significant_windows_components(dir))
+        {
+            return Ok(());
+        }
+        if self.separators == Separators::Reject {
+            let prefix_len = match path.components().next() {
+                Some(Utf8WindowsComponent::Prefix(prefix)) => prefix.as_str().len(),
+                _ => 0,
+            };
+            let rest = path.as_str().get(prefix_len..).unwrap_or_default();
+            if has_redundant_separator(rest, |c| c == '\\' || c == '/') {
+                return Err(rejection(Violation::RedundantSeparator));
+            }
+        }
+        self.check_components(path.components().map(crate::windows::std_component))?;
+        crate::windows::check_join_safety(
+            dir,
+            path,
+            self.relaxed,
+            if self.strict { Some(false) } else { None },
+        )?;
+        self.check_result(Path::new(dir.as_str()), || {
+            PathBuf::from(dir.join(path).into_string())
+        })
+    }
+
+    /// Performs the checks on `path`'s length, depth, and component lengths. `len` is `path`'s
+    /// length in bytes.
+    // smoelius: These checks run before any other, so that an oversized argument is rejected before
+    // any work proportional to its size is done.
+    fn check_limits<'a, I>(&self, len: usize, components: I) -> Result<()>
+    where
+        I: IntoIterator<Item = Component<'a>>,
+    {
+        if exceeds(self.max_len, len) {
+            return Err(rejection(Violation::TooLong));
+        }
+        if self.max_depth.is_none() && self.max_component_len.is_none() {
+            return Ok(());
+        }
+        for (index, component) in components.into_iter().enumerate() {
+            if exceeds(self.max_depth, index + 1) {
+                return Err(rejection(Violation::TooDeep));
+            }
+            if let Component::Normal(name) = component {
+                if exceeds(self.max_component_len, name.len()) {
+                    return Err(rejection(Violation::ComponentTooLong { index }));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Performs the checks on `path`'s components.
+    fn check_components<'a, I>(&self, components: I) -> Result<()>
+    where
+        I: IntoIterator<Item = Component<'a>>,
+    {
+        let mut last = None;
+        for (index, component) in components.into_iter().enumerate() {
+            last = Some(component);
+            #[cfg(feature = "unicode-normalization")]
+            let normalized = match component {
+                Component::Normal(name) => name.to_str().and_then(|name| self.normalize(name)),
+                _ => None,
+            };
+            #[cfg(feature = "unicode-normalization")]
+            let component = normalized
+                .as_ref()
+                .map_or(component, |name| Component::Normal(OsStr::new(name)));
+            if let Some(violation) = self.check_component(index, component) {
+                return Err(rejection(violation));
+            }
+            for check in &self.component_checks.0 {
+                if let Err(reason) = check.check_component(index, component) {
+                    return Err(rejection(Violation::Custom {
+                        index: Some(index),
+                        reason,
+                    }));
+                }
+            }
+        }
+        // smoelius: This agrees with `Path::extension`, which considers only a final normal
+        // component.
+        let extension = match last {
+            Some(Component::Normal(name)) => Path::new(name).extension(),
+            _ => None,
+        };
+        if !self.extension_allowed(extension) {
+            return Err(rejection(Violation::DisallowedExtension));
+        }
+        Ok(())
+    }
+
+    /// Runs the [`PathCheck`]s on `result`, which is computed only if there are any.
+    fn check_result<F>(&self, dir: &Path, result: F) -> Result<()>
+    where
+        F: FnOnce() -> PathBuf,
+    {
+        if self.path_checks.0.is_empty() {
+            return Ok(());
+        }
+        let result = result();
+        for check in &self.path_checks.0 {
+            if let Err(reason) = check.check_path(dir, &result) {
+                return Err(rejection(Violation::Custom {
+                    index: None,
+                    reason,
+                }));
+            }
+        }
+        Ok(())
+    }
+
+    fn check_component(&self, index: usize, component: Component<'_>) -> Option<Violation> {
+        match component {
+            Component::ParentDir if self.no_parent => Some(Violation::ParentDir { index }),
+            Component::Normal(name)
+                if self.no_hidden && name.to_string_lossy().starts_with('.') =>
+            {
+                Some(Violation::HiddenComponent { index })
+            }
+            Component::Normal(name)
+                if self.no_trailing_dot_or_space
+                    && name.to_string_lossy().ends_with(['.', ' '].as_ref()) =>
+            {
+                Some(Violation::TrailingDotOrSpace { index })
+            }
+            Component::Normal(name)
+                if self.no_short_names && is_short_name(&name.to_string_lossy()) =>
+            {
+                Some(Violation::ShortName { index })
+            }
+            Component::Normal(name)
+                if self.no_windows_reserved && is_windows_reserved(&name.to_string_lossy()) =>
+            {
+                Some(Violation::ReservedName { index })
+            }
+            Component::Normal(name)
+                if self
+                    .denied
+                    .iter()
+                    .any(|denied| self.names_match(denied, name)) =>
+            {
+                Some(Violation::DeniedComponent { index })
+            }
+            // smoelius: Lossy conversion preserves ASCII characters and the invisible characters,
+            // so it suffices for the next three checks.
+            Component::Normal(name)
+                if self.no_control
+                    && name.to_string_lossy().chars().any(|c| c.is_ascii_control()) =>
+            {
+                Some(Violation::ControlChar { index })
+            }
+            Component::Normal(name)
+                if self.no_invisible && name.to_string_lossy().chars().any(is_invisible) =>
+            {
+                Some(Violation::InvisibleChar { index })
+            }
+            Component::Normal(name) if self.no_streams && name.to_string_lossy().contains(':') => {
+                Some(Violation::Stream { index })
+            }
+            Component::Normal(name) if !self.chars_allowed(name) => {
+                Some(Violation::DisallowedChar { index })
+            }
+            Component::Normal(name)
+                if self.no_env_vars && references_env_var(&name.to_string_lossy()) =>
+            {
+                Some(Violation::EnvVar { index })
+            }
+            Component::Normal(name) if self.no_tilde && name.to_string_lossy().starts_with('~') => {
+                Some(Violation::Tilde { index })
+            }
+            _ => None,
+        }
+    }
+
+    fn names_match(&self, x: &OsStr, y: &OsStr) -> bool {
+        match (x.to_str(), y.to_str()) {
+            (Some(x), Some(y)) if self.case_insensitive => {
+                self.fold(x).to_lowercase() == self.fold(y).to_lowercase()
+            }
+            (Some(x), Some(y)) => self.fold(x) == self.fold(y),
+            _ => x == y,
+        }
+    }
+
+    fn fold<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        self.normalize(name).map_or(Cow::Borrowed(name), Cow::Owned)
+    }
+
+    #[cfg(feature = "unicode-normalization")]
+    fn normalize(&self, name: &str) -> Option<String> {
+        match self.normalization? {
+            NormalizationForm::Nfc => Some(name.nfc().collect()),
+            NormalizationForm::Nfd => Some(name.nfd().collect()),
+        }
+    }
+
+    #[cfg(not(feature = "unicode-normalization"))]
+    #[allow(clippy::unused_self)]
+    fn normalize(&self, _: &str) -> Option<String> {
+        None
+    }
+
+    fn extension_allowed(&self, extension: Option<&OsStr>) -> bool {
+        let on = |list: &[OsString]| match extension {
+            Some(extension) => list.iter().any(|other| extensions_match(extension, other)),
+            None => false,
+        };
+        (self.allowed_extensions.is_empty() || on(&self.allowed_extensions))
+            && !on(&self.denied_extensions)
+    }
+
+    fn chars_allowed(&self, name: &OsStr) -> bool {
+        match (&self.allowed_chars, name.to_str()) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(allowed_chars), Some(name)) => name.chars().all(|c| allowed_chars.contains(&c)),
+        }
+    }
+}
+
+fn extensions_match(x: &OsStr, y: &OsStr) -> bool {
+    match (x.to_str(), y.to_str()) {
+        (Some(x), Some(y)) => x.eq_ignore_ascii_case(y),
+        _ => x == y,
+    }
+}
+
+/// Returns true if `c` is a bidirectional control or a zero-width character.
+fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{061c}'
+            | '\u{180e}'
+            | '\u{200b}'..='\u{200f}'
+            | '\u{202a}'..='\u{202e}'
+            | '\u{2060}'..='\u{2064}'
+            | '\u{2066}'..='\u{2069}'
+            | '\u{feff}'
+    )
+}
+
+/// Returns true if `name` has the form of a Windows 8.3 short name, e.g., `PROGRA~1` or
+/// `GIT~1.TXT`.
+fn is_short_name(name: &str) -> bool {
+    let mut parts = name.splitn(2, '.');
+    let stem = parts.next().unwrap_or_default();
+    let extension = parts.next().unwrap_or_default();
+    let (base, number) = match stem.rfind('~') {
+        Some(i) => (&stem[..i], &stem[i + 1..]),
+        None => return false,
+    };
+    stem.chars().count() <= 8
+        && !base.is_empty()
+        && !number.is_empty()
+        && number.bytes().all(|byte| byte.is_ascii_digit())
+        && extension.chars().count() <= 3
+        && !extension.contains('.')
+}
+
+/// Returns true if `name` contains `$` followed by `{` or a name, or a name enclosed in `%`s.
+fn references_env_var(name: &str) -> bool {
+    let is_start = |c: char| c.is_ascii_alphabetic() || c == '_';
+    let mut chars = name.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('$', Some(&next)) if next == '{' || is_start(next) => return true,
+            ('%', Some(&next)) if is_start(next) => {
+                let mut rest = chars.clone();
+                if rest.find(|&c| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '(' | ')')))
+                    == Some('%')
+                {
+                    return true;
+                }
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Returns true if `rest`, a path without its prefix, contains a repeated separator, a `.`
+/// component other than a leading one, or a trailing separator.
+fn has_redundant_separator<F>(rest: &str, is_separator: F) -> bool
+where
+    F: Fn(char) -> bool,
+{
+    let rest = rest.strip_prefix(&is_separator).unwrap_or(rest);
+    !rest.is_empty()
+        && rest
+            .split(&is_separator)
+            .enumerate()
+            .any(|(index, name)| name.is_empty() || (index > 0 && name == "."))
+}
+
+fn significant_components(path: &Path) -> impl Iterator<Item = Component<'_>> {
+    path.components()
+        .filter(|component| *component != Component::CurDir)
+}
+
+#[cfg(feature = "typed-path")]
+fn significant_windows_components(
+    path: &Utf8WindowsPath,
+) -> impl Iterator<Item = Utf8WindowsComponent<'_>> {
+    path.components()
+        .filter(|component| *component != Utf8WindowsComponent::CurDir)
+}
+
+fn exceeds(limit: Option<usize>, n: usize) -> bool {
+    matches!(limit, Some(limit) if n > limit)
+}
+
+fn rejection(violation: Violation) -> std::io::Error {
+    Error::new("unsafe join operation")
+        .with_violation(violation)
+        .into()
+}
+
+/// A Unicode normalization form. See [`Policy::normalization`].
+#[cfg(feature = "unicode-normalization")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum NormalizationForm {
+    /// Canonical composition, e.g., `é` is one code point
+    Nfc,
+    /// Canonical decomposition, e.g., `é` is `e` followed by a combining acute accent, as on HFS+
+    Nfd,
+}
+
+/// What happens when a [`Policy`]'s check fails. See [`Policy::on_reject`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Action {
+    /// Return an error
+    Reject,
+    /// Redirect the operation into the contained decoy directory, e.g., for deception-based
+    /// detection. The rejected argument is converted into a single file name with
+    /// [`crate::sanitize::sanitize`] and joined onto the decoy directory, so the result is always
+    /// directly inside it.
+    Divert(PathBuf),
+}
+
+// smoelius: `#[default]` on enum variants requires Rust 1.62.
+#[allow(clippy::derivable_impls)]
+impl Default for Action {
+    fn default() -> Self {
+        Self::Reject
+    }
+}
+
+/// Generates a `Policy` with arbitrary options and limits. The generated policy has no deny-lists,
+/// allow-lists, or user-supplied checks, and its rejection action is the default.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Policy {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Self::generate(u, |u, n| u.int_in_range(0..=n - 1))
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Separators {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(*u.choose(&[Self::Preserve, Self::Normalize, Self::Reject])?)
+    }
+}
+
+/// Generates a `Policy` as the `arbitrary::Arbitrary` implementation does
+#[cfg(feature = "quickcheck")]
+impl quickcheck::Arbitrary for Policy {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        Self::generate(g, |g, n| {
+            Ok::<_, std::convert::Infallible>(usize::arbitrary(g) % n)
+        })
+        .unwrap_or_else(|never| match never {})
+    }
+}
+
+#[cfg(any(feature = "arbitrary", feature = "quickcheck"))]
+impl Policy {
+    // smoelius: `choose(g, n)` returns a random integer less than `n`. Both the `arbitrary` and
+    // `quickcheck` implementations generate a `Policy` with this function, so that they agree.
+    fn generate<G, E>(
+        g: &mut G,
+        choose: fn(&mut G, usize) -> std::result::Result<usize, E>,
+    ) -> std::result::Result<Self, E> {
+        let flag = |g: &mut G| choose(g, 2).map(|n| n == 1);
+        let separators = [
+            Separators::Preserve,
+            Separators::Normalize,
+            Separators::Reject,
+        ];
+        let mut policy = Self::new()
+            .relaxed(flag(g)?)
+            .strict(flag(g)?)
+            .allow_absolute_self(flag(g)?)
+            .no_parent(flag(g)?)
+            .no_hidden(flag(g)?)
+            .no_invisible(flag(g)?)
+            .no_control(flag(g)?)
+            .no_env_vars(flag(g)?)
+            .no_tilde(flag(g)?)
+            .no_windows_reserved(flag(g)?)
+            .no_streams(flag(g)?)
+            .no_trailing_dot_or_space(flag(g)?)
+            .no_short_names(flag(g)?)
+            .case_insensitive(flag(g)?)
+            .separators(separators[choose(g, 3)?]);
+        if flag(g)? {
+            policy = policy.max_len(choose(g, 256)?);
+        }
+        if flag(g)? {
+            policy = policy.max_depth(choose(g, 256)?);
+        }
+        if flag(g)? {
+            policy = policy.max_component_len(choose(g, 256)?);
+        }
+        Ok(policy)
+    }
+}
+
+#[cfg(feature = "quickcheck")]
+impl quickcheck::Arbitrary for Separators {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        *g.choose(&[Self::Preserve, Self::Normalize, Self::Reject])
+            .unwrap()
+    }
+}
+
+/// How a [`Policy`] treats redundant separators. See [`Policy::separators`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "snake_case")
+)]
+#[non_exhaustive]
+pub enum Separators {
+    /// Leave them in the result
+    Preserve,
+    /// Remove them from the result with [`crate::PathOps::collapse`]
+    Normalize,
+    /// Return an error
+    Reject,
+}
+
+#[allow(clippy::derivable_impls)]
+impl Default for Separators {
+    fn default() -> Self {
+        Self::Preserve
+    }
+}
+
+/// Identifies the semantics of [`Policy::check`]
+///
+/// The version is incremented whenever a release of this crate changes which paths some policy
+/// accepts, e.g., because a check was fixed or tightened. Adding a new, opt-in rule to [`Policy`]
+/// does not change the version, since existing policies accept the same paths. Systems that store
+/// validated paths (e.g., manifests or signed verdicts) can store the version alongside them and
+/// re-validate when [`Policy::version`] differs from the stored version.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PolicyVersion(u32);
+
+impl PolicyVersion {
+    /// The version implemented by this release
+    pub const CURRENT: Self = Self(1);
+
+    /// Creates a `PolicyVersion` from a stored version number.
+    #[must_use]
+    pub const fn new(version: u32) -> Self {
+        Self(version)
+    }
+
+    /// The version number
+    #[must_use]
+    pub const fn get(self) -> u32 {
+        self.0
+    }
+}
+
+impl Display for PolicyVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "v{}", self.0)
+    }
+}
@@ -0,0 +1,279 @@
+use crate::{escape::escape, Action, Policy, Separators};
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    convert::TryFrom,
+    path::{Component, Path, PathBuf},
+};
+
+#[cfg(any(feature = "json", feature = "toml"))]
+use std::io::{self, Result};
+
+#[cfg(feature = "unicode-normalization")]
+use crate::NormalizationForm;
+
+/// The serialized representation of a [`Policy`]. Keys are named after the corresponding builder
+/// methods. User-supplied checks cannot be represented.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+struct Config {
+    relaxed: bool,
+    strict: bool,
+    allow_absolute_self: bool,
+    no_parent: bool,
+    no_hidden: bool,
+    no_invisible: bool,
+    no_control: bool,
+    no_env_vars: bool,
+    no_tilde: bool,
+    no_windows_reserved: bool,
+    no_streams: bool,
+    no_trailing_dot_or_space: bool,
+    no_short_names: bool,
+    case_insensitive: bool,
+    #[cfg(feature = "unicode-normalization")]
+    normalization: Option<NormalizationForm>,
+    deny: Vec<String>,
+    max_len: Option<usize>,
+    max_depth: Option<usize>,
+    max_component_len: Option<usize>,
+    allowed_chars: Option<String>,
+    allow_extensions: Vec<String>,
+    deny_extensions: Vec<String>,
+    divert: Option<PathBuf>,
+    separators: Separators,
+}
+
+impl Policy {
+    /// Parses a `Policy` from TOML (requires the `toml` feature). The keys are described at
+    /// [`Policy`]:
+    /// ```
+    /// # use safe_path::{Policy, SafePath};
+    /// # use std::path::Path;
+    /// let policy = Policy::from_toml_str(
+    ///     r#"
+    ///     no_hidden = true
+    ///     deny = ["node_modules"]
+    ///     max_depth = 8
+    ///     deny_extensions = ["php"]
+    ///     separators = "normalize"
+    ///     "#,
+    /// )
+    /// .unwrap();
+    /// assert!(Path::new("www").safe_join_with(&policy, "css/site.css").is_ok());
+    /// assert!(Path::new("www").safe_join_with(&policy, "x/node_modules").is_err());
+    ///
+    /// assert!(Policy::from_toml_str("no_hiden = true").is_err());
+    /// ```
+    /// # Errors
+    /// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::InvalidData`] if `s` is not
+    /// valid TOML, contains an unknown key or a value of the wrong type, or specifies rules that
+    /// conflict, e.g., an extension that is both allowed and denied. The error's message describes
+    /// the problem.
+    #[cfg(feature = "toml")]
+    pub fn from_toml_str(s: &str) -> Result<Self> {
+        let config = toml::from_str::<Config>(s)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        Self::try_from(config).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+
+    /// Reads a `Policy` in JSON from `reader` (requires the `json` feature). The keys are described
+    /// at [`Policy`].
+    /// # Errors
+    /// Returns a [`std::io::Error`] if reading from `reader` fails, or one of `kind`
+    /// [`std::io::ErrorKind::InvalidData`] if the input is not valid JSON, contains an unknown key
+    /// or a value of the wrong type, or specifies rules that conflict. The error's message
+    /// describes the problem.
+    #[cfg(feature = "json")]
+    pub fn from_reader<R: std::io::Read>(reader: R) -> Result<Self> {
+        let config = serde_json::from_reader::<_, Config>(reader).map_err(io::Error::from)?;
+        Self::try_from(config).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+}
+
+impl TryFrom<Config> for Policy {
+    type Error = String;
+
+    fn try_from(config: Config) -> std::result::Result<Self, String> {
+        check_conflicts(&config)?;
+        let mut policy = Self::new()
+            .relaxed(config.relaxed)
+            .strict(config.strict)
+            .allow_absolute_self(config.allow_absolute_self)
+            .no_parent(config.no_parent)
+            .no_hidden(config.no_hidden)
+            .no_invisible(config.no_invisible)
+            .no_control(config.no_control)
+            .no_env_vars(config.no_env_vars)
+            .no_tilde(config.no_tilde)
+            .no_windows_reserved(config.no_windows_reserved)
+            .no_streams(config.no_streams)
+            .no_trailing_dot_or_space(config.no_trailing_dot_or_space)
+            .no_short_names(config.no_short_names)
+            .case_insensitive(config.case_insensitive)
+            .separators(config.separators);
+        #[cfg(feature = "unicode-normalization")]
+        if let Some(form) = config.normalization {
+            policy = policy.normalization(form);
+        }
+        for name in config.deny {
+            policy = policy.deny(name);
+        }
+        if let Some(max_len) = config.max_len {
+            policy = policy.max_len(max_len);
+        }
+        if let Some(max_depth) = config.max_depth {
+            policy = policy.max_depth(max_depth);
+        }
+        if let Some(max_component_len) = config.max_component_len {
+            policy = policy.max_component_len(max_component_len);
+        }
+        if let Some(allowed_chars) = config.allowed_chars {
+            policy = policy.allowed_chars(allowed_chars.chars());
+        }
+        for extension in config.allow_extensions {
+            policy = policy.allow_extension(extension);
+        }
+        for extension in config.deny_extensions {
+            policy = policy.deny_extension(extension);
+        }
+        if let Some(decoy) = config.divert {
+            policy = policy.on_reject(Action::Divert(decoy));
+        }
+        Ok(policy)
+    }
+}
+
+impl TryFrom<&Policy> for Config {
+    type Error = String;
+
+    fn try_from(policy: &Policy) -> std::result::Result<Self, String> {
+        if !policy.component_checks.0.is_empty() || !policy.path_checks.0.is_empty() {
+            return Err("a policy with user-supplied checks cannot be serialized".to_owned());
+        }
+        let strings = |names: &[std::ffi::OsString]| {
+            names
+                .iter()
+                .map(|name| {
+                    name.to_str()
+                        .map(ToOwned::to_owned)
+                        .ok_or_else(|| format!("`{}` is not valid UTF-8", escape(name)))
+                })
+                .collect::<std::result::Result<Vec<_>, _>>()
+        };
+        Ok(Self {
+            relaxed: policy.relaxed,
+            strict: policy.strict,
+            allow_absolute_self: policy.allow_absolute_self,
+            no_parent: policy.no_parent,
+            no_hidden: policy.no_hidden,
+            no_invisible: policy.no_invisible,
+            no_control: policy.no_control,
+            no_env_vars: policy.no_env_vars,
+            no_tilde: policy.no_tilde,
+            no_windows_reserved: policy.no_windows_reserved,
+            no_streams: policy.no_streams,
+            no_trailing_dot_or_space: policy.no_trailing_dot_or_space,
+            no_short_names: policy.no_short_names,
+            case_insensitive: policy.case_insensitive,
+            #[cfg(feature = "unicode-normalization")]
+            normalization: policy.normalization,
+            deny: strings(&policy.denied)?,
+            max_len: policy.max_len,
+            max_depth: policy.max_depth,
+            max_component_len: policy.max_component_len,
+            allowed_chars: policy
+                .allowed_chars
+                .as_ref()
+                .map(|chars| chars.iter().collect()),
+            allow_extensions: strings(&policy.allowed_extensions)?,
+            deny_extensions: strings(&policy.denied_extensions)?,
+            divert: match &policy.action {
+                Action::Reject => None,
+                Action::Divert(decoy) => Some(decoy.clone()),
+            },
+            separators: policy.separators,
+        })
+    }
+}
+
+/// Serializes a `Policy` with the keys described at [`Policy`]. Fails if the policy has
+/// user-supplied checks, or a name or extension that is not valid UTF-8.
+impl Serialize for Policy {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        Config::try_from(self)
+            .map_err(ser::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+/// Deserializes a `Policy` with the keys described at [`Policy`]. Fails on unknown keys and on
+/// conflicting rules, e.g., an extension that is both allowed and denied.
+impl<'de> Deserialize<'de> for Policy {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let config = Config::deserialize(deserializer)?;
+        Self::try_from(config).map_err(de::Error::custom)
+    }
+}
+
+// smoelius: Each of these would produce a policy that is valid but almost certainly not what the
+// author meant, e.g., one that rejects every argument.
+fn check_conflicts(config: &Config) -> std::result::Result<(), String> {
+    for name in &config.deny {
+        // smoelius: This is the check `FileName::new` performs.
+        if !matches!(
+            Path::new(name).components().next(),
+            Some(Component::Normal(normal)) if normal == name.as_str()
+        ) {
+            return Err(format!(
+                "denied name {:?} is not a file name, so it never matches a component",
+                name
+            ));
+        }
+    }
+    for (key, limit) in &[
+        ("max_len", config.max_len),
+        ("max_depth", config.max_depth),
+        ("max_component_len", config.max_component_len),
+    ] {
+        if *limit == Some(0) {
+            return Err(format!("`{}` is 0, so every argument is rejected", key));
+        }
+    }
+    if config.allowed_chars.as_deref() == Some("") {
+        return Err("`allowed_chars` is empty, so every argument is rejected".to_owned());
+    }
+    for extension in config
+        .allow_extensions
+        .iter()
+        .chain(&config.deny_extensions)
+    {
+        if extension.is_empty() || extension.contains(['.', '/', '\\'].as_ref()) {
+            return Err(format!(
+                "extension {:?} must be non-empty and must not contain `.` or a separator",
+                extension
+            ));
+        }
+    }
+    if let Some(extension) = config.allow_extensions.iter().find(|allowed| {
+        config
+            .deny_extensions
+            .iter()
+            .any(|denied| allowed.eq_ignore_ascii_case(denied))
+    }) {
+        return Err(format!(
+            "extension {:?} is both allowed and denied",
+            extension
+        ));
+    }
+    if !config.allow_extensions.is_empty()
+        && matches!(&config.allowed_chars, Some(chars) if !chars.contains('.'))
+    {
+        return Err(
+            "`allow_extensions` is set but `allowed_chars` does not include `.`, so every \
+             argument is rejected"
+                .to_owned(),
+        );
+    }
+    Ok(())
+}
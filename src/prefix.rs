@@ -0,0 +1,66 @@
+//! Comparison of Windows path prefixes. On other platforms, paths have no prefixes, and these
+//! functions do nothing.
+
+#[cfg(any(feature = "std-path", feature = "camino"))]
+use std::path::Path;
+#[cfg(windows)]
+use std::{
+    ffi::OsString,
+    path::{Component, Prefix, PrefixComponent},
+};
+
+/// Returns the canonical spelling of `prefix`, i.e., `C:` for a drive or `\\server\share` for a
+/// share, or `None` if `prefix` has no other spelling (e.g., `\\?\Volume{…}`).
+#[cfg(windows)]
+pub(crate) fn canonical(prefix: &PrefixComponent<'_>) -> Option<OsString> {
+    let drive = |drive: u8| format!("{}:", char::from(drive.to_ascii_uppercase())).into();
+    match prefix.kind() {
+        Prefix::Disk(letter) | Prefix::VerbatimDisk(letter) => Some(drive(letter)),
+        Prefix::DeviceNS(name) => match name.to_str().map(str::as_bytes) {
+            Some(&[letter, b':']) if letter.is_ascii_alphabetic() => Some(drive(letter)),
+            _ => None,
+        },
+        Prefix::UNC(server, share) | Prefix::VerbatimUNC(server, share) => {
+            let mut canonical = OsString::from(r"\\");
+            canonical.push(server);
+            canonical.push(r"\");
+            canonical.push(share);
+            Some(canonical)
+        }
+        Prefix::Verbatim(_) => None,
+    }
+}
+
+/// Returns the index of the first non-verbatim prefix in `path` that does not refer to the same
+/// drive or share as `dir`'s prefix. Drive letters and share names are compared
+/// case-insensitively.
+#[cfg(any(feature = "std-path", feature = "camino"))]
+#[cfg_attr(not(windows), allow(unused_variables))]
+pub(crate) fn mismatch(dir: &Path, path: &Path) -> Option<usize> {
+    #[cfg(windows)]
+    {
+        let key = |prefix: &PrefixComponent<'_>| {
+            canonical(prefix)
+                .unwrap_or_else(|| prefix.as_os_str().to_os_string())
+                .to_string_lossy()
+                .to_lowercase()
+        };
+        let dir_key = match dir.components().next() {
+            Some(Component::Prefix(prefix)) => Some(key(&prefix)),
+            _ => None,
+        };
+        // smoelius: Verbatim prefixes are rejected by the backend regardless of `dir`.
+        path.components()
+            .enumerate()
+            .find_map(|(index, component)| match component {
+                Component::Prefix(prefix)
+                    if !prefix.kind().is_verbatim() && dir_key.as_ref() != Some(&key(&prefix)) =>
+                {
+                    Some(index)
+                }
+                _ => None,
+            })
+    }
+    #[cfg(not(windows))]
+    None
+}
@@ -0,0 +1,113 @@
+//! Records of checks for audit pipelines (requires the `receipt` feature)
+//!
+//! A [`ValidationReceipt`] records the outcome of one [`Policy::check`]. Receipts can be signed
+//! with a [`Signer`], e.g., one that computes an HMAC, so that it can later be shown that a path
+//! passed validation under a specific policy version:
+//! ```
+//! # use safe_path::{receipt::{self, Signer}, Policy};
+//! # use std::path::Path;
+//! struct Xor(u8);
+//!
+//! impl Signer for Xor {
+//!     fn sign(&self, message: &[u8]) -> Vec<u8> {
+//!         message.iter().map(|byte| byte ^ self.0).collect()
+//!     }
+//! }
+//!
+//! let mut receipt = receipt::check(&Policy::new(), Path::new("www"), Path::new("x/../index.html"));
+//! assert!(receipt.accepted);
+//! assert_eq!("index.html", receipt.path);
+//! receipt.sign(&Xor(0x5c));
+//! assert!(receipt.verify(&Xor(0x5c)));
+//! assert!(!receipt.verify(&Xor(0x36)));
+//! ```
+
+use crate::{escape::escape, hash::fnv1a_64, lexical, Policy};
+use serde::{Deserialize, Serialize};
+use std::{
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Computes signatures over receipts, e.g., an HMAC with a secret key
+pub trait Signer {
+    /// Returns a signature over `message`.
+    fn sign(&self, message: &[u8]) -> Vec<u8>;
+}
+
+/// The outcome of one [`Policy::check`]
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ValidationReceipt {
+    /// A hash of the base directory, computed with [`root_hash`]
+    pub root_hash: u64,
+    /// The checked path after lexical normalization, converted to a string with
+    /// [`crate::escape::escape`]
+    pub path: String,
+    /// The [`crate::PolicyVersion`] number of the policy that performed the check
+    pub policy_version: u32,
+    /// Whether the check succeeded
+    pub accepted: bool,
+    /// When the check was performed, in seconds since the Unix epoch
+    pub timestamp: u64,
+    /// A signature over the other fields, if the receipt has been signed
+    pub signature: Option<Vec<u8>>,
+}
+
+/// Checks `path` against `policy` with `dir` as the base directory, and returns an unsigned receipt
+/// recording the outcome.
+#[must_use]
+pub fn check(policy: &Policy, dir: &Path, path: &Path) -> ValidationReceipt {
+    let accepted = policy.check(dir, path).is_ok();
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    ValidationReceipt {
+        root_hash: root_hash(dir),
+        path: escape(lexical::normalize(path).to_path_buf().as_os_str()),
+        policy_version: policy.version().get(),
+        accepted,
+        timestamp,
+        signature: None,
+    }
+}
+
+/// Returns the 64-bit FNV-1a hash of `dir`'s lossy UTF-8 representation. The hash identifies the
+/// base directory without revealing it; it is not a cryptographic commitment.
+#[must_use]
+pub fn root_hash(dir: &Path) -> u64 {
+    fnv1a_64(dir.to_string_lossy().as_bytes())
+}
+
+impl ValidationReceipt {
+    /// The bytes a [`Signer`] signs, i.e., every field except `signature`, in a fixed encoding
+    #[must_use]
+    pub fn message(&self) -> Vec<u8> {
+        format!(
+            "{:016x}\0{}\0{}\0{}\0{}",
+            self.root_hash, self.path, self.policy_version, self.accepted, self.timestamp
+        )
+        .into_bytes()
+    }
+
+    /// Signs `self` with `signer`, replacing any existing signature.
+    pub fn sign<S: ?Sized + Signer>(&mut self, signer: &S) {
+        self.signature = Some(signer.sign(&self.message()));
+    }
+
+    /// Returns true if `self` is signed and `signer` produces the same signature. The signatures
+    /// are compared in constant time.
+    #[must_use]
+    pub fn verify<S: ?Sized + Signer>(&self, signer: &S) -> bool {
+        let expected = signer.sign(&self.message());
+        match &self.signature {
+            Some(signature) if signature.len() == expected.len() => {
+                signature
+                    .iter()
+                    .zip(&expected)
+                    .fold(0, |acc, (x, y)| acc | (x ^ y))
+                    == 0
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use crate::{Error, PathOps, Violation};
+use std::{
+    convert::TryFrom,
+    ffi::OsStr,
+    fmt::{Debug, Display, Formatter},
+    io::Result,
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+};
+
+/// A relative path that refers to a file inside of whatever directory it is joined onto, e.g.,
+/// `assets/logo.png` or `x/../y`
+///
+/// A `SafeRelativePath` is a path that [`crate::SafePath::safe_join`] accepts for any receiver
+/// other than `/`. Thus, [`Path::join`] can be used to join it onto a directory:
+/// ```
+/// # use safe_path::SafeRelativePath;
+/// # use std::path::Path;
+/// let path = SafeRelativePath::new("assets/logo.png").unwrap();
+/// assert_eq!(Path::new("www").join(&path), Path::new("www/assets/logo.png"));
+///
+/// assert!(SafeRelativePath::new("x/../y").is_ok());
+/// assert!(SafeRelativePath::new("x/../../y").is_err());
+/// assert!(SafeRelativePath::new("/etc/passwd").is_err());
+/// assert!(SafeRelativePath::new(".").is_err());
+/// ```
+///
+/// With the `serde` feature, a `SafeRelativePath` is checked when it is deserialized, so that
+/// configuration files and request bodies can declare fields that are safe by construction.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SafeRelativePath(PathBuf);
+
+impl SafeRelativePath {
+    /// Returns `Ok(SafeRelativePath(path))` if `path` is relative and, for every prefix `prefix` of
+    /// `path`, `dir.join(prefix)` refers to a file inside of `dir`, for any directory `dir` other
+    /// than `/`. `path` must not refer to `dir` itself.
+    /// # Errors
+    /// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if the check fails. The
+    /// error payload is an [`Error`] whose contents are unstable and subject to change.
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        // smoelius: An empty receiver is relative and not `/`, so it stands for any such directory.
+        Path::new("")
+            .check_join_safety(path, false)
+            .map_err(|error| Error::map(error, |error| error.with_path(path)))?;
+        Ok(Self(path.to_path_buf()))
+    }
+
+    /// Builds a path from `components`, each of which must be a single normal component or `..`,
+    /// and returns it if it is a `SafeRelativePath`:
+    /// ```
+    /// # use safe_path::SafeRelativePath;
+    /// # use std::path::Path;
+    /// let path = SafeRelativePath::from_components(&["assets", "..", "logo.png"]).unwrap();
+    /// assert_eq!(path.as_path(), Path::new("assets/../logo.png"));
+    ///
+    /// assert!(SafeRelativePath::from_components(&["assets/logo.png"]).is_err());
+    /// assert!(SafeRelativePath::from_components(&["..", "logo.png"]).is_err());
+    /// ```
+    /// # Errors
+    /// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if a component is not
+    /// a single normal component or `..`, or if the check fails. The error payload is an [`Error`]
+    /// whose contents are unstable and subject to change.
+    pub fn from_components<I>(components: I) -> Result<Self>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<OsStr>,
+    {
+        let mut path = PathBuf::new();
+        for component in components {
+            let component = component.as_ref();
+            // smoelius: Comparing the component to `component` rejects trailing separators.
+            if !matches!(
+                Path::new(component).components().next(),
+                Some(Component::Normal(normal)) if normal == component
+            ) && component != ".."
+            {
+                return Err(Error::new("invalid file name")
+                    .with_violation(Violation::NotFileName)
+                    .with_path(component)
+                    .into());
+            }
+            path.push(component);
+        }
+        Self::new(path)
+    }
+
+    /// Returns the path as a [`Path`].
+    #[must_use]
+    pub fn as_path(&self) -> &Path {
+        &self.0
+    }
+
+    /// Converts `self` into a [`PathBuf`].
+    #[must_use]
+    pub fn into_path_buf(self) -> PathBuf {
+        self.0
+    }
+
+    /// Deserializes a path that was joined onto `base`, e.g., one cached after a successful
+    /// [`crate::SafePath::safe_join`], and returns the part after `base` (requires the `serde`
+    /// feature). The path is checked again, so a persisted path that was modified, or that was
+    /// joined onto a different directory, is rejected rather than trusted:
+    /// ```
+    /// # use safe_path::SafeRelativePath;
+    /// # use std::path::Path;
+    /// let mut deserializer = serde_json::Deserializer::from_str(r#""www/x/y""#);
+    /// let path = SafeRelativePath::deserialize_under("www", &mut deserializer).unwrap();
+    /// assert_eq!(path.as_path(), Path::new("x/y"));
+    ///
+    /// let mut deserializer = serde_json::Deserializer::from_str(r#""www/../etc/passwd""#);
+    /// assert!(SafeRelativePath::deserialize_under("www", &mut deserializer).is_err());
+    /// let mut deserializer = serde_json::Deserializer::from_str(r#""tmp/x/y""#);
+    /// assert!(SafeRelativePath::deserialize_under("www", &mut deserializer).is_err());
+    /// ```
+    /// # Errors
+    /// Returns `D::Error` if the path cannot be deserialized, does not begin with `base`, or the
+    /// part after `base` is not a `SafeRelativePath`.
+    #[cfg(feature = "serde")]
+    pub fn deserialize_under<'de, P: AsRef<Path>, D: serde::Deserializer<'de>>(
+        base: P,
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        use serde::{de::Error as _, Deserialize};
+        let base = base.as_ref();
+        let path = PathBuf::deserialize(deserializer)?;
+        let relative = path.strip_prefix(base).map_err(|_| {
+            D::Error::custom(format_args!(
+                "path `{}` is not under `{}`",
+                crate::escape::escape(path.as_os_str()),
+                crate::escape::escape(base.as_os_str())
+            ))
+        })?;
+        Self::new(relative).map_err(|error| {
+            D::Error::custom(format_args!(
+                "unsafe relative path `{}`: {}",
+                crate::escape::escape(relative.as_os_str()),
+                error
+            ))
+        })
+    }
+}
+
+impl AsRef<OsStr> for SafeRelativePath {
+    fn as_ref(&self) -> &OsStr {
+        self.0.as_os_str()
+    }
+}
+
+impl AsRef<Path> for SafeRelativePath {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl FromStr for SafeRelativePath {
+    type Err = std::io::Error;
+
+    fn from_str(path: &str) -> Result<Self> {
+        Self::new(path)
+    }
+}
+
+impl TryFrom<&str> for SafeRelativePath {
+    type Error = std::io::Error;
+
+    fn try_from(path: &str) -> Result<Self> {
+        Self::new(path)
+    }
+}
+
+/// Formats the path as its list of components, e.g., `SafeRelativePath(["assets", "logo.png"])`
+impl Debug for SafeRelativePath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("SafeRelativePath")
+            .field(
+                &self
+                    .0
+                    .components()
+                    .map(Component::as_os_str)
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+/// Displays the path lossily. Use [`crate::escape::escape`] for a lossless representation.
+impl Display for SafeRelativePath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.display())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for SafeRelativePath {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for SafeRelativePath {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let path = PathBuf::deserialize(deserializer)?;
+        Self::new(&path).map_err(|error| {
+            serde::de::Error::custom(format_args!(
+                "unsafe relative path `{}`: {}",
+                crate::escape::escape(path.as_os_str()),
+                error
+            ))
+        })
+    }
+}
+
+/// Describes a `SafeRelativePath` as a string that does not begin with a separator or a drive
+/// prefix. A schema cannot express the rest of the check, so the description states it.
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for SafeRelativePath {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "SafeRelativePath".into()
+    }
+
+    fn json_schema(_generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "type": "string",
+            "minLength": 1,
+            "pattern": r"^(?![/\\]|[A-Za-z]:)",
+            "description": "A relative path that refers to a file inside of the directory onto \
+                which it is joined, e.g., `assets/logo.png`. `..` components must not lead \
+                outside of that directory, and the path must not refer to the directory itself."
+        })
+    }
+}
+
+/// Generates a `SafeRelativePath` from arbitrary [`crate::FileName`]s and `..` components. A `..`
+/// is generated only where it does not lead outside of the directory onto which the path is joined.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for SafeRelativePath {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Self::generate(
+            u,
+            |u, n| u.int_in_range(0..=n - 1),
+            crate::FileName::arbitrary,
+        )
+    }
+}
+
+/// Generates a `SafeRelativePath` as the `arbitrary::Arbitrary` implementation does
+#[cfg(feature = "quickcheck")]
+impl quickcheck::Arbitrary for SafeRelativePath {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        Self::generate(
+            g,
+            |g, n| Ok::<_, std::convert::Infallible>(usize::arbitrary(g) % n),
+            |g| Ok(crate::FileName::arbitrary(g)),
+        )
+        .unwrap_or_else(|never| match never {})
+    }
+}
+
+#[cfg(any(feature = "arbitrary", feature = "quickcheck"))]
+impl SafeRelativePath {
+    // smoelius: `choose(g, n)` returns a random integer less than `n`. As with `Policy::generate`,
+    // both the `arbitrary` and `quickcheck` implementations use this function.
+    fn generate<G, E>(
+        g: &mut G,
+        choose: fn(&mut G, usize) -> std::result::Result<usize, E>,
+        file_name: fn(&mut G) -> std::result::Result<crate::FileName, E>,
+    ) -> std::result::Result<Self, E> {
+        let mut path = PathBuf::new();
+        let mut depth = 0_usize;
+        for _ in 0..=choose(g, crate::adversarial::MAX_COMPONENTS)? {
+            if depth > 1 && choose(g, 4)? == 0 {
+                path.push("..");
+                depth -= 1;
+            } else {
+                path.push(file_name(g)?);
+                depth += 1;
+            }
+        }
+        Ok(Self(path))
+    }
+}
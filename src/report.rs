@@ -0,0 +1,79 @@
+//! Rate limiting for rejection reports
+//!
+//! `safe_path` does not log rejections itself. Applications that log or count them (e.g., in a
+//! hook called whenever a check fails) can use a [`RateLimiter`] so that a client probing with many
+//! traversal attempts cannot flood the logs or the metrics pipeline.
+
+use crate::{Error, Violation};
+use std::{
+    collections::HashMap,
+    io,
+    mem::{self, Discriminant},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+const MAX_KEYS: usize = 1024;
+
+type Key = (PathBuf, &'static str, Option<Discriminant<Violation>>);
+
+/// Allows at most `max` reports per base directory and rejection reason in each window of time
+///
+/// ```
+/// # use safe_path::{report::RateLimiter, SafePath};
+/// # use std::{path::Path, time::Duration};
+/// let limiter = RateLimiter::new(2, Duration::from_secs(60));
+/// let dir = Path::new("www");
+/// let reported = (0..10)
+///     .map(|_| dir.safe_join("../etc/passwd").unwrap_err())
+///     .filter(|err| limiter.allow(dir, err))
+///     .count();
+/// assert_eq!(2, reported);
+/// ```
+#[derive(Debug)]
+pub struct RateLimiter {
+    max: usize,
+    window: Duration,
+    windows: Mutex<HashMap<Key, (Instant, usize)>>,
+}
+
+impl RateLimiter {
+    /// Creates a `RateLimiter` that allows `max` reports per key in each `window`.
+    #[must_use]
+    pub fn new(max: usize, window: Duration) -> Self {
+        Self {
+            max,
+            window,
+            windows: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns true if `error`, a rejection of a path joined to `dir`, should be reported. Errors
+    /// are considered identical if they have the same [`Error::context`] and the same kind of
+    /// [`Error::violation`], ignoring component indices.
+    pub fn allow(&self, dir: &Path, error: &io::Error) -> bool {
+        let (context, violation) = match Error::downcast_ref(error) {
+            Some(error) => (error.context(), error.violation().map(mem::discriminant)),
+            None => ("", None),
+        };
+        let now = Instant::now();
+        let mut windows = self
+            .windows
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        if windows.len() >= MAX_KEYS {
+            let window = self.window;
+            windows.retain(|_, (start, _)| now.duration_since(*start) < window);
+        }
+        let (start, count) = windows
+            .entry((dir.to_path_buf(), context, violation))
+            .or_insert((now, 0));
+        if now.duration_since(*start) >= self.window {
+            *start = now;
+            *count = 0;
+        }
+        *count = count.saturating_add(1);
+        *count <= self.max
+    }
+}
@@ -0,0 +1,215 @@
+//! Conversion of untrusted strings into safe file names
+//!
+//! The results of [`sanitize`] and [`encode`] are always accepted by [`crate::FileName::new`], so
+//! they can be joined onto a directory with [`crate::SafePath::safe_join_component`]. In addition,
+//! they are valid file names on both Unix and Windows.
+//!
+//! [`sanitize`] is lossy. [`encode`] is reversible with [`decode`].
+
+#[cfg(feature = "std-path")]
+use crate::SafePath;
+use crate::{Error, Violation};
+#[cfg(feature = "std-path")]
+use std::path::{Path, PathBuf};
+use std::{fmt::Write, io::Result};
+
+/// The string [`sanitize`] substitutes for each disallowed character
+pub const DEFAULT_REPLACEMENT: &str = "_";
+
+/// The maximum length, in bytes, of a sanitized name
+pub const MAX_LEN: usize = 255;
+
+const WINDOWS_RESERVED: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Converts `name` into a safe file name, using [`DEFAULT_REPLACEMENT`] as the replacement.
+/// ```
+/// # use safe_path::sanitize::sanitize;
+/// assert_eq!(sanitize("../../etc/passwd"), ".._.._etc_passwd");
+/// assert_eq!(sanitize(".."), "_");
+/// assert_eq!(sanitize("con.txt"), "_con.txt");
+/// assert_eq!(sanitize("report.pdf. "), "report.pdf");
+/// ```
+#[must_use]
+pub fn sanitize(name: &str) -> String {
+    sanitize_with(name, DEFAULT_REPLACEMENT)
+}
+
+/// Converts `name` into a safe file name:
+///
+/// * Separators (`/` and `\`), characters Windows forbids (`<>:"|?*`), and control characters are
+///   each replaced with `replacement`.
+/// * Trailing dots and spaces are removed.
+/// * Windows reserved names (e.g., `CON` or `nul.txt`) are prefixed with `replacement`.
+/// * The result is truncated to [`MAX_LEN`] bytes.
+/// * If the result would be empty, `replacement` is returned instead.
+///
+/// Disallowed characters in `replacement` are dropped. If `replacement` is thereby unusable (e.g.,
+/// it is empty), [`DEFAULT_REPLACEMENT`] is used where a non-empty string is required.
+#[must_use]
+pub fn sanitize_with(name: &str, replacement: &str) -> String {
+    let replacement: String = replacement.chars().filter(|&c| !disallowed(c)).collect();
+    let fallback = if replacement.trim_end_matches(['.', ' '].as_ref()).is_empty() {
+        DEFAULT_REPLACEMENT
+    } else {
+        &replacement
+    };
+
+    let mut sanitized = String::with_capacity(name.len());
+    for c in name.chars() {
+        if disallowed(c) {
+            sanitized.push_str(&replacement);
+        } else {
+            sanitized.push(c);
+        }
+    }
+
+    if is_windows_reserved(&sanitized) {
+        sanitized.insert_str(0, fallback);
+    }
+
+    truncate(&mut sanitized, MAX_LEN);
+    let len = sanitized.trim_end_matches(['.', ' '].as_ref()).len();
+    sanitized.truncate(len);
+
+    if sanitized.is_empty() {
+        sanitized.push_str(fallback);
+    }
+    sanitized
+}
+
+/// Returns the path under `base` at which to store an upload whose multipart/form-data `filename`
+/// parameter is `filename`. Directory components supplied by the client are stripped (some
+/// clients send a full path, e.g., `C:\Users\x\report.pdf`), as are leading dots, so that an
+/// upload cannot create a hidden file such as `.htaccess`. The remainder is passed through
+/// [`sanitize`].
+/// ```
+/// # use safe_path::sanitize::upload_path;
+/// # use std::path::Path;
+/// assert_eq!(upload_path("uploads", "report.pdf").unwrap(), Path::new("uploads/report.pdf"));
+/// let path = upload_path("uploads", "C:\\Users\\x\\report.pdf").unwrap();
+/// assert_eq!(path, Path::new("uploads/report.pdf"));
+/// assert_eq!(upload_path("uploads", "../../.htaccess").unwrap(), Path::new("uploads/htaccess"));
+/// assert!(upload_path("uploads", "x/..").is_err());
+/// ```
+/// # Errors
+/// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if nothing remains after
+/// stripping. The error payload is an [`Error`] whose contents are unstable and subject to change.
+#[cfg(feature = "std-path")]
+pub fn upload_path<P: AsRef<Path>>(base: P, filename: &str) -> Result<PathBuf> {
+    let name = filename
+        .rsplit(['/', '\\'].as_ref())
+        .next()
+        .unwrap_or_default()
+        .trim_start_matches('.');
+    if name.trim().is_empty() {
+        return Err(Error::new("invalid file name")
+            .with_violation(Violation::NotFileName)
+            .with_path(filename)
+            .into());
+    }
+    base.as_ref().safe_join_component(sanitize(name))
+}
+
+/// Converts `name` into a safe file name by percent-encoding the bytes of each character that
+/// [`sanitize_with`] would replace, `%` itself, a trailing dot or space, and the first character of
+/// a Windows reserved name. The empty string is encoded as `%`. Distinct names produce distinct
+/// results, though the results may be longer than [`MAX_LEN`], and names differing only in case
+/// may collide on case-insensitive filesystems.
+/// ```
+/// # use safe_path::sanitize::{decode, encode};
+/// assert_eq!(encode("../etc/passwd"), "..%2Fetc%2Fpasswd");
+/// assert_eq!(encode(".."), ".%2E");
+/// assert_eq!(encode("100%"), "100%25");
+/// assert_eq!(encode("con"), "%63on");
+/// assert_eq!(decode(&encode("../etc/passwd")).unwrap(), "../etc/passwd");
+/// ```
+#[must_use]
+pub fn encode(name: &str) -> String {
+    if name.is_empty() {
+        return String::from("%");
+    }
+    let reserved = is_windows_reserved(name);
+    let last = name.char_indices().last().map_or(0, |(i, _)| i);
+    let mut encoded = String::with_capacity(name.len());
+    for (i, c) in name.char_indices() {
+        if disallowed(c)
+            || c == '%'
+            || (i == 0 && reserved)
+            || (i == last && (c == '.' || c == ' '))
+        {
+            let mut buf = [0; 4];
+            for byte in c.encode_utf8(&mut buf).bytes() {
+                // smoelius: Writing to a `String` cannot fail.
+                let _ = write!(encoded, "%{:02X}", byte);
+            }
+        } else {
+            encoded.push(c);
+        }
+    }
+    encoded
+}
+
+/// Reverses [`encode`].
+/// # Errors
+/// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if `encoded` is not a
+/// possible result of [`encode`]. The error payload is an [`Error`] whose contents are unstable
+/// and subject to change.
+pub fn decode(encoded: &str) -> Result<String> {
+    let err = || {
+        Error::new("invalid encoded name")
+            .with_violation(Violation::InvalidEncoding)
+            .into()
+    };
+    if encoded == "%" {
+        return Ok(String::new());
+    }
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut rest = encoded.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = tail
+                .get(..2)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .ok_or_else(err)?;
+            bytes.push(u8::from_str_radix(hex, 16).map_err(|_| err())?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    let name = String::from_utf8(bytes).map_err(|_| err())?;
+    // smoelius: Requiring the canonical encoding makes `encode` and `decode` inverses.
+    if encode(&name) != encoded {
+        return Err(err());
+    }
+    Ok(name)
+}
+
+fn disallowed(c: char) -> bool {
+    matches!(c, '/' | '\\' | '<' | '>' | ':' | '"' | '|' | '?' | '*') || c.is_control()
+}
+
+/// Returns true if `name`'s stem (the part before the first `.`) is a name Windows reserves for a
+/// device, e.g., `CON` or `nul.txt`.
+pub(crate) fn is_windows_reserved(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or_default();
+    let stem = stem.trim_end_matches(' ');
+    WINDOWS_RESERVED
+        .iter()
+        .any(|reserved| stem.eq_ignore_ascii_case(reserved))
+}
+
+fn truncate(s: &mut String, max_len: usize) {
+    if s.len() <= max_len {
+        return;
+    }
+    let mut len = max_len;
+    while !s.is_char_boundary(len) {
+        len -= 1;
+    }
+    s.truncate(len);
+}
@@ -0,0 +1,92 @@
+//! `proptest` strategies for paths (requires the `proptest` feature)
+//!
+//! The strategies generate paths with [`crate::SafePath::safe_join`]'s semantics in mind, so that
+//! handlers can be property-tested against them:
+//! ```
+//! # use proptest::prelude::*;
+//! # use safe_path::{strategies, SafePath};
+//! # use std::path::Path;
+//! proptest!(|(path in strategies::contained_in("www"))| {
+//!     prop_assert!(Path::new("www").safe_join(&path).is_ok());
+//! });
+//! proptest!(|(path in strategies::escaping("www"))| {
+//!     prop_assert!(Path::new("www").safe_join(&path).is_err());
+//! });
+//! ```
+
+use crate::{
+    adversarial::{ArbitraryPath, COMPONENTS, MAX_COMPONENTS},
+    FileName, SafePath,
+};
+use proptest::{collection::vec, prelude::*, sample::select};
+use std::path::{Path, PathBuf};
+
+/// Generates [`ArbitraryPath`]s, i.e., paths built from [`crate::adversarial::COMPONENTS`]. The
+/// paths may be absolute, may escape, and may contain names that Windows treats specially.
+pub fn adversarial_path() -> impl Strategy<Value = PathBuf> {
+    vec(0..COMPONENTS.len(), 0..=MAX_COMPONENTS)
+        .prop_map(|indices| ArbitraryPath::from_indices(indices).0)
+}
+
+/// Generates relative paths that `base.safe_join` accepts. The paths are built from the
+/// [`crate::adversarial::COMPONENTS`] that are file names on this platform, and from `..`
+/// components that do not lead outside of `base`.
+pub fn contained_in<P: AsRef<Path>>(base: P) -> impl Strategy<Value = PathBuf> {
+    let base = base.as_ref().to_path_buf();
+    steps()
+        .prop_map(|steps| build(&steps).0)
+        .prop_filter("path must be contained", move |path| {
+            base.safe_join(path).is_ok()
+        })
+}
+
+/// Generates paths that `base.safe_join` rejects because they refer to a file outside of `base`:
+/// either a relative path whose `..` components lead outside of `base`, or a path with a root. If
+/// `base` is `/`, no path escapes, and the strategy fails.
+pub fn escaping<P: AsRef<Path>>(base: P) -> impl Strategy<Value = PathBuf> {
+    let base = base.as_ref().to_path_buf();
+    let relative = (steps(), 0..3_usize, vec(name(), 0..3)).prop_map(|(steps, extra, suffix)| {
+        let (mut path, depth) = build(&steps);
+        for _ in 0..=depth + extra {
+            path.push("..");
+        }
+        path.extend(suffix);
+        path
+    });
+    let rooted = steps().prop_map(|steps| Path::new("/").join(build(&steps).0));
+    prop_oneof![relative, rooted].prop_filter("path must escape", move |path| {
+        base.safe_join(path).is_err()
+    })
+}
+
+/// A sequence of steps, each of which is either a `..` (if the flag is set and the path is deep
+/// enough) or a normal component
+fn steps() -> impl Strategy<Value = Vec<(bool, &'static str)>> {
+    vec((prop::bool::weighted(0.25), name()), 1..=MAX_COMPONENTS)
+}
+
+fn name() -> impl Strategy<Value = &'static str> {
+    let names = COMPONENTS
+        .iter()
+        .copied()
+        .filter(|component| FileName::new(component).is_ok())
+        .collect::<Vec<_>>();
+    select(names)
+}
+
+/// Builds a path from `steps`, and returns it along with its depth, i.e., its number of normal
+/// components minus its number of `..` components. The depth is always at least 1.
+fn build(steps: &[(bool, &str)]) -> (PathBuf, usize) {
+    let mut path = PathBuf::new();
+    let mut depth = 0;
+    for &(parent, name) in steps {
+        if parent && depth > 1 {
+            path.push("..");
+            depth -= 1;
+        } else {
+            path.push(name);
+            depth += 1;
+        }
+    }
+    (path, depth)
+}
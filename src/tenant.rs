@@ -0,0 +1,79 @@
+//! Paths confined to a per-tenant subdirectory of a shared root
+
+use crate::{FileName, Policy, SafePath};
+use std::{
+    io::Result,
+    path::{Path, PathBuf},
+};
+
+/// A root directory bound to one tenant
+///
+/// Every path a `TenantRoot` produces is inside `root/tenant`. The tenant is a [`FileName`], so it
+/// cannot contain separators or `..`, and untrusted paths can be joined only after the tenant:
+/// ```
+/// # use safe_path::{tenant::TenantRoot, FileName};
+/// # use std::path::Path;
+/// let acme = TenantRoot::new("/srv/data", FileName::new("acme").unwrap());
+/// assert_eq!(acme.join("x.txt").unwrap(), Path::new("/srv/data/acme/x.txt"));
+/// assert!(acme.join("../globex/x.txt").is_err());
+///
+/// let globex = acme.switch_tenant(FileName::new("globex").unwrap());
+/// assert_eq!(globex.join("x.txt").unwrap(), Path::new("/srv/data/globex/x.txt"));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TenantRoot {
+    root: PathBuf,
+    tenant: FileName,
+    policy: Policy,
+}
+
+impl TenantRoot {
+    /// Binds `tenant` to `root`, checking untrusted paths with the default [`Policy`].
+    pub fn new<P: AsRef<Path>>(root: P, tenant: FileName) -> Self {
+        Self::with_policy(root, tenant, Policy::default())
+    }
+
+    /// Binds `tenant` to `root`, checking untrusted paths with `policy`.
+    pub fn with_policy<P: AsRef<Path>>(root: P, tenant: FileName, policy: Policy) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            tenant,
+            policy,
+        }
+    }
+
+    /// The shared root directory
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The tenant
+    #[must_use]
+    pub fn tenant(&self) -> &FileName {
+        &self.tenant
+    }
+
+    /// Returns `root/tenant`.
+    #[must_use]
+    pub fn tenant_dir(&self) -> PathBuf {
+        self.root.join(&self.tenant)
+    }
+
+    /// Returns `self.tenant_dir().safe_join_with(policy, path)`.
+    /// # Errors
+    /// Returns an error if the check fails. See [`SafePath::safe_join_with`].
+    pub fn join<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf> {
+        self.tenant_dir().safe_join_with(&self.policy, path)
+    }
+
+    /// Returns a `TenantRoot` with the same root and policy as `self`, bound to `tenant`.
+    #[must_use]
+    pub fn switch_tenant(&self, tenant: FileName) -> Self {
+        Self {
+            root: self.root.clone(),
+            tenant,
+            policy: self.policy.clone(),
+        }
+    }
+}
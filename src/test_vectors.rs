@@ -0,0 +1,262 @@
+//! A corpus of known directory traversal payloads (requires the `test-vectors` feature)
+//!
+//! Each [`TestVector`] is a payload to be appended to the route of an endpoint that serves files
+//! from a base directory, e.g., `/static/` + `..%2fetc%2fpasswd`, together with the [`Verdict`] a
+//! safe endpoint reaches. Downstream projects can run the corpus through their own endpoints in
+//! integration tests:
+//! ```
+//! # use safe_path::{test_vectors::VECTORS, url::safe_join_url_path};
+//! # use std::path::Path;
+//! let base = Path::new("www");
+//! for vector in VECTORS {
+//!     let result = safe_join_url_path(base, vector.payload);
+//!     assert!(vector.check(base, result.as_deref().ok()), "{:?}", vector);
+//! }
+//! ```
+//!
+//! Payloads are in the form they appear in a URL, i.e., percent-encoded. Verdicts concern
+//! containment only. An endpoint that applies a stricter policy (e.g., one that rejects `..`
+//! altogether) may reject payloads whose verdict is [`Verdict::Contained`].
+
+use crate::lexical::{lexically_equal, lexically_starts_with};
+use std::path::Path;
+
+/// A known payload and the verdict a safe endpoint reaches
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TestVector {
+    /// The payload, percent-encoded as it would appear in a URL
+    pub payload: &'static str,
+    /// The technique the payload uses
+    pub category: Category,
+    /// The verdict a safe endpoint reaches
+    pub verdict: Verdict,
+}
+
+/// The technique a [`TestVector`]'s payload uses
+///
+/// New variants may be added in minor releases.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Category {
+    /// Unencoded `..` components, e.g., `../etc/passwd`
+    Plain,
+    /// Percent-encoded dots or separators, e.g., `%2e%2e%2fetc%2fpasswd`, and malformed
+    /// percent-encodings
+    PercentEncoded,
+    /// Percent-encoded percent signs, e.g., `%252e%252e/etc/passwd`
+    DoubleEncoded,
+    /// Overlong or otherwise invalid UTF-8, e.g., `%c0%ae%c0%ae/etc/passwd`
+    InvalidUtf8,
+    /// Characters that resemble dots or separators, and non-standard Unicode escapes, e.g.,
+    /// `%u002e%u002e/etc/passwd`
+    Unicode,
+    /// Backslashes, drive letters, and UNC paths, which only Windows treats specially
+    Windows,
+    /// Inputs that naive filters mangle into traversals, e.g., `....//etc/passwd`
+    FilterBypass,
+    /// Ordinary paths
+    Benign,
+}
+
+/// The verdict a safe endpoint reaches on a [`TestVector`]'s payload
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Verdict {
+    /// The payload must be rejected.
+    Reject,
+    /// The payload must be rejected on Windows. Elsewhere, it is treated as [`Verdict::Contained`].
+    RejectOnWindows,
+    /// The payload may be rejected. If it is accepted, it must resolve to a file inside of the base
+    /// directory.
+    Contained,
+    /// The payload must be accepted and must resolve to a file inside of the base directory.
+    Accept,
+}
+
+impl TestVector {
+    /// Returns true if `resolved`, the path under `base` to which an endpoint resolved
+    /// `self.payload` (or `None` if the endpoint rejected it), is consistent with `self.verdict`.
+    /// A path is inside of `base` if it lexically starts with `base` and is not `base` itself.
+    #[must_use]
+    pub fn check(&self, base: &Path, resolved: Option<&Path>) -> bool {
+        let inside =
+            |path: &Path| lexically_starts_with(path, base) && !lexically_equal(path, base);
+        match (self.verdict, resolved) {
+            (Verdict::Reject, resolved) => resolved.is_none(),
+            (Verdict::RejectOnWindows, resolved) if cfg!(windows) => resolved.is_none(),
+            (Verdict::Accept, None) => false,
+            (_, None) => true,
+            (_, Some(path)) => inside(path),
+        }
+    }
+}
+
+const fn vector(payload: &'static str, category: Category, verdict: Verdict) -> TestVector {
+    TestVector {
+        payload,
+        category,
+        verdict,
+    }
+}
+
+/// The corpus
+pub const VECTORS: &[TestVector] = &[
+    vector("..", Category::Plain, Verdict::Reject),
+    vector("../", Category::Plain, Verdict::Reject),
+    vector("../etc/passwd", Category::Plain, Verdict::Reject),
+    vector(
+        "../../../../../../../../etc/passwd",
+        Category::Plain,
+        Verdict::Reject,
+    ),
+    vector("x/../../etc/passwd", Category::Plain, Verdict::Reject),
+    vector("./../etc/passwd", Category::Plain, Verdict::Reject),
+    vector("x/./../../etc/passwd", Category::Plain, Verdict::Reject),
+    vector(
+        "%2e%2e/etc/passwd",
+        Category::PercentEncoded,
+        Verdict::Reject,
+    ),
+    vector(
+        "%2E%2E/%2E%2E/etc/passwd",
+        Category::PercentEncoded,
+        Verdict::Reject,
+    ),
+    vector(".%2e/etc/passwd", Category::PercentEncoded, Verdict::Reject),
+    vector("%2e./etc/passwd", Category::PercentEncoded, Verdict::Reject),
+    vector(
+        "..%2fetc%2fpasswd",
+        Category::PercentEncoded,
+        Verdict::Reject,
+    ),
+    vector(
+        "%2e%2e%2fetc%2fpasswd",
+        Category::PercentEncoded,
+        Verdict::Reject,
+    ),
+    vector(
+        "..%5c..%5cwindows%5cwin.ini",
+        Category::PercentEncoded,
+        Verdict::Reject,
+    ),
+    vector("%", Category::PercentEncoded, Verdict::Reject),
+    vector("%zz/etc/passwd", Category::PercentEncoded, Verdict::Reject),
+    vector(
+        "%252e%252e/etc/passwd",
+        Category::DoubleEncoded,
+        Verdict::Reject,
+    ),
+    vector(
+        "..%252fetc%252fpasswd",
+        Category::DoubleEncoded,
+        Verdict::Reject,
+    ),
+    vector(
+        "..%255c..%255cwindows",
+        Category::DoubleEncoded,
+        Verdict::Reject,
+    ),
+    vector(
+        "%25%32%65%25%32%65/etc/passwd",
+        Category::DoubleEncoded,
+        Verdict::Reject,
+    ),
+    vector(
+        "%25252e%25252e/etc/passwd",
+        Category::DoubleEncoded,
+        Verdict::Reject,
+    ),
+    vector(
+        "%c0%ae%c0%ae/etc/passwd",
+        Category::InvalidUtf8,
+        Verdict::Reject,
+    ),
+    vector(
+        "..%c0%afetc%c0%afpasswd",
+        Category::InvalidUtf8,
+        Verdict::Reject,
+    ),
+    vector(
+        "..%c1%9c..%c1%9cwindows",
+        Category::InvalidUtf8,
+        Verdict::Reject,
+    ),
+    vector(
+        "%e0%80%ae%e0%80%ae/etc/passwd",
+        Category::InvalidUtf8,
+        Verdict::Reject,
+    ),
+    vector(
+        "%c0%2e%c0%2e/etc/passwd",
+        Category::InvalidUtf8,
+        Verdict::Reject,
+    ),
+    vector("%ff/etc/passwd", Category::InvalidUtf8, Verdict::Reject),
+    vector(
+        "%u002e%u002e/etc/passwd",
+        Category::Unicode,
+        Verdict::Reject,
+    ),
+    vector(
+        "..%u2215etc%u2215passwd",
+        Category::Unicode,
+        Verdict::Reject,
+    ),
+    vector(
+        "%ef%bc%8e%ef%bc%8e/etc/passwd",
+        Category::Unicode,
+        Verdict::Contained,
+    ),
+    vector(
+        "..%e2%88%95etc%e2%88%95passwd",
+        Category::Unicode,
+        Verdict::Contained,
+    ),
+    vector(
+        "%e2%80%a5/etc/passwd",
+        Category::Unicode,
+        Verdict::Contained,
+    ),
+    vector(
+        "..%ef%bc%8fetc%ef%bc%8fpasswd",
+        Category::Unicode,
+        Verdict::Contained,
+    ),
+    vector(
+        r"..\..\windows\win.ini",
+        Category::Windows,
+        Verdict::RejectOnWindows,
+    ),
+    vector(r"x/..\..\..\y", Category::Windows, Verdict::RejectOnWindows),
+    vector(
+        "C:/windows/win.ini",
+        Category::Windows,
+        Verdict::RejectOnWindows,
+    ),
+    vector(
+        r"C:\windows\win.ini",
+        Category::Windows,
+        Verdict::RejectOnWindows,
+    ),
+    vector(
+        r"\\server\share\x",
+        Category::Windows,
+        Verdict::RejectOnWindows,
+    ),
+    vector("C:%5cwindows%5cwin.ini", Category::Windows, Verdict::Reject),
+    vector(
+        "....//....//etc/passwd",
+        Category::FilterBypass,
+        Verdict::Contained,
+    ),
+    vector(
+        "..././..././etc/passwd",
+        Category::FilterBypass,
+        Verdict::Contained,
+    ),
+    vector("..;/etc/passwd", Category::FilterBypass, Verdict::Contained),
+    vector("index.html", Category::Benign, Verdict::Accept),
+    vector("css/site.css", Category::Benign, Verdict::Accept),
+    vector("a%20b.txt", Category::Benign, Verdict::Accept),
+    vector("caf%c3%a9.txt", Category::Benign, Verdict::Accept),
+    vector("docs/../index.html", Category::Benign, Verdict::Contained),
+];
@@ -0,0 +1,280 @@
+//! Checks of percent-encoded URL paths (requires the `url` feature)
+//!
+//! A web server that checks a request path in its encoded form, e.g., `/static/%2e%2e/secret`, and
+//! then opens the decoded form, checks a different path than the one the filesystem sees.
+//! [`safe_join_url_path`] decodes the path before checking it:
+//! ```
+//! # use safe_path::url::safe_join_url_path;
+//! # use std::path::Path;
+//! let path = safe_join_url_path("www", "/css/site%20v2.css").unwrap();
+//! assert_eq!(path, Path::new("www/css/site v2.css"));
+//! assert!(safe_join_url_path("www", "/css/%2e%2e/%2E%2E/secret").is_err());
+//! assert!(safe_join_url_path("www", "/css%2f..%2f..%2fsecret").is_err());
+//! assert!(safe_join_url_path("www", "/%c0%ae%c0%ae/secret").is_err());
+//! assert!(safe_join_url_path("www", "/%252e%252e/secret").is_err());
+//! ```
+//!
+//! [`resolve_request_path`] implements the rest of a static-file server's mapping from a request
+//! to a file under a document root.
+
+use crate::{lexical, Error, Policy, SafePath, Violation};
+use std::{
+    io::Result,
+    path::{Path, PathBuf},
+};
+
+/// Percent-decodes `raw_request_path`, splits it on `/`, and returns `base.safe_join(..)` of the
+/// decoded segments. `raw_request_path` should be the path part of a URL as received, i.e., without
+/// a query or a fragment. In addition:
+///
+/// * A `%` not followed by two hexadecimal digits is rejected.
+/// * A segment that does not decode to valid UTF-8 is rejected. In particular, overlong encodings
+///   (e.g., `%c0%ae` for `.`) are rejected.
+/// * A segment containing an encoded separator (`%2f` or `%5c`) is rejected, since the filesystem
+///   would not see the segment as a single component.
+/// * A segment that, once decoded, still contains an encoded `.`, separator, or `%` (e.g., `%252e`
+///   for `%2e`) is rejected, since a second decoding pass elsewhere (e.g., in a proxy or in the
+///   application) would see a different path than the one checked.
+/// * Empty and `.` segments, e.g., in a leading `/` or a trailing `/`, are ignored.
+///
+/// As with [`SafePath::safe_join`], a path that refers to `base` itself (e.g., `/`) is rejected.
+/// # Errors
+/// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if decoding or the check
+/// fails. The error payload is an [`Error`] whose contents are unstable and subject to change.
+pub fn safe_join_url_path<P: AsRef<Path>>(base: P, raw_request_path: &str) -> Result<PathBuf> {
+    base.as_ref()
+        .safe_join(relative_url_path(raw_request_path)?)
+}
+
+/// Returns the policy [`resolve_request_path`] applies: [`Policy::new`] with [`Policy::no_hidden`]
+/// set, so that dotfiles (e.g., `.env` or `.git/config`) are not served.
+#[must_use]
+pub fn policy() -> Policy {
+    Policy::new().no_hidden(true)
+}
+
+/// Maps the request target `raw_request` (e.g., `/docs/?page=2`) to a file under `docroot`,
+/// checking it against [`policy`]. See [`resolve_request_path_with`].
+/// ```
+/// # use safe_path::url::resolve_request_path;
+/// # use std::path::Path;
+/// let index = Some("index.html");
+/// let path = resolve_request_path("www", "/docs/?page=2", index).unwrap();
+/// assert_eq!(path, Path::new("www/docs/index.html"));
+/// let path = resolve_request_path("www", "/docs/../css/site.css", index).unwrap();
+/// assert_eq!(path, Path::new("www/css/site.css"));
+/// assert!(resolve_request_path("www", "/.git/config", index).is_err());
+/// assert!(resolve_request_path("www", "/", None).is_err());
+/// ```
+/// # Errors
+/// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if decoding or the check
+/// fails. The error payload is an [`Error`] whose contents are unstable and subject to change.
+pub fn resolve_request_path<P: AsRef<Path>>(
+    docroot: P,
+    raw_request: &str,
+    index: Option<&str>,
+) -> Result<PathBuf> {
+    resolve_request_path_with(&policy(), docroot, raw_request, index)
+}
+
+/// Maps the request target `raw_request` to a file under `docroot`, checking it against `policy`:
+///
+/// 1. Any query or fragment, i.e., everything from the first `?` or `#`, is removed.
+/// 2. The path is decoded and split as by [`safe_join_url_path`].
+/// 3. If `index` is `Some` and the path ends with `/` or refers to a directory under `docroot`,
+///    `index` is appended to the path. Determining the latter requires a (blocking) filesystem
+///    query.
+/// 4. The path is checked against `policy` with `docroot` as the base directory.
+/// 5. The path is normalized with [`lexical::normalize`] and joined onto `docroot`.
+///
+/// If `index` is `None`, a request for `docroot` itself (e.g., `/`) fails the check, as with
+/// [`SafePath::safe_join`]. If the error payload has an [`Error::violation`], it identifies the
+/// check that failed. [`Error::http_status`] maps the error to a response status.
+/// # Errors
+/// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if decoding or the check
+/// fails. The error payload is an [`Error`] whose contents are unstable and subject to change.
+pub fn resolve_request_path_with<P: AsRef<Path>>(
+    policy: &Policy,
+    docroot: P,
+    raw_request: &str,
+    index: Option<&str>,
+) -> Result<PathBuf> {
+    let docroot = docroot.as_ref();
+    let raw_request_path = raw_request
+        .split(['?', '#'].as_ref())
+        .next()
+        .unwrap_or_default();
+    let mut path = relative_url_path(raw_request_path)?;
+    if let Some(index) = index {
+        // smoelius: Check `path` before asking whether it is a directory, so that the filesystem is
+        // not queried about paths outside of `docroot`.
+        if raw_request_path.is_empty()
+            || raw_request_path.ends_with('/')
+            || (policy.check(docroot, &path).is_ok()
+                && docroot
+                    .join(lexical::normalize(&path).to_path_buf())
+                    .is_dir())
+        {
+            path.push(index);
+        }
+    }
+    policy.check(docroot, &path)?;
+    let normalized = lexical::normalize(&path).to_path_buf();
+    Ok(docroot.join(normalized))
+}
+
+/// A document root and the options with which to map requests to files under it
+///
+/// A `Docroot` is the configuration of [`crate::http::SafeRequestPath`], but can also be used on
+/// its own:
+/// ```
+/// # use safe_path::url::Docroot;
+/// # use std::path::Path;
+/// let docroot = Docroot::new("www").prefix("/static").index("index.html");
+/// assert_eq!(docroot.resolve("/static/").unwrap(), Path::new("www/index.html"));
+/// assert_eq!(docroot.resolve("/static/x.css").unwrap(), Path::new("www/x.css"));
+/// assert!(docroot.resolve("/staticx.css").is_err());
+/// assert!(docroot.resolve("/static/../x.css").is_err());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Docroot {
+    path: PathBuf,
+    prefix: String,
+    index: Option<String>,
+    policy: Policy,
+}
+
+impl Docroot {
+    /// Creates a `Docroot` for the directory `path`, with no prefix, no index file, and
+    /// [`policy`] as its policy.
+    #[must_use]
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            prefix: String::new(),
+            index: None,
+            policy: policy(),
+        }
+    }
+
+    /// Sets the prefix that request paths must begin with, e.g., the `/static` of a route
+    /// `/static/{*path}`. The prefix is removed before the rest of the path is mapped to a file. A
+    /// request path that does not begin with the prefix, followed by `/` or nothing, is rejected.
+    #[must_use]
+    pub fn prefix(self, prefix: &str) -> Self {
+        Self {
+            prefix: prefix.trim_end_matches('/').to_owned(),
+            ..self
+        }
+    }
+
+    /// Sets the file served for requests that refer to a directory, e.g., `index.html`. See
+    /// [`resolve_request_path_with`].
+    #[must_use]
+    pub fn index(mut self, index: &str) -> Self {
+        self.index = Some(index.to_owned());
+        self
+    }
+
+    /// Sets the policy against which request paths are checked.
+    #[must_use]
+    pub fn policy(mut self, policy: Policy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Returns the directory under which requests are mapped.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Removes `self`'s prefix from `raw_request` and calls [`resolve_request_path_with`] on the
+    /// remainder.
+    /// # Errors
+    /// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if `raw_request` does
+    /// not begin with `self`'s prefix, or if decoding or the check fails. The error payload is an
+    /// [`Error`] whose contents are unstable and subject to change.
+    pub fn resolve(&self, raw_request: &str) -> Result<PathBuf> {
+        let rest = raw_request
+            .strip_prefix(self.prefix.as_str())
+            .filter(|rest| rest.is_empty() || rest.starts_with(['/', '?', '#'].as_ref()))
+            .ok_or_else(|| {
+                Error::new("unsafe join operation")
+                    .with_violation(Violation::MissingPrefix)
+                    .with_path(raw_request)
+            })?;
+        self.resolve_unprefixed(rest)
+    }
+
+    /// Like [`Docroot::resolve`], but for a request path from which the prefix has already been
+    /// removed
+    pub(crate) fn resolve_unprefixed(&self, raw_request: &str) -> Result<PathBuf> {
+        resolve_request_path_with(&self.policy, &self.path, raw_request, self.index.as_deref())
+    }
+}
+
+fn relative_url_path(raw_request_path: &str) -> Result<PathBuf> {
+    let mut path = String::with_capacity(raw_request_path.len());
+    let mut index = 0;
+    for raw_segment in raw_request_path.split('/') {
+        let segment = percent_decode(raw_segment).ok_or_else(|| {
+            Error::new("invalid request path")
+                .with_violation(Violation::InvalidEncoding)
+                .with_path(raw_request_path)
+        })?;
+        if segment.is_empty() || segment == "." {
+            continue;
+        }
+        let lowercase = raw_segment.to_ascii_lowercase();
+        if lowercase.contains("%2f") || lowercase.contains("%5c") {
+            return Err(Error::new("unsafe join operation")
+                .with_violation(Violation::EncodedSeparator { index })
+                .with_path(raw_request_path)
+                .into());
+        }
+        if is_encoded_traversal(&segment) {
+            return Err(Error::new("unsafe join operation")
+                .with_violation(Violation::DoubleEncoding { index })
+                .with_path(raw_request_path)
+                .into());
+        }
+        if index > 0 {
+            path.push('/');
+        }
+        path.push_str(&segment);
+        index += 1;
+    }
+    Ok(PathBuf::from(path))
+}
+
+fn is_encoded_traversal(segment: &str) -> bool {
+    let lowercase = segment.to_ascii_lowercase();
+    ["%2e", "%2f", "%5c", "%25"]
+        .iter()
+        .any(|encoded| lowercase.contains(encoded))
+}
+
+fn percent_decode(raw: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(raw.len());
+    let mut iter = raw.bytes();
+    while let Some(byte) = iter.next() {
+        if byte == b'%' {
+            let high = iter.next().and_then(hex_value)?;
+            let low = iter.next().and_then(hex_value)?;
+            bytes.push(high << 4 | low);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+fn hex_value(byte: u8) -> Option<u8> {
+    match byte {
+        b'0'..=b'9' => Some(byte - b'0'),
+        b'a'..=b'f' => Some(byte - b'a' + 10),
+        b'A'..=b'F' => Some(byte - b'A' + 10),
+        _ => None,
+    }
+}
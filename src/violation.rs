@@ -0,0 +1,189 @@
+use std::fmt::{Display, Formatter};
+
+/// A reason a join fails its check, as reported by [`crate::SafePath::explain_join`] and
+/// [`crate::Error::violation`]
+///
+/// Indices are positions in the argument's [components](std::path::Path::components).
+///
+/// New variants may be added in minor releases. An existing variant's meaning does not change
+/// without an increment of [`crate::PolicyVersion::CURRENT`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum Violation {
+    /// The component at `index` is a root directory or prefix, and the receiver is not `/`
+    Absolute { index: usize },
+    /// The component at `index` is a Windows verbatim prefix, e.g., `\\?\C:`
+    VerbatimPrefix { index: usize },
+    /// The component at `index` is a Windows prefix not followed by a root directory, e.g., `C:` in
+    /// `C:x`, which is relative to the drive's current directory
+    DriveRelative { index: usize },
+    /// The component at `index` is a Windows prefix that refers to a different drive or share than
+    /// the receiver's prefix
+    PrefixMismatch { index: usize },
+    /// The component at `index` is a symbolic link, or a `..` following one, that resolves to a
+    /// file outside of the receiver. Reported only by `fs::safe_join_fs`.
+    EscapingSymlink { index: usize },
+    /// The component at `index` is on a different filesystem than the receiver. Reported only by
+    /// `fs::SafeDir::join` with `fs::SafeDir::same_device` enabled.
+    DeviceBoundary { index: usize },
+    /// The component at `index` contains a percent-encoded separator (e.g., `%2f`). Reported only
+    /// by `url::safe_join_url_path`.
+    EncodedSeparator { index: usize },
+    /// The component at `index` still contains a percent-encoded `.`, separator, or `%` after
+    /// percent-decoding (e.g., `%252e`). Reported only by `url::safe_join_url_path`.
+    DoubleEncoding { index: usize },
+    /// The `..` at `index` refers to a file outside of the receiver, and the receiver is not `/`
+    EscapingParentDir { index: usize },
+    /// The result would refer to the receiver itself
+    SelfJoin,
+    /// The `..` at `index` is forbidden by a [`crate::Policy`]
+    ParentDir { index: usize },
+    /// The component at `index` is on a [`crate::Policy`]'s deny-list
+    DeniedComponent { index: usize },
+    /// The argument is longer than a [`crate::Policy`]'s maximum length
+    TooLong,
+    /// The argument has more components than a [`crate::Policy`]'s maximum depth
+    TooDeep,
+    /// The component at `index` is longer than a [`crate::Policy`]'s maximum component length
+    ComponentTooLong { index: usize },
+    /// The component at `index` contains a character not allowed by a [`crate::Policy`]
+    DisallowedChar { index: usize },
+    /// The component at `index` contains an ASCII control character, e.g., NUL, and
+    /// [`crate::Policy::no_control`] is in effect
+    ControlChar { index: usize },
+    /// The component at `index` contains a bidirectional control or zero-width character, and
+    /// [`crate::Policy::no_invisible`] is in effect
+    InvisibleChar { index: usize },
+    /// The component at `index` contains a `:`, which could name a Windows alternate data stream,
+    /// and [`crate::Policy::no_streams`] is in effect
+    Stream { index: usize },
+    /// The component at `index` begins with `.` and is forbidden by a [`crate::Policy`]
+    HiddenComponent { index: usize },
+    /// The argument's extension is not allowed by a [`crate::Policy`]
+    DisallowedExtension,
+    /// The component at `index` ends with `.` or a space and is forbidden by a [`crate::Policy`]
+    TrailingDotOrSpace { index: usize },
+    /// The component at `index` looks like a Windows 8.3 short name (e.g., `PROGRA~1`) and is
+    /// forbidden by a [`crate::Policy`]
+    ShortName { index: usize },
+    /// The component at `index` is a name Windows reserves for a device (e.g., `CON` or `nul.txt`)
+    /// and is forbidden by a [`crate::Policy`]
+    ReservedName { index: usize },
+    /// The component at `index` contains an environment-variable reference (e.g., `$HOME` or
+    /// `%APPDATA%`) and is forbidden by a [`crate::Policy`]
+    EnvVar { index: usize },
+    /// The component at `index` begins with `~` (e.g., `~` or `~user`) and is forbidden by a
+    /// [`crate::Policy`]
+    Tilde { index: usize },
+    /// The argument contains a repeated separator (e.g., `a//b`), a `.` component other than a
+    /// leading one (e.g., `a/./b`), or a trailing separator, and [`crate::Separators::Reject`] is
+    /// in effect
+    RedundantSeparator,
+    /// The argument is not exactly one normal component (e.g., it is empty or contains a separator)
+    /// where a file name is required, e.g., by [`crate::FileName::new`]
+    NotFileName,
+    /// The argument to [`crate::SafePath::safe_with_extension`] is neither empty nor exactly one
+    /// normal component
+    InvalidExtension,
+    /// The argument is not valid UTF-8 or contains an invalid escape or percent-encoding
+    InvalidEncoding,
+    /// The argument is not a content hash that `fs::FanOut` accepts
+    InvalidContentHash,
+    /// The request path does not begin with the route's prefix. Reported only by
+    /// `url::Docroot::resolve`.
+    MissingPrefix,
+    /// A check added with [`crate::Policy::component_check`] (if `index` is `Some`) or
+    /// [`crate::Policy::path_check`] (if `index` is `None`) failed for `reason`
+    Custom {
+        index: Option<usize>,
+        reason: &'static str,
+    },
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Absolute { index } => write!(f, "component {} is absolute", index),
+            Self::VerbatimPrefix { index } => write!(f, "component {} is a verbatim prefix", index),
+            Self::DriveRelative { index } => {
+                write!(f, "component {} is a drive-relative prefix", index)
+            }
+            Self::PrefixMismatch { index } => {
+                write!(
+                    f,
+                    "component {} refers to a different drive or share",
+                    index
+                )
+            }
+            Self::EscapingSymlink { index } => {
+                write!(
+                    f,
+                    "component {} resolves outside of the base directory",
+                    index
+                )
+            }
+            Self::DeviceBoundary { index } => {
+                write!(
+                    f,
+                    "component {} is on a different filesystem than the base directory",
+                    index
+                )
+            }
+            Self::EncodedSeparator { index } => {
+                write!(f, "component {} contains an encoded separator", index)
+            }
+            Self::DoubleEncoding { index } => {
+                write!(f, "component {} is percent-encoded more than once", index)
+            }
+            Self::EscapingParentDir { index } => {
+                write!(f, "component {} escapes the base directory", index)
+            }
+            Self::SelfJoin => write!(f, "result refers to the base directory itself"),
+            Self::ParentDir { index } => write!(f, "component {} is `..`", index),
+            Self::DeniedComponent { index } => write!(f, "component {} is denied", index),
+            Self::TooLong => write!(f, "path is too long"),
+            Self::TooDeep => write!(f, "path has too many components"),
+            Self::ComponentTooLong { index } => write!(f, "component {} is too long", index),
+            Self::DisallowedChar { index } => {
+                write!(f, "component {} contains a disallowed character", index)
+            }
+            Self::ControlChar { index } => {
+                write!(f, "component {} contains a control character", index)
+            }
+            Self::InvisibleChar { index } => {
+                write!(f, "component {} contains an invisible character", index)
+            }
+            Self::Stream { index } => {
+                write!(f, "component {} could name an alternate data stream", index)
+            }
+            Self::HiddenComponent { index } => write!(f, "component {} is hidden", index),
+            Self::DisallowedExtension => write!(f, "extension is not allowed"),
+            Self::TrailingDotOrSpace { index } => {
+                write!(f, "component {} ends with a dot or a space", index)
+            }
+            Self::ShortName { index } => write!(f, "component {} looks like a short name", index),
+            Self::ReservedName { index } => {
+                write!(f, "component {} is a reserved device name", index)
+            }
+            Self::EnvVar { index } => {
+                write!(f, "component {} references an environment variable", index)
+            }
+            Self::Tilde { index } => write!(f, "component {} begins with a tilde", index),
+            Self::RedundantSeparator => write!(f, "path contains a redundant separator"),
+            Self::NotFileName => write!(f, "argument is not a file name"),
+            Self::InvalidExtension => write!(f, "argument is not an extension"),
+            Self::InvalidEncoding => write!(f, "argument is not validly encoded"),
+            Self::InvalidContentHash => write!(f, "argument is not a content hash"),
+            Self::MissingPrefix => write!(f, "request path does not begin with the prefix"),
+            Self::Custom {
+                index: Some(index),
+                reason,
+            } => write!(f, "component {}: {}", index, reason),
+            Self::Custom {
+                index: None,
+                reason,
+            } => write!(f, "{}", reason),
+        }
+    }
+}
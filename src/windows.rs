@@ -0,0 +1,166 @@
+//! Checks under Windows rules on any platform (requires the `typed-path` feature)
+//!
+//! [`crate::SafePath`] interprets paths according to the host, so on Linux, `..\..\x` is a single
+//! normal component. A server that ships paths to Windows machines can use this module to check
+//! them as Windows would interpret them:
+//! ```
+//! # use safe_path::windows;
+//! # use typed_path::Utf8WindowsPath;
+//! let dir = Utf8WindowsPath::new(r"C:\srv\www");
+//! assert!(windows::safe_join(dir, Utf8WindowsPath::new(r"css\site.css")).is_ok());
+//! assert!(windows::safe_join(dir, Utf8WindowsPath::new(r"..\..\Windows")).is_err());
+//! assert!(windows::safe_join(dir, Utf8WindowsPath::new(r"D:\x")).is_err());
+//! assert!(windows::safe_join(dir, Utf8WindowsPath::new("con.txt")).is_err());
+//! ```
+//!
+//! Both `\` and `/` are separators, and prefixes (e.g., `C:` or `\\server\share`) are recognized.
+
+use crate::{
+    backend::{self, ComponentKind},
+    Error, Policy, Separators, Violation,
+};
+use std::{ffi::OsStr, io::Result, path::Component};
+use typed_path::{Utf8WindowsComponent, Utf8WindowsPath, Utf8WindowsPathBuf, Utf8WindowsPrefix};
+
+/// Returns the policy [`safe_join`] applies: [`Policy::new`] with [`Policy::no_windows_reserved`],
+/// [`Policy::no_streams`], [`Policy::no_trailing_dot_or_space`], and [`Policy::case_insensitive`]
+/// set. Windows would resolve a path rejected by these options to a device, a stream, or a file
+/// with a different name.
+#[must_use]
+pub fn policy() -> Policy {
+    Policy::new()
+        .no_windows_reserved(true)
+        .no_streams(true)
+        .no_trailing_dot_or_space(true)
+        .case_insensitive(true)
+}
+
+/// Like [`crate::SafePath::safe_join`], but interprets `dir` and `path` as Windows paths and
+/// checks `path` against [`policy`].
+/// # Errors
+/// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if the check fails. The
+/// error payload is an [`Error`] whose contents are unstable and subject to change.
+pub fn safe_join(dir: &Utf8WindowsPath, path: &Utf8WindowsPath) -> Result<Utf8WindowsPathBuf> {
+    safe_join_with(&policy(), dir, path)
+}
+
+/// Like [`crate::SafePath::safe_join_with`], but interprets `dir` and `path` as Windows paths.
+///
+/// A [`crate::ComponentCheck`] sees a prefix as [`Component::RootDir`]. A [`crate::PathCheck`]
+/// sees the Windows paths as host paths.
+/// # Errors
+/// Returns a [`std::io::Error`] of `kind` [`std::io::ErrorKind::Other`] if the check fails. The
+/// error payload is an [`Error`] whose contents are unstable and subject to change.
+pub fn safe_join_with(
+    policy: &Policy,
+    dir: &Utf8WindowsPath,
+    path: &Utf8WindowsPath,
+) -> Result<Utf8WindowsPathBuf> {
+    policy.check_windows(dir, path)?;
+    let result = dir.join(path);
+    if policy.separator_handling() == Separators::Normalize {
+        Ok(result.components().collect())
+    } else {
+        Ok(result)
+    }
+}
+
+/// Like [`crate::SafePath::explain_join`], but interprets `dir` and `path` as Windows paths.
+/// Violations of [`policy`] are not included.
+#[must_use]
+pub fn explain_join(dir: &Utf8WindowsPath, path: &Utf8WindowsPath) -> Vec<Violation> {
+    let mut violations = backend::explain_join_safety(kinds(path), || is_root(dir));
+    if let Some(index) = prefix_mismatch(dir, path) {
+        violations.retain(|violation| *violation != Violation::SelfJoin);
+        violations.insert(0, Violation::PrefixMismatch { index });
+    }
+    violations
+}
+
+/// Returns true if `dir` normalizes to its root, e.g., `C:\` or `\\server\share\`.
+#[must_use]
+pub fn is_root(dir: &Utf8WindowsPath) -> bool {
+    dir.has_root() && backend::is_root(kinds(dir))
+}
+
+/// Implements the join-safety part of [`Policy::check_windows`]. `self_is_root`, if provided, is
+/// used in place of `is_root(dir)`.
+pub(crate) fn check_join_safety(
+    dir: &Utf8WindowsPath,
+    path: &Utf8WindowsPath,
+    relaxed: bool,
+    self_is_root: Option<bool>,
+) -> Result<()> {
+    if let Some(index) = prefix_mismatch(dir, path) {
+        return Err(Error::new("unsafe join operation")
+            .with_violation(Violation::PrefixMismatch { index })
+            .into());
+    }
+    backend::check_join_safety(kinds(path), relaxed, || {
+        self_is_root.unwrap_or_else(|| is_root(dir))
+    })
+}
+
+/// Converts `component` to a [`Component`] for the checks [`Policy`] performs on each component.
+/// There is no way to construct a [`Component::Prefix`] on other platforms, so a prefix is
+/// converted to [`Component::RootDir`].
+pub(crate) fn std_component(component: Utf8WindowsComponent<'_>) -> Component<'_> {
+    match component {
+        Utf8WindowsComponent::Prefix(_) | Utf8WindowsComponent::RootDir => Component::RootDir,
+        Utf8WindowsComponent::CurDir => Component::CurDir,
+        Utf8WindowsComponent::ParentDir => Component::ParentDir,
+        Utf8WindowsComponent::Normal(name) => Component::Normal(OsStr::new(name)),
+    }
+}
+
+fn kinds(path: &Utf8WindowsPath) -> impl Iterator<Item = ComponentKind> + Clone + '_ {
+    path.components().map(|component| match component {
+        Utf8WindowsComponent::Prefix(prefix) if prefix.kind().is_verbatim() => {
+            ComponentKind::VerbatimPrefix
+        }
+        Utf8WindowsComponent::Prefix(_) => ComponentKind::Prefix,
+        Utf8WindowsComponent::RootDir => ComponentKind::RootDir,
+        Utf8WindowsComponent::CurDir => ComponentKind::CurDir,
+        Utf8WindowsComponent::ParentDir => ComponentKind::ParentDir,
+        Utf8WindowsComponent::Normal(_) => ComponentKind::Normal,
+    })
+}
+
+/// Like [`crate::prefix::mismatch`], but for [`Utf8WindowsPath`]s.
+fn prefix_mismatch(dir: &Utf8WindowsPath, path: &Utf8WindowsPath) -> Option<usize> {
+    let dir_key = match dir.components().next() {
+        Some(Utf8WindowsComponent::Prefix(prefix)) => Some(prefix_key(prefix.kind())),
+        _ => None,
+    };
+    path.components()
+        .enumerate()
+        .find_map(|(index, component)| match component {
+            Utf8WindowsComponent::Prefix(prefix)
+                if !prefix.kind().is_verbatim()
+                    && dir_key.as_ref() != Some(&prefix_key(prefix.kind())) =>
+            {
+                Some(index)
+            }
+            _ => None,
+        })
+}
+
+/// Returns a case-folded key identifying the drive or share to which `prefix` refers.
+fn prefix_key(prefix: Utf8WindowsPrefix<'_>) -> String {
+    let key = match prefix {
+        Utf8WindowsPrefix::Disk(letter) | Utf8WindowsPrefix::VerbatimDisk(letter) => {
+            format!("{}:", letter)
+        }
+        Utf8WindowsPrefix::DeviceNS(name) if is_drive(name) => name.to_owned(),
+        Utf8WindowsPrefix::UNC(server, share) | Utf8WindowsPrefix::VerbatimUNC(server, share) => {
+            format!(r"\\{}\{}", server, share)
+        }
+        Utf8WindowsPrefix::DeviceNS(name) => format!(r"\\.\{}", name),
+        Utf8WindowsPrefix::Verbatim(name) => format!(r"\\?\{}", name),
+    };
+    key.to_lowercase()
+}
+
+fn is_drive(name: &str) -> bool {
+    matches!(name.as_bytes(), [letter, b':'] if letter.is_ascii_alphabetic())
+}
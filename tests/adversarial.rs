@@ -0,0 +1,69 @@
+#![cfg(feature = "arbitrary")]
+
+use arbitrary::{Arbitrary, Unstructured};
+use safe_path::{
+    adversarial::{ArbitraryPath, COMPONENTS, MAX_COMPONENTS},
+    FileName, Policy, SafePath, SafeRelativePath,
+};
+use std::path::Path;
+
+// smoelius: A simple deterministic byte source, so that the tests do not depend on a random number
+// generator.
+fn data(seed: u32) -> Vec<u8> {
+    let mut state = seed;
+    (0..256)
+        .map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (state >> 16).to_le_bytes()[0]
+        })
+        .collect()
+}
+
+#[test]
+fn arbitrary_path() {
+    let mut accepted = 0;
+    let mut rejected = 0;
+    for seed in 0..256 {
+        let data = data(seed);
+        let mut u = Unstructured::new(&data);
+        let ArbitraryPath(path) = ArbitraryPath::arbitrary(&mut u).unwrap();
+        assert!(path.to_string_lossy().split('/').count() <= MAX_COMPONENTS);
+        if Path::new("www").safe_join(&path).is_ok() {
+            accepted += 1;
+        } else {
+            rejected += 1;
+        }
+    }
+    assert!(accepted > 0);
+    assert!(rejected > 0);
+}
+
+#[test]
+fn from_indices() {
+    assert_eq!(Path::new("../x"), ArbitraryPath::from_indices(vec![0, 3]).0);
+    assert_eq!(
+        Path::new(".."),
+        ArbitraryPath::from_indices(vec![COMPONENTS.len()]).0
+    );
+    assert_eq!(
+        MAX_COMPONENTS,
+        ArbitraryPath::from_indices(vec![3; 2 * MAX_COMPONENTS])
+            .0
+            .components()
+            .count()
+    );
+}
+
+#[test]
+fn wrappers() {
+    for seed in 0..256 {
+        let data = data(seed);
+        let mut u = Unstructured::new(&data);
+        let name = FileName::arbitrary(&mut u).unwrap();
+        assert!(FileName::new(name.as_os_str()).is_ok(), "{:?}", name);
+        let path = SafeRelativePath::arbitrary(&mut u).unwrap();
+        assert!(SafeRelativePath::new(path.as_path()).is_ok(), "{:?}", path);
+        let policy = Policy::arbitrary(&mut u).unwrap();
+        let _ = Path::new("www").safe_join_with(&policy, &path);
+    }
+}
@@ -0,0 +1,205 @@
+#![cfg(feature = "std-path")]
+
+#[cfg(any(feature = "tar", feature = "zip"))]
+use std::fs::{create_dir, read_dir, read_link, read_to_string};
+use std::path::Path;
+#[cfg(any(feature = "tar", feature = "zip"))]
+use tempfile::tempdir;
+
+#[cfg(all(feature = "tar", unix))]
+#[test]
+fn tar() {
+    use safe_path::archive::safe_unpack;
+    use tar::{Archive, EntryType};
+
+    let tempdir = tempdir().unwrap();
+    let base = tempdir.path().join("base");
+    create_dir(&base).unwrap();
+
+    let data = archive(&[
+        ("d", EntryType::Directory, None, ""),
+        ("d/f", EntryType::Regular, None, "contents"),
+        ("d/l", EntryType::Symlink, Some("f"), ""),
+        ("h", EntryType::Link, Some("d/f"), ""),
+        ("e/g", EntryType::Regular, None, "contents"),
+        ("fifo", EntryType::Fifo, None, ""),
+    ]);
+    safe_unpack(&mut Archive::new(data.as_slice()), &base).unwrap();
+    assert_eq!("contents", read_to_string(base.join("d/f")).unwrap());
+    assert_eq!(Path::new("f"), read_link(base.join("d/l")).unwrap());
+    assert_eq!("contents", read_to_string(base.join("h")).unwrap());
+    assert_eq!("contents", read_to_string(base.join("e/g")).unwrap());
+    assert!(!base.join("fifo").exists());
+}
+
+#[cfg(all(feature = "tar", unix))]
+#[test]
+fn tar_slip() {
+    use safe_path::archive::safe_unpack;
+    use std::os::unix::fs::symlink;
+    use tar::{Archive, EntryType};
+
+    let tempdir = tempdir().unwrap();
+    let base = tempdir.path().join("base");
+    let outside = tempdir.path().join("outside");
+    create_dir(&base).unwrap();
+    create_dir(&outside).unwrap();
+    symlink(&outside, base.join("escape")).unwrap();
+
+    for entries in &[
+        &[("../x", EntryType::Regular, None, "")][..],
+        &[("/x", EntryType::Regular, None, "")],
+        &[("escape/x", EntryType::Regular, None, "")],
+        &[("l", EntryType::Symlink, Some("../outside"), "")],
+        &[("l", EntryType::Symlink, Some("/etc"), "")],
+        &[("d/l", EntryType::Symlink, Some("../../outside"), "")],
+        &[("h", EntryType::Link, Some("../outside/x"), "")],
+        &[("h", EntryType::Link, Some("escape/x"), "")],
+        &[
+            ("l", EntryType::Symlink, Some("d"), ""),
+            ("l/../../x", EntryType::Regular, None, ""),
+        ],
+        &[("d/../x", EntryType::Regular, None, "")],
+    ] {
+        let data = archive(entries);
+        assert!(
+            safe_unpack(&mut Archive::new(data.as_slice()), &base).is_err(),
+            "{:?}",
+            entries
+        );
+    }
+    assert!(read_dir(&outside).unwrap().next().is_none());
+}
+
+/// Builds a tar archive from `(name, entry_type, link_name, contents)` tuples. Names are written
+/// directly into headers, because `Builder` rejects names containing `..`.
+#[cfg(feature = "tar")]
+fn archive(entries: &[(&str, tar::EntryType, Option<&str>, &str)]) -> Vec<u8> {
+    use tar::{Builder, Header};
+
+    let mut builder = Builder::new(Vec::new());
+    for (name, entry_type, link_name, contents) in entries {
+        let mut header = Header::new_gnu();
+        let gnu = header.as_gnu_mut().unwrap();
+        gnu.name[..name.len()].copy_from_slice(name.as_bytes());
+        if let Some(link_name) = link_name {
+            gnu.linkname[..link_name.len()].copy_from_slice(link_name.as_bytes());
+        }
+        header.set_entry_type(*entry_type);
+        header.set_mode(0o755);
+        header.set_size(contents.len() as u64);
+        header.set_cksum();
+        builder.append(&header, contents.as_bytes()).unwrap();
+    }
+    builder.into_inner().unwrap()
+}
+
+#[test]
+fn entry_path() {
+    use safe_path::{archive::entry_path, Error, Violation};
+
+    for (name, expected) in &[
+        ("a", "a"),
+        ("a/b/", "a/b"),
+        ("./a//b", "a/b"),
+        ("a\\b", "a/b"),
+        ("ab:c", "ab:c"),
+    ] {
+        assert_eq!(
+            Path::new("out").join(expected),
+            entry_path("out", name).unwrap(),
+            "{:?}",
+            name
+        );
+    }
+    assert_eq!(
+        Path::new("out/d\u{e9}j\u{e0}"),
+        entry_path("out", b"d\xe9j\xe0").unwrap()
+    );
+    assert_eq!(
+        Path::new("out/d\u{e9}j\u{e0}"),
+        entry_path("out", "d\u{e9}j\u{e0}".as_bytes()).unwrap()
+    );
+    for (name, violation) in &[
+        ("/a", Violation::Absolute { index: 0 }),
+        ("\\a", Violation::Absolute { index: 0 }),
+        ("C:\\a", Violation::Absolute { index: 0 }),
+        ("c:/a", Violation::Absolute { index: 0 }),
+        ("C:a", Violation::DriveRelative { index: 0 }),
+        ("a/../b", Violation::ParentDir { index: 1 }),
+        ("./..\\a", Violation::ParentDir { index: 0 }),
+    ] {
+        let error = entry_path("out", name).unwrap_err();
+        assert_eq!(
+            Some(violation),
+            Error::downcast_ref(&error).unwrap().violation(),
+            "{:?}",
+            name
+        );
+    }
+    assert!(entry_path("out", "./").is_err());
+}
+
+#[cfg(all(feature = "zip", unix))]
+#[test]
+fn zip() {
+    use safe_path::archive::safe_extract_all;
+    use std::{io::Cursor, os::unix::fs::symlink};
+
+    let tempdir = tempdir().unwrap();
+    let base = tempdir.path().join("base");
+    let outside = tempdir.path().join("outside");
+    create_dir(&base).unwrap();
+    create_dir(&outside).unwrap();
+    symlink(&outside, base.join("escape")).unwrap();
+
+    let data = zip_archive(&[
+        ("d/", None, ""),
+        ("d/f", None, "contents"),
+        ("d/l", Some("f"), ""),
+        ("e\\g", None, "contents"),
+    ]);
+    safe_extract_all(&mut zip::ZipArchive::new(Cursor::new(data)).unwrap(), &base).unwrap();
+    assert_eq!("contents", read_to_string(base.join("d/f")).unwrap());
+    assert_eq!(Path::new("f"), read_link(base.join("d/l")).unwrap());
+    assert_eq!("contents", read_to_string(base.join("e/g")).unwrap());
+
+    for entries in &[
+        &[("../x", None, "")][..],
+        &[("/x", None, "")],
+        &[("escape/x", None, "")],
+        &[("l", Some("../outside"), "")],
+        &[("l", Some("/etc"), "")],
+        &[("l", Some("d"), ""), ("l/../../x", None, "")],
+    ] {
+        let data = zip_archive(entries);
+        assert!(
+            safe_extract_all(&mut zip::ZipArchive::new(Cursor::new(data)).unwrap(), &base).is_err(),
+            "{:?}",
+            entries
+        );
+    }
+    assert!(read_dir(&outside).unwrap().next().is_none());
+}
+
+/// Builds a zip archive from `(name, symlink_target, contents)` tuples. Names ending in `/` are
+/// directories.
+#[cfg(feature = "zip")]
+fn zip_archive(entries: &[(&str, Option<&str>, &str)]) -> Vec<u8> {
+    use std::io::{Cursor, Write};
+    use zip::{write::SimpleFileOptions, ZipWriter};
+
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default();
+    for (name, target, contents) in entries {
+        if let Some(target) = target {
+            writer.add_symlink(*name, *target, options).unwrap();
+        } else if name.ends_with('/') {
+            writer.add_directory(*name, options).unwrap();
+        } else {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(contents.as_bytes()).unwrap();
+        }
+    }
+    writer.finish().unwrap().into_inner()
+}
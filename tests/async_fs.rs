@@ -0,0 +1,134 @@
+#![cfg(any(feature = "async-std", feature = "tokio"))]
+
+use safe_path::{
+    async_fs::{AsyncSafeDir, Backend},
+    fs::{Operation, SafeDir},
+};
+use std::fs::{create_dir, read_dir, read_to_string};
+use tempfile::tempdir;
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn tokio() {
+    use safe_path::async_fs::Tokio;
+    use tokio::io::AsyncReadExt;
+
+    file_operations::<Tokio>().await;
+    move_and_copy::<Tokio>().await;
+    dry_run::<Tokio>().await;
+
+    let tempdir = tempdir().unwrap();
+    let dir = AsyncSafeDir::<Tokio>::new(SafeDir::new(tempdir.path()));
+    dir.create_dir_all("x").await.unwrap();
+    dir.write("x/file", "contents").await.unwrap();
+    let mut contents = String::new();
+    dir.open("x/file")
+        .await
+        .unwrap()
+        .read_to_string(&mut contents)
+        .await
+        .unwrap();
+    assert_eq!("contents", contents);
+    let mut read_dir = dir.read_dir("x").await.unwrap();
+    assert_eq!(
+        "file",
+        read_dir.next_entry().await.unwrap().unwrap().file_name()
+    );
+    assert!(read_dir.next_entry().await.unwrap().is_none());
+}
+
+#[cfg(feature = "async-std")]
+#[async_std::test]
+async fn async_std() {
+    use async_std::{io::ReadExt, stream::StreamExt};
+    use safe_path::async_fs::AsyncStd;
+
+    file_operations::<AsyncStd>().await;
+    move_and_copy::<AsyncStd>().await;
+    dry_run::<AsyncStd>().await;
+
+    let tempdir = tempdir().unwrap();
+    let dir = AsyncSafeDir::<AsyncStd>::new(SafeDir::new(tempdir.path()));
+    dir.create_dir_all("x").await.unwrap();
+    dir.write("x/file", "contents").await.unwrap();
+    let mut contents = String::new();
+    dir.open("x/file")
+        .await
+        .unwrap()
+        .read_to_string(&mut contents)
+        .await
+        .unwrap();
+    assert_eq!("contents", contents);
+    let mut read_dir = dir.read_dir("x").await.unwrap();
+    assert_eq!("file", read_dir.next().await.unwrap().unwrap().file_name());
+    assert!(read_dir.next().await.is_none());
+}
+
+async fn file_operations<B: Backend>() {
+    let tempdir = tempdir().unwrap();
+    let dir = AsyncSafeDir::<B>::new(SafeDir::new(tempdir.path()));
+
+    dir.create_dir_all("x/y").await.unwrap();
+    dir.write("x/file", "contents").await.unwrap();
+    assert_eq!(b"contents", &dir.read("x/file").await.unwrap()[..]);
+    assert!(dir.open("x/file").await.is_ok());
+    drop(dir.create("x/empty").await.unwrap());
+    let mut names = read_dir(tempdir.path().join("x"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(vec!["empty", "file", "y"], names);
+    dir.remove_file("x/empty").await.unwrap();
+    assert!(!tempdir.path().join("x/empty").exists());
+
+    assert!(dir.create_dir_all("../x").await.is_err());
+    assert!(dir.write("../file", "contents").await.is_err());
+    assert!(dir.read("x/../../file").await.is_err());
+    assert!(dir.open("/etc/passwd").await.is_err());
+    assert!(dir.create("../file").await.is_err());
+    assert!(dir.remove_file("../file").await.is_err());
+    assert!(dir.read_dir("..").await.is_err());
+}
+
+async fn move_and_copy<B: Backend>() {
+    let tempdir = tempdir().unwrap();
+    create_dir(tempdir.path().join("tmp")).unwrap();
+    create_dir(tempdir.path().join("store")).unwrap();
+    let tmp = AsyncSafeDir::<B>::from(SafeDir::new(tempdir.path().join("tmp")));
+    let store = SafeDir::new(tempdir.path().join("store"));
+
+    tmp.write("upload", "contents").await.unwrap();
+    tmp.copy_file("upload", &store, "copy").await.unwrap();
+    tmp.move_file("upload", &store, "moved").await.unwrap();
+    assert!(tmp.copy_file("moved", &store, "../x").await.is_err());
+    assert!(tmp.move_file("../store/moved", &store, "x").await.is_err());
+    assert!(!tempdir.path().join("tmp/upload").exists());
+    for name in &["copy", "moved"] {
+        assert_eq!("contents", read_to_string(store.root().join(name)).unwrap());
+    }
+}
+
+async fn dry_run<B: Backend>() {
+    let tempdir = tempdir().unwrap();
+    let dir = AsyncSafeDir::<B>::new(SafeDir::new(tempdir.path()).dry_run(true));
+
+    dir.create_dir_all("x").await.unwrap();
+    dir.write("x/file", "contents").await.unwrap();
+    assert!(dir.create("x/file").await.is_err());
+    assert_eq!(
+        vec![
+            Operation::CreateDirAll {
+                path: tempdir.path().join("x")
+            },
+            Operation::Write {
+                path: tempdir.path().join("x/file")
+            },
+            Operation::Write {
+                path: tempdir.path().join("x/file")
+            },
+        ],
+        dir.dir().planned_operations()
+    );
+    assert!(!tempdir.path().join("x").exists());
+}
@@ -0,0 +1,64 @@
+use safe_path::{
+    backend::{self, ComponentKind},
+    Violation,
+};
+
+use ComponentKind::{Normal, ParentDir, Prefix, RootDir, VerbatimPrefix};
+
+#[test]
+fn verbatim_prefix() {
+    for self_is_root in &[false, true] {
+        for path in &[
+            &[VerbatimPrefix, RootDir, Normal][..],
+            &[Normal, VerbatimPrefix, RootDir, Normal],
+            &[VerbatimPrefix, RootDir, Normal, ParentDir, Normal],
+        ] {
+            assert!(
+                backend::check_join_safety(path.iter().copied(), true, || *self_is_root).is_err()
+            );
+        }
+    }
+    assert!(backend::check_join_safety(vec![Prefix, RootDir, Normal], false, || true).is_ok());
+    assert_eq!(
+        vec![Violation::VerbatimPrefix { index: 1 }],
+        backend::explain_join_safety(vec![Normal, VerbatimPrefix, RootDir, Normal], || true)
+    );
+
+    assert!(backend::is_root(vec![VerbatimPrefix, RootDir]));
+    assert!(backend::is_root(vec![
+        VerbatimPrefix,
+        RootDir,
+        Normal,
+        ParentDir
+    ]));
+    assert!(!backend::is_root(vec![VerbatimPrefix, RootDir, Normal]));
+    assert!(backend::check_parent_safety(Some(VerbatimPrefix), false, || true).is_err());
+    assert!(backend::check_parent_safety(Some(VerbatimPrefix), true, || true).is_ok());
+}
+
+#[test]
+fn drive_relative() {
+    for self_is_root in &[false, true] {
+        for path in &[
+            &[Prefix][..],
+            &[Prefix, Normal],
+            &[Prefix, ParentDir, Normal],
+        ] {
+            assert!(
+                backend::check_join_safety(path.iter().copied(), true, || *self_is_root).is_err()
+            );
+        }
+    }
+    assert_eq!(
+        vec![Violation::DriveRelative { index: 0 }],
+        backend::explain_join_safety(vec![Prefix, Normal], || true)
+    );
+    assert_eq!(
+        vec![
+            Violation::DriveRelative { index: 0 },
+            Violation::Absolute { index: 2 },
+            Violation::Absolute { index: 3 },
+        ],
+        backend::explain_join_safety(vec![Prefix, Normal, Prefix, RootDir, Normal], || false)
+    );
+}
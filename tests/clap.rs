@@ -0,0 +1,51 @@
+#![cfg(feature = "clap")]
+
+use clap::{error::ErrorKind, Arg, Command};
+use safe_path::{clap::contained_in, Policy};
+use std::path::{Path, PathBuf};
+
+fn command() -> Command {
+    Command::new("test")
+        .arg(
+            Arg::new("path")
+                .long("path")
+                .value_parser(contained_in("www")),
+        )
+        .arg(
+            Arg::new("visible")
+                .long("visible")
+                .value_parser(contained_in("www").policy(Policy::new().no_hidden(true))),
+        )
+}
+
+#[test]
+fn contained_in_accepts() {
+    for (value, expected) in &[("x", "www/x"), ("x/../y", "www/x/../y")] {
+        let matches = command()
+            .try_get_matches_from(["test", "--path", value])
+            .unwrap();
+        assert_eq!(
+            Path::new(expected),
+            matches.get_one::<PathBuf>("path").unwrap()
+        );
+    }
+}
+
+#[test]
+fn contained_in_rejects() {
+    for (name, value) in &[
+        ("--path", ".."),
+        ("--path", "x/../.."),
+        ("--path", "/etc/passwd"),
+        ("--path", "."),
+        ("--visible", ".env"),
+    ] {
+        let error = command()
+            .try_get_matches_from(["test", name, value])
+            .unwrap_err();
+        assert_eq!(ErrorKind::ValueValidation, error.kind(), "{:?}", value);
+        let message = error.to_string();
+        assert!(message.contains(value), "{}", message);
+        assert!(message.contains(name), "{}", message);
+    }
+}
@@ -6,12 +6,11 @@ use std::{
     path::{Component, Path, PathBuf},
 };
 
-#[cfg_attr(
-    feature = "fuzz",
-    derive(Clone, Debug, serde::Deserialize, serde::Serialize)
-)]
+#[cfg(feature = "fuzz")]
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
 pub struct PathBufWrapper(PathBuf);
 
+#[cfg(feature = "fuzz")]
 impl From<&Path> for PathBufWrapper {
     fn from(path: &Path) -> Self {
         PathBufWrapper(path.to_path_buf())
@@ -28,8 +27,7 @@ impl test_fuzz::Into<&Path> for PathBufWrapper {
 pub fn fresh_normal(paths: &[&Path]) -> String {
     let n = paths
         .iter()
-        .map(|path| path.components())
-        .flatten()
+        .flat_map(|path| path.components())
         .fold(0, |n, component| {
             if let Component::Normal(s) = component {
                 max(n, s.len())
@@ -52,7 +50,9 @@ pub fn adopt(n: usize, x: &str, path: &Path) -> PathBuf {
     }
 }
 
-pub const NORMALIZATION_FUNCTIONS: &[(&str, &dyn Fn(&Path) -> PathBuf)] = &[
+pub type NormalizationFunction = dyn Fn(&Path) -> PathBuf;
+
+pub const NORMALIZATION_FUNCTIONS: &[(&str, &NormalizationFunction)] = &[
     ("normalize_path", &normalize_path),
     ("lexiclean", &|path: &Path| Lexiclean::lexiclean(path)),
     ("path_clean", &|path: &Path| {
@@ -0,0 +1,77 @@
+#![cfg(any(feature = "json", feature = "toml"))]
+
+use safe_path::Policy;
+use std::io::ErrorKind;
+
+#[cfg(feature = "toml")]
+#[test]
+fn from_toml_str() {
+    use safe_path::{Action, Separators};
+
+    let policy = Policy::from_toml_str(
+        r#"
+        strict = true
+        no_hidden = true
+        no_tilde = true
+        deny = [".git"]
+        max_len = 255
+        allowed_chars = "abcdefghijklmnopqrstuvwxyz./"
+        allow_extensions = ["html", "css"]
+        divert = "decoy"
+        separators = "reject"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(
+        Policy::new()
+            .strict(true)
+            .no_hidden(true)
+            .no_tilde(true)
+            .deny(".git")
+            .max_len(255)
+            .allowed_chars("abcdefghijklmnopqrstuvwxyz./".chars())
+            .allow_extension("html")
+            .allow_extension("css")
+            .on_reject(Action::Divert("decoy".into()))
+            .separators(Separators::Reject),
+        policy
+    );
+    assert_eq!(Policy::new(), Policy::from_toml_str("").unwrap());
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn from_reader() {
+    let policy =
+        Policy::from_reader(&br#"{ "no_parent": true, "deny_extensions": ["php"] }"#[..]).unwrap();
+    assert_eq!(Policy::new().no_parent(true).deny_extension("php"), policy);
+    let error = Policy::from_reader(&br#"{ "no_parent": 1 }"#[..]).unwrap_err();
+    assert_eq!(ErrorKind::InvalidData, error.kind());
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn diagnostics() {
+    for (config, message) in &[
+        ("no_hiden = true", "unknown field `no_hiden`"),
+        ("max_depth = -1", "max_depth"),
+        ("separators = \"collapse\"", "unknown variant `collapse`"),
+        ("deny = [\"x/.git\"]", "never matches"),
+        ("deny = [\"..\"]", "never matches"),
+        ("max_len = 0", "`max_len` is 0"),
+        ("allowed_chars = \"\"", "`allowed_chars` is empty"),
+        ("allow_extensions = [\".png\"]", "must not contain `.`"),
+        (
+            "allow_extensions = [\"php\"]\ndeny_extensions = [\"PHP\"]",
+            "both allowed and denied",
+        ),
+        (
+            "allowed_chars = \"abc\"\nallow_extensions = [\"abc\"]",
+            "does not include `.`",
+        ),
+    ] {
+        let error = Policy::from_toml_str(config).unwrap_err();
+        assert_eq!(ErrorKind::InvalidData, error.kind(), "{:?}", config);
+        assert!(error.to_string().contains(message), "{}", error);
+    }
+}
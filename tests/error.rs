@@ -0,0 +1,75 @@
+#![cfg(feature = "std-path")]
+
+use safe_path::{Error, SafePath};
+use std::{
+    error::Error as _,
+    io::{self, ErrorKind},
+    path::Path,
+};
+
+#[test]
+fn check_failure() {
+    let err = Path::new("x").safe_join("..").unwrap_err();
+    assert_eq!(ErrorKind::Other, err.kind());
+    let payload = Error::downcast_ref(&err).unwrap();
+    assert_eq!("unsafe join operation", payload.context());
+    assert!(payload.path().is_none());
+    assert!(payload.source().is_none());
+}
+
+#[test]
+fn os_error_is_source() {
+    let os_err = io::Error::from_raw_os_error(2);
+    let kind = os_err.kind();
+    let err = Error::wrap_os_error("open", Path::new("x/y"), os_err);
+    assert_eq!(kind, err.kind());
+    let payload = Error::downcast_ref(&err).unwrap();
+    assert_eq!("open", payload.context());
+    assert_eq!(Some(Path::new("x/y")), payload.path());
+    assert_eq!(Some(2), payload.raw_os_error());
+    let source = payload
+        .source()
+        .unwrap()
+        .downcast_ref::<io::Error>()
+        .unwrap();
+    assert_eq!(Some(2), source.raw_os_error());
+    assert_eq!("open: x/y", payload.to_string());
+}
+
+#[test]
+fn redacted() {
+    let os_err = io::Error::from_raw_os_error(2);
+    let kind = os_err.kind();
+    let err = Error::wrap_os_error("open", Path::new("/srv/www/x/passwd"), os_err);
+    let redacted = Error::redact(&err);
+    assert_eq!(kind, redacted.kind());
+    let payload = Error::downcast_ref(&redacted).unwrap();
+    assert_eq!("open", payload.context());
+    assert_eq!(Some(2), payload.raw_os_error());
+    let path = payload.path().unwrap();
+    assert!(!path.starts_with("/"));
+    assert_eq!(Some("passwd".as_ref()), path.file_name());
+    assert!(!payload.to_string().contains("srv"));
+
+    let other = Error::wrap_os_error("open", Path::new("/srv/www/y/passwd"), kind.into());
+    assert_ne!(
+        path,
+        Error::downcast_ref(&Error::redact(&other))
+            .unwrap()
+            .path()
+            .unwrap()
+    );
+
+    let long = Error::wrap_os_error("open", "x".repeat(100), kind.into());
+    let long = Error::downcast_ref(&long).unwrap().redacted();
+    assert_eq!(
+        Error::REDACTED_NAME_LEN,
+        long.path().unwrap().file_name().unwrap().len()
+    );
+
+    let check = Path::new("x").safe_join("..").unwrap_err();
+    assert_eq!(
+        "unsafe join operation: component 0 escapes the base directory",
+        Error::redact(&check).to_string()
+    );
+}
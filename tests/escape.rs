@@ -0,0 +1,74 @@
+#![cfg(feature = "std-path")]
+
+use safe_path::{
+    escape::{escape, unescape},
+    Error,
+};
+use std::ffi::OsStr;
+
+#[test]
+fn round_trip() {
+    for s in &[
+        "",
+        "x",
+        "caf\u{e9}",
+        "a\\b",
+        "\\x41",
+        "\\u{a}",
+        "\n\t\u{7f}\u{85}",
+        "\u{202e}",
+    ] {
+        let escaped = escape(OsStr::new(s));
+        assert!(!escaped.chars().any(char::is_control), "{:?}", escaped);
+        assert_eq!(OsStr::new(s), unescape(&escaped).unwrap());
+    }
+    assert_eq!("\\\\x41", escape(OsStr::new("\\x41")));
+    assert_eq!("\\u{7f}\\u{85}", escape(OsStr::new("\u{7f}\u{85}")));
+}
+
+#[test]
+fn invalid() {
+    for escaped in &[
+        "\\",
+        "\\q",
+        "\\u{}",
+        "\\u{110000}",
+        "\\u{41}",
+        "\\u41",
+        "\n",
+    ] {
+        let err = unescape(escaped).unwrap_err();
+        assert_eq!(
+            "invalid escaped string",
+            Error::downcast_ref(&err).unwrap().context(),
+            "{:?}",
+            escaped
+        );
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn non_utf8() {
+    use safe_path::FileName;
+    use std::os::unix::ffi::OsStrExt;
+
+    for bytes in &[&b"\xff"[..], b"caf\xe9", b"\xe9\xe9x\xc3", b"\xc3\xa9\xc3"] {
+        let s = OsStr::from_bytes(bytes);
+        assert_eq!(s, unescape(&escape(s)).unwrap());
+    }
+    assert_eq!(
+        "\\xe9\\xe9x\\xc3",
+        escape(OsStr::from_bytes(b"\xe9\xe9x\xc3"))
+    );
+    assert_eq!("\u{e9}\\xc3", escape(OsStr::from_bytes(b"\xc3\xa9\xc3")));
+    for escaped in &["\\x", "\\xe", "\\x41", "\\xE9", "\\xc3\\xa9", "\\x+1"] {
+        assert!(unescape(escaped).is_err(), "{:?}", escaped);
+    }
+
+    let name = FileName::from_bytes(b"\xff.txt").unwrap();
+    assert_eq!(b"\xff.txt", name.as_bytes());
+    assert_eq!("\u{fffd}.txt", name.to_string());
+    assert_eq!("\\xff.txt", escape(name.as_os_str()));
+    assert!(FileName::from_bytes(b"x/\xff").is_err());
+}
@@ -0,0 +1,93 @@
+#![cfg(feature = "std-path")]
+
+use safe_path::{FileName, SafePath};
+use std::path::Path;
+
+#[test]
+fn file_name() {
+    for name in &["x", "x.txt", ".x", "..x"] {
+        assert!(FileName::new(name).is_ok(), "{:?}", name);
+    }
+    for name in &["", ".", "..", "/", "/x", "./x", "x/", "x/y", "x/.."] {
+        assert!(FileName::new(name).is_err(), "{:?}", name);
+    }
+}
+
+#[test]
+fn safe_join_component() {
+    let dir = Path::new("dir");
+    assert_eq!(Path::new("dir/x"), dir.safe_join_component("x").unwrap());
+    for path in &["", ".", "..", "/", "./x", "x/y", "x/.."] {
+        assert!(dir.safe_join_component(path).is_err(), "{:?}", path);
+    }
+    let name = FileName::new("x").unwrap();
+    assert_eq!(Path::new("dir/x"), dir.safe_join_component(&name).unwrap());
+}
+
+#[test]
+fn safe_with_file_name() {
+    let path = Path::new("dir/x.txt");
+    assert_eq!(Path::new("dir/y"), path.safe_with_file_name("y").unwrap());
+    for name in &["", ".", "..", "/", "/y", "../y", "y/z"] {
+        assert!(path.safe_with_file_name(name).is_err(), "{:?}", name);
+    }
+}
+
+#[test]
+fn safe_with_extension() {
+    let path = Path::new("dir/x.txt");
+    assert_eq!(
+        Path::new("dir/x.md"),
+        path.safe_with_extension("md").unwrap()
+    );
+    assert_eq!(
+        Path::new("dir/x.tar.gz"),
+        path.safe_with_extension("tar.gz").unwrap()
+    );
+    assert_eq!(Path::new("dir/x"), path.safe_with_extension("").unwrap());
+    for extension in &[".", "..", "/", "/y", "../y", "y/z", "y/../../z"] {
+        assert!(
+            path.safe_with_extension(extension).is_err(),
+            "{:?}",
+            extension
+        );
+    }
+}
+
+#[test]
+fn from_str() {
+    use std::convert::TryFrom;
+
+    let name: FileName = "x.txt".parse().unwrap();
+    assert_eq!(Path::new("x.txt"), name.as_path());
+    assert_eq!(name, FileName::try_from("x.txt").unwrap());
+    assert!("../x.txt".parse::<FileName>().is_err());
+    assert!(FileName::try_from("x/y").is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde() {
+    let name: FileName = serde_json::from_str(r#""x.txt""#).unwrap();
+    assert_eq!(Path::new("x.txt"), name.as_path());
+    assert_eq!(r#""x.txt""#, serde_json::to_string(&name).unwrap());
+    for json in &[r#""""#, r#"".""#, r#""../x""#, r#""x/y""#] {
+        assert!(
+            serde_json::from_str::<FileName>(json).is_err(),
+            "{:?}",
+            json
+        );
+    }
+}
+
+#[cfg(feature = "schemars")]
+#[test]
+fn json_schema() {
+    let schema = schemars::schema_for!(FileName);
+    assert_eq!(
+        Some("string"),
+        schema.get("type").and_then(|value| value.as_str())
+    );
+    assert!(schema.get("pattern").is_some());
+    assert!(schema.get("description").is_some());
+}
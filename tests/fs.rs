@@ -0,0 +1,798 @@
+#![cfg(feature = "fs")]
+
+use safe_path::{
+    fs::{safe_join_cwd, FanOut, Operation, SafeDir, SafeDirTransaction},
+    Action, Error, Policy,
+};
+use std::{
+    fs::{create_dir, read_dir, read_to_string, write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use tempfile::tempdir;
+
+#[test]
+fn move_file() {
+    let tempdir = tempdir().unwrap();
+    create_dir(tempdir.path().join("staging")).unwrap();
+    create_dir(tempdir.path().join("final")).unwrap();
+    let staging = SafeDir::new(tempdir.path().join("staging"));
+    let published = SafeDir::new(tempdir.path().join("final"));
+
+    write(staging.root().join("x"), "x").unwrap();
+    staging.move_file("x", &published, "y").unwrap();
+    assert!(!staging.root().join("x").exists());
+    assert_eq!("x", read_to_string(published.root().join("y")).unwrap());
+
+    assert!(staging.move_file("../final/y", &published, "z").is_err());
+    assert!(published.move_file("y", &staging, "../y").is_err());
+    assert!(published.root().join("y").exists());
+}
+
+#[test]
+fn transfer() {
+    let tempdir = tempdir().unwrap();
+    create_dir(tempdir.path().join("quarantine")).unwrap();
+    create_dir(tempdir.path().join("published")).unwrap();
+    let quarantine = SafeDir::new(tempdir.path().join("quarantine"));
+    let published = SafeDir::new(tempdir.path().join("published"));
+
+    write(quarantine.root().join("x"), "x").unwrap();
+    let transfers = quarantine.transfer(&["x", "../published/x", "missing"], &published);
+    assert_eq!(3, transfers.len());
+    assert_eq!(
+        published.root().join("x"),
+        *transfers[0].result.as_ref().unwrap()
+    );
+    assert!(transfers[1].result.is_err());
+    assert!(transfers[2].result.is_err());
+    assert_eq!("x", read_to_string(published.root().join("x")).unwrap());
+}
+
+#[test]
+fn divert() {
+    let tempdir = tempdir().unwrap();
+    create_dir(tempdir.path().join("www")).unwrap();
+    create_dir(tempdir.path().join("decoy")).unwrap();
+    let rejections = Arc::new(Mutex::new(Vec::new()));
+    let policy = Policy::new().on_reject(Action::Divert(tempdir.path().join("decoy")));
+    let www = SafeDir::with_policy(tempdir.path().join("www"), policy).on_rejection({
+        let rejections = rejections.clone();
+        move |path, _| rejections.lock().unwrap().push(path.to_path_buf())
+    });
+
+    write(www.root().join("x"), "x").unwrap();
+    write(tempdir.path().join("decoy/.._secret"), "decoy").unwrap();
+    assert_eq!(www.root().join("x"), www.join("x").unwrap());
+    assert_eq!(
+        "decoy",
+        read_to_string(www.join("../secret").unwrap()).unwrap()
+    );
+    assert_eq!(tempdir.path().join("decoy/_"), www.join("..").unwrap());
+    assert_eq!(
+        vec![PathBuf::from("../secret"), PathBuf::from("..")],
+        *rejections.lock().unwrap()
+    );
+}
+
+#[test]
+fn snapshot() {
+    let tempdir = tempdir().unwrap();
+    let dir = SafeDir::with_policy(tempdir.path(), Policy::new().no_hidden(true));
+    create_dir(tempdir.path().join("x")).unwrap();
+    write(tempdir.path().join("x/y"), "y").unwrap();
+    write(tempdir.path().join("z"), "z").unwrap();
+    write(tempdir.path().join(".hidden"), "").unwrap();
+
+    let snapshot = dir.snapshot_with_hashes().unwrap();
+    let paths = snapshot
+        .entries()
+        .iter()
+        .map(|entry| entry.path.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![PathBuf::from("x"), PathBuf::from("x/y"), PathBuf::from("z")],
+        paths
+    );
+    assert!(snapshot.get("x").unwrap().file_type.is_dir());
+    assert!(snapshot.get("x/y").unwrap().hash.is_some());
+    assert!(snapshot.get(".hidden").is_none());
+    dir.verify_unchanged(&snapshot).unwrap();
+
+    write(tempdir.path().join(".hidden"), "ignored").unwrap();
+    dir.verify_unchanged(&snapshot).unwrap();
+
+    write(tempdir.path().join("x/y"), "w").unwrap();
+    let err = dir.verify_unchanged(&snapshot).unwrap_err();
+    assert_eq!(
+        Some(tempdir.path().join("x/y").as_path()),
+        Error::downcast_ref(&err).unwrap().path()
+    );
+    let snapshot = dir.snapshot().unwrap();
+    assert!(snapshot.get("x/y").unwrap().hash.is_none());
+
+    // smoelius: Adding `x/w` may also change `x`'s modification time, in which case `x` is the
+    // first difference.
+    write(tempdir.path().join("x/w"), "w").unwrap();
+    let err = dir.verify_unchanged(&snapshot).unwrap_err();
+    let path = Error::downcast_ref(&err).unwrap().path().unwrap();
+    assert!(path == tempdir.path().join("x") || path == tempdir.path().join("x/w"));
+}
+
+#[test]
+fn fan_out() {
+    let tempdir = tempdir().unwrap();
+    let store = SafeDir::new(tempdir.path());
+    let layout = FanOut::new(3, 1);
+    assert_eq!(
+        PathBuf::from("a/b/c/abcd"),
+        layout.relative_path("abcd").unwrap()
+    );
+    for hash in &["", "abc", "ab/cd", "../abcdef", "abc.def", "abcdé"] {
+        assert!(layout.relative_path(hash).is_err(), "{:?}", hash);
+    }
+    assert_eq!(
+        PathBuf::from("abc"),
+        FanOut::new(0, 2).relative_path("abc").unwrap()
+    );
+
+    let path = store.create_fan_out_path(&layout, "0123").unwrap();
+    assert_eq!(tempdir.path().join("0/1/2/0123"), path);
+    assert!(tempdir.path().join("0/1/2").is_dir());
+    assert!(!path.exists());
+}
+
+#[test]
+fn dry_run() {
+    let tempdir = tempdir().unwrap();
+    let store = SafeDir::new(tempdir.path()).dry_run(true);
+    let other = store.clone();
+    assert!(store.is_dry_run());
+
+    write(tempdir.path().join("x"), "x").unwrap();
+    store.move_file("x", &store, "y").unwrap();
+    let path = other
+        .create_fan_out_path(&FanOut::default(), "abcdef")
+        .unwrap();
+    assert!(store.move_file("x", &store, "../y").is_err());
+    let transfers = store.transfer(&["x", "/x"], &store);
+    assert!(transfers[0].result.is_ok());
+    assert!(transfers[1].result.is_err());
+
+    assert_eq!(
+        vec![
+            Operation::Move {
+                from: tempdir.path().join("x"),
+                to: tempdir.path().join("y"),
+            },
+            Operation::CreateDirAll {
+                path: tempdir.path().join("ab/cd"),
+            },
+            Operation::Move {
+                from: tempdir.path().join("x"),
+                to: tempdir.path().join("x"),
+            },
+        ],
+        store.planned_operations()
+    );
+    assert_eq!(tempdir.path().join("ab/cd/abcdef"), path);
+    assert!(tempdir.path().join("x").exists());
+    assert!(!tempdir.path().join("y").exists());
+    assert!(!tempdir.path().join("ab").exists());
+
+    let store = store.dry_run(false);
+    assert!(store.planned_operations().is_empty());
+    store.move_file("x", &store, "y").unwrap();
+    assert!(tempdir.path().join("y").exists());
+}
+
+#[test]
+fn transaction() {
+    let tempdir = tempdir().unwrap();
+    let dir = SafeDir::new(tempdir.path());
+    write(tempdir.path().join("config"), "old").unwrap();
+    write(tempdir.path().join("x"), "x").unwrap();
+
+    let mut transaction = dir.transaction().unwrap();
+    transaction.create_dir("bin").unwrap();
+    transaction.write("bin/tool", "tool").unwrap();
+    transaction.write("config", "new").unwrap();
+    transaction.rename("x", "bin/x").unwrap();
+    assert!(transaction.create_dir("../bin").is_err());
+    assert!(transaction.rename("x", "/x").is_err());
+    assert!(!tempdir.path().join("bin").exists());
+    transaction.commit().unwrap();
+
+    assert_eq!(
+        "tool",
+        read_to_string(tempdir.path().join("bin/tool")).unwrap()
+    );
+    assert_eq!(
+        "new",
+        read_to_string(tempdir.path().join("config")).unwrap()
+    );
+    assert_eq!("x", read_to_string(tempdir.path().join("bin/x")).unwrap());
+    assert_eq!(vec!["bin", "config"], entries(tempdir.path()));
+}
+
+#[test]
+fn transaction_rollback() {
+    let tempdir = tempdir().unwrap();
+    let dir = SafeDir::new(tempdir.path());
+    write(tempdir.path().join("config"), "old").unwrap();
+    write(tempdir.path().join("x"), "x").unwrap();
+
+    let mut transaction = dir.transaction().unwrap();
+    transaction.create_dir("bin").unwrap();
+    transaction.write("config", "new").unwrap();
+    transaction.rename("x", "y").unwrap();
+    transaction.rename("missing", "z").unwrap();
+    assert!(transaction.commit().is_err());
+
+    assert_eq!(
+        "old",
+        read_to_string(tempdir.path().join("config")).unwrap()
+    );
+    assert_eq!(vec!["config", "x"], entries(tempdir.path()));
+
+    let mut transaction = dir.transaction().unwrap();
+    transaction.write("config", "new").unwrap();
+    transaction.rollback();
+    assert_eq!(
+        "old",
+        read_to_string(tempdir.path().join("config")).unwrap()
+    );
+    assert_eq!(vec!["config", "x"], entries(tempdir.path()));
+}
+
+#[test]
+fn transaction_dry_run() {
+    let tempdir = tempdir().unwrap();
+    let dir = SafeDir::new(tempdir.path()).dry_run(true);
+
+    let mut transaction: SafeDirTransaction<'_> = dir.transaction().unwrap();
+    transaction.create_dir("bin").unwrap();
+    transaction.write("bin/tool", "tool").unwrap();
+    transaction.commit().unwrap();
+
+    assert_eq!(
+        vec![
+            Operation::CreateDir {
+                path: tempdir.path().join("bin"),
+            },
+            Operation::Write {
+                path: tempdir.path().join("bin/tool"),
+            },
+        ],
+        dir.planned_operations()
+    );
+    assert!(entries(tempdir.path()).is_empty());
+}
+
+fn entries(path: &Path) -> Vec<String> {
+    let mut entries = read_dir(path)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    entries.sort();
+    entries
+}
+
+#[test]
+fn current() {
+    let current_dir = std::env::current_dir().unwrap();
+    assert_eq!(current_dir, SafeDir::current().unwrap().root());
+    assert_eq!(
+        std::fs::canonicalize(&current_dir).unwrap(),
+        SafeDir::current_canonicalized().unwrap().root()
+    );
+    assert_eq!(current_dir.join("x/y"), safe_join_cwd("x/y").unwrap());
+    for path in &["..", "x/../..", "/x"] {
+        assert!(safe_join_cwd(path).is_err(), "{:?}", path);
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn safe_join_fs() {
+    use safe_path::{fs::safe_join_fs, Violation};
+    use std::os::unix::fs::symlink;
+
+    let tempdir = tempdir().unwrap();
+    let base = tempdir.path().join("base");
+    create_dir(&base).unwrap();
+    create_dir(base.join("dir")).unwrap();
+    create_dir(tempdir.path().join("outside")).unwrap();
+    symlink("dir", base.join("inside")).unwrap();
+    symlink(".", base.join("self")).unwrap();
+    symlink(tempdir.path().join("outside"), base.join("outside")).unwrap();
+    symlink("missing", base.join("dangling")).unwrap();
+
+    for path in &["dir/x", "inside/x", "self/dir", "x/y", "dir/../x"] {
+        assert_eq!(
+            base.join(path),
+            safe_join_fs(&base, path).unwrap(),
+            "{:?}",
+            path
+        );
+    }
+    for (path, index) in &[
+        ("outside", 0),
+        ("outside/x", 0),
+        ("dir/../outside", 2),
+        ("self/../x", 1),
+        ("dangling", 0),
+    ] {
+        let error = safe_join_fs(&base, path).unwrap_err();
+        assert_eq!(
+            Some(&Violation::EscapingSymlink { index: *index }),
+            Error::downcast_ref(&error).unwrap().violation(),
+            "{:?}",
+            path
+        );
+    }
+    assert!(safe_join_fs(&base, "../outside").is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn safe_canonicalize() {
+    use safe_path::fs::safe_canonicalize;
+    use std::os::unix::fs::symlink;
+
+    let tempdir = tempdir().unwrap();
+    let root = tempdir.path().canonicalize().unwrap();
+    let base = root.join("base");
+    create_dir(&base).unwrap();
+    write(base.join("file"), "").unwrap();
+    write(root.join("secret"), "").unwrap();
+    symlink("file", base.join("inside")).unwrap();
+    symlink(root.join("secret"), base.join("outside")).unwrap();
+
+    assert_eq!(
+        base.join("file"),
+        safe_canonicalize(&base, "inside").unwrap()
+    );
+    assert_eq!(
+        base.join("file"),
+        safe_canonicalize(&base, "./file").unwrap()
+    );
+    let error = safe_canonicalize(&base, "outside").unwrap_err();
+    assert_eq!(
+        "unsafe canonicalize operation",
+        Error::downcast_ref(&error).unwrap().context()
+    );
+    let error = safe_canonicalize(&base, "missing").unwrap_err();
+    assert_eq!(
+        "canonicalize failed",
+        Error::downcast_ref(&error).unwrap().context()
+    );
+}
+
+#[cfg(all(feature = "openat2", target_os = "linux"))]
+#[test]
+fn open_beneath() {
+    use safe_path::fs::{create_beneath, open_beneath};
+    use std::{fs::File, io::Read, os::unix::fs::symlink};
+
+    let tempdir = tempdir().unwrap();
+    let base = tempdir.path().join("base");
+    create_dir(&base).unwrap();
+    write(tempdir.path().join("secret"), "secret").unwrap();
+    symlink(tempdir.path().join("secret"), base.join("absolute")).unwrap();
+    symlink("../secret", base.join("relative")).unwrap();
+    let dir = File::open(&base).unwrap();
+
+    let mut file = create_beneath(&dir, "file").unwrap();
+    std::io::Write::write_all(&mut file, b"contents").unwrap();
+    let mut contents = String::new();
+    open_beneath(&dir, "./file")
+        .unwrap()
+        .read_to_string(&mut contents)
+        .unwrap();
+    assert_eq!("contents", contents);
+
+    for path in &["../secret", "absolute", "relative", "/etc/passwd"] {
+        let error = open_beneath(&dir, path).unwrap_err();
+        // smoelius: 18 is `EXDEV`.
+        assert_eq!(
+            Some(18),
+            Error::downcast_ref(&error).unwrap().raw_os_error(),
+            "{:?}",
+            path
+        );
+        assert!(create_beneath(&dir, path).is_err());
+    }
+    assert_eq!(
+        "secret",
+        read_to_string(tempdir.path().join("secret")).unwrap()
+    );
+}
+
+#[cfg(feature = "cap-std")]
+#[test]
+fn cap_std() {
+    use std::convert::TryFrom;
+
+    let tempdir = tempdir().unwrap();
+    let rejected = Arc::new(Mutex::new(Vec::new()));
+    let dir = SafeDir::with_policy(tempdir.path(), Policy::new().no_hidden(true)).on_rejection({
+        let rejected = rejected.clone();
+        move |path, _| rejected.lock().unwrap().push(path.to_path_buf())
+    });
+    let cap_dir = cap_std::fs::Dir::try_from(&dir).unwrap();
+    cap_dir.create_dir(dir.relative("x").unwrap()).unwrap();
+    cap_dir
+        .write(dir.relative("x/y").unwrap(), "contents")
+        .unwrap();
+    assert_eq!(
+        "contents",
+        read_to_string(tempdir.path().join("x/y")).unwrap()
+    );
+    assert!(dir.relative("../y").is_err());
+    assert!(dir.relative("x/.y").is_err());
+    assert_eq!(
+        vec![PathBuf::from("../y"), PathBuf::from("x/.y")],
+        *rejected.lock().unwrap()
+    );
+}
+
+#[test]
+fn file_operations() {
+    let tempdir = tempdir().unwrap();
+    let dir = SafeDir::new(tempdir.path());
+
+    dir.create_dir_all("x/y").unwrap();
+    dir.write("x/y/z", "contents").unwrap();
+    assert_eq!(b"contents".to_vec(), dir.read("x/y/z").unwrap());
+    std::io::Write::write_all(&mut dir.create("x/w").unwrap(), b"w").unwrap();
+    assert_eq!("w", read_to_string(tempdir.path().join("x/w")).unwrap());
+    let mut names = dir
+        .read_dir("x")
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(vec!["w", "y"], names);
+    dir.remove_file("x/w").unwrap();
+    assert!(!tempdir.path().join("x/w").exists());
+
+    assert!(dir.open("../x").is_err());
+    assert!(dir.create("x/../../w").is_err());
+    assert!(dir.read("/etc/passwd").is_err());
+    assert!(dir.write("..", "").is_err());
+    assert!(dir.remove_file("x/../..").is_err());
+    assert!(dir.create_dir_all("../x").is_err());
+    assert!(dir.read_dir(".").is_err());
+
+    let dir = SafeDir::new(tempdir.path()).dry_run(true);
+    dir.write("x/y/z", "").unwrap();
+    dir.remove_file("x/y/z").unwrap();
+    dir.create_dir_all("v").unwrap();
+    assert!(dir.create("u").is_err());
+    assert_eq!(
+        vec![
+            Operation::Write {
+                path: tempdir.path().join("x/y/z")
+            },
+            Operation::RemoveFile {
+                path: tempdir.path().join("x/y/z")
+            },
+            Operation::CreateDirAll {
+                path: tempdir.path().join("v")
+            },
+            Operation::Write {
+                path: tempdir.path().join("u")
+            },
+        ],
+        dir.planned_operations()
+    );
+    assert_eq!(b"contents".to_vec(), dir.read("x/y/z").unwrap());
+    assert_eq!(vec!["x"], entries(tempdir.path()));
+}
+
+#[test]
+fn free_functions() {
+    use safe_path::fs::{safe_create_dir_all, safe_read, safe_remove_file, safe_write};
+
+    let tempdir = tempdir().unwrap();
+    let base = tempdir.path().join("base");
+    create_dir(&base).unwrap();
+
+    safe_create_dir_all(&base, "x/y").unwrap();
+    safe_write(&base, "x/y/z", "contents").unwrap();
+    assert_eq!(b"contents".to_vec(), safe_read(&base, "x/y/z").unwrap());
+    safe_remove_file(&base, "x/y/z").unwrap();
+    assert!(entries(&base.join("x/y")).is_empty());
+
+    assert!(safe_create_dir_all(&base, "../x").is_err());
+    assert!(safe_write(&base, "../z", "").is_err());
+    assert!(safe_read(&base, "x/../../base/x").is_err());
+    assert!(safe_remove_file(&base, "..").is_err());
+    assert_eq!(vec!["base"], entries(tempdir.path()));
+}
+
+#[test]
+fn rename_and_copy() {
+    use safe_path::fs::{safe_copy, safe_rename};
+
+    let tempdir = tempdir().unwrap();
+    create_dir(tempdir.path().join("tmp")).unwrap();
+    create_dir(tempdir.path().join("store")).unwrap();
+    let tmp = SafeDir::new(tempdir.path().join("tmp"));
+    let store = SafeDir::new(tempdir.path().join("store"));
+
+    tmp.write("upload", "contents").unwrap();
+    tmp.copy_file("upload", &store, "copy").unwrap();
+    assert!(tmp.copy_file("upload", &store, "../copy").is_err());
+    assert!(tmp.copy_file("../store/copy", &store, "copy").is_err());
+    safe_copy(tmp.root(), "upload", "backup").unwrap();
+    assert!(safe_copy(tmp.root(), "upload", "../backup").is_err());
+    safe_rename(tmp.root(), "backup", "renamed").unwrap();
+    assert!(safe_rename(tmp.root(), "renamed", "../renamed").is_err());
+    assert!(safe_rename(tmp.root(), "../store/copy", "copy").is_err());
+
+    assert_eq!(vec!["renamed", "upload"], entries(tmp.root()));
+    assert_eq!(vec!["copy"], entries(store.root()));
+    assert_eq!(
+        "contents",
+        read_to_string(store.root().join("copy")).unwrap()
+    );
+
+    let tmp = tmp.dry_run(true);
+    tmp.copy_file("upload", &store, "dry").unwrap();
+    assert_eq!(
+        vec![Operation::Copy {
+            from: tmp.root().join("upload"),
+            to: store.root().join("dry"),
+        }],
+        tmp.planned_operations()
+    );
+    assert_eq!(vec!["copy"], entries(store.root()));
+}
+
+#[cfg(unix)]
+#[test]
+fn remove_dir_all() {
+    use safe_path::{fs::safe_remove_dir_all, Violation};
+    use std::os::unix::fs::symlink;
+
+    let tempdir = tempdir().unwrap();
+    let base = tempdir.path().join("base");
+    let outside = tempdir.path().join("outside");
+    create_dir(&base).unwrap();
+    create_dir(&outside).unwrap();
+    write(outside.join("file"), "contents").unwrap();
+    let dir = SafeDir::new(&base);
+    dir.create_dir_all("cache/x/y").unwrap();
+    dir.write("cache/x/file", "contents").unwrap();
+    symlink(&outside, base.join("cache/x/outside")).unwrap();
+    symlink(&outside, base.join("outside")).unwrap();
+
+    for path in &[".", "cache/..", "cache/x/../.."] {
+        assert!(dir.remove_dir_all(path).is_err(), "{:?}", path);
+    }
+    let error = dir.remove_dir_all("outside/file").unwrap_err();
+    assert_eq!(
+        Some(&Violation::EscapingSymlink { index: 0 }),
+        Error::downcast_ref(&error).unwrap().violation()
+    );
+    assert!(dir.remove_dir_all("../outside").is_err());
+
+    let dry_run = dir.clone().dry_run(true);
+    dry_run.remove_dir_all("cache").unwrap();
+    assert_eq!(
+        vec![Operation::RemoveDirAll {
+            path: base.join("cache")
+        }],
+        dry_run.planned_operations()
+    );
+    assert_eq!(vec!["cache", "outside"], entries(&base));
+
+    safe_remove_dir_all(&base, "cache").unwrap();
+    assert_eq!(vec!["outside"], entries(&base));
+    assert_eq!(vec!["file"], entries(&outside));
+}
+
+#[cfg(unix)]
+#[test]
+fn symlink() {
+    use safe_path::fs::safe_symlink;
+    use std::{fs::read_link, os::unix::fs::symlink};
+
+    let tempdir = tempdir().unwrap();
+    let base = tempdir.path().join("base");
+    create_dir(&base).unwrap();
+    create_dir(tempdir.path().join("outside")).unwrap();
+    let dir = SafeDir::new(&base);
+    dir.create_dir_all("x/y").unwrap();
+    symlink(tempdir.path().join("outside"), base.join("escape")).unwrap();
+
+    for (target, link) in &[("y", "x/a"), ("../x", "x/b"), ("../escape/z", "x/c")] {
+        dir.symlink(target, link).unwrap();
+        assert_eq!(Path::new(target), read_link(base.join(link)).unwrap());
+    }
+    safe_symlink(&base, "x/y", "d").unwrap();
+    for (target, link) in &[
+        ("../../outside", "x/e"),
+        ("..", "x/e"),
+        (".", "e"),
+        ("/etc/passwd", "e"),
+        ("x", "../e"),
+    ] {
+        assert!(
+            dir.symlink(target, link).is_err(),
+            "{:?} {:?}",
+            target,
+            link
+        );
+    }
+
+    dir.symlink_fs("y", "x/f").unwrap();
+    assert!(dir.symlink_fs("../escape/z", "x/g").is_err());
+    assert!(dir.symlink_fs("z", "escape/g").is_err());
+
+    let dry_run = dir.clone().dry_run(true);
+    dry_run.symlink("y", "x/h").unwrap();
+    assert_eq!(
+        vec![Operation::Symlink {
+            target: PathBuf::from("y"),
+            link: base.join("x/h"),
+        }],
+        dry_run.planned_operations()
+    );
+    assert_eq!(vec!["a", "b", "c", "f", "y"], entries(&base.join("x")));
+}
+
+#[cfg(all(feature = "safe-open", unix))]
+#[test]
+fn safe_open() {
+    use safe_path::fs::safe_open;
+    use std::{io::Read, os::unix::fs::symlink};
+
+    let tempdir = tempdir().unwrap();
+    let base = tempdir.path().join("base");
+    create_dir(&base).unwrap();
+    create_dir(base.join("dir")).unwrap();
+    create_dir(tempdir.path().join("outside")).unwrap();
+    write(base.join("dir/file"), "contents").unwrap();
+    write(tempdir.path().join("outside/file"), "secret").unwrap();
+    symlink("dir", base.join("inside")).unwrap();
+    symlink(tempdir.path().join("outside"), base.join("outside")).unwrap();
+    symlink("file", base.join("dir/link")).unwrap();
+    symlink("base", tempdir.path().join("link")).unwrap();
+
+    for (dir, path) in &[
+        (&base, "dir/file"),
+        (&base, "./dir/x/../file"),
+        (&tempdir.path().join("link"), "dir/file"),
+    ] {
+        let mut contents = String::new();
+        safe_open(dir, path)
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!("contents", contents, "{:?}", path);
+    }
+    for path in &[
+        "inside/file",
+        "outside/file",
+        "dir/link",
+        "../outside/file",
+        "dir/missing",
+        "dir/file/x",
+    ] {
+        assert!(safe_open(&base, path).is_err(), "{:?}", path);
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn is_contained_fs() {
+    use safe_path::fs::is_contained_fs;
+    use std::os::unix::fs::symlink;
+
+    let tempdir = tempdir().unwrap();
+    let base = tempdir.path().join("base");
+    create_dir(&base).unwrap();
+    create_dir(base.join("dir")).unwrap();
+    create_dir(tempdir.path().join("outside")).unwrap();
+    symlink(tempdir.path().join("outside"), base.join("outside")).unwrap();
+    symlink("base", tempdir.path().join("link")).unwrap();
+
+    for path in &[
+        base.clone(),
+        base.join("dir"),
+        tempdir.path().join("link/dir"),
+    ] {
+        assert!(is_contained_fs(&base, path).unwrap(), "{:?}", path);
+    }
+    for path in &[
+        base.join("outside"),
+        base.join(".."),
+        tempdir.path().join("outside"),
+    ] {
+        assert!(!is_contained_fs(&base, path).unwrap(), "{:?}", path);
+    }
+    assert!(is_contained_fs(&base, base.join("missing")).is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn same_device() {
+    use safe_path::Violation;
+    use std::os::unix::fs::{symlink, MetadataExt};
+
+    let tempdir = tempdir().unwrap();
+    let base = tempdir.path().join("base");
+    create_dir(&base).unwrap();
+    create_dir(base.join("dir")).unwrap();
+    symlink("dir", base.join("inside")).unwrap();
+    // smoelius: `/proc` is on a different device than the temporary directory on typical Linux
+    // systems. The test is skipped elsewhere.
+    let other = Path::new("/proc");
+    let differs =
+        other.exists() && other.metadata().unwrap().dev() != base.metadata().unwrap().dev();
+    symlink(other, base.join("other")).unwrap();
+
+    let dir = SafeDir::new(&base).same_device(true);
+    for path in &["dir/x", "inside/x", "x/y"] {
+        assert_eq!(base.join(path), dir.join(path).unwrap(), "{:?}", path);
+    }
+    assert!(SafeDir::new(&base).join("other/x").is_ok());
+    if differs {
+        let error = dir.join("other/x").unwrap_err();
+        assert_eq!(
+            Some(&Violation::DeviceBoundary { index: 0 }),
+            Error::downcast_ref(&error).unwrap().violation()
+        );
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn audit_base() {
+    use safe_path::fs::{audit_base, Finding};
+    use std::{
+        fs::{set_permissions, Permissions},
+        os::unix::fs::{symlink, MetadataExt, PermissionsExt},
+    };
+
+    let tempdir = tempdir().unwrap();
+    let base = tempdir.path().join("base");
+    create_dir(&base).unwrap();
+    set_permissions(&base, Permissions::from_mode(0o755)).unwrap();
+    let canonical = base.canonicalize().unwrap();
+    let uid = base.metadata().unwrap().uid();
+    symlink("base", tempdir.path().join("link")).unwrap();
+    write(tempdir.path().join("file"), "").unwrap();
+
+    assert_eq!(Vec::<Finding>::new(), audit_base(&base, None).unwrap());
+    assert_eq!(
+        vec![Finding::Symlink {
+            path: tempdir.path().join("link")
+        }],
+        audit_base(tempdir.path().join("link"), None).unwrap()
+    );
+    assert!(audit_base(&base, Some(uid.wrapping_add(1)))
+        .unwrap()
+        .contains(&Finding::UnexpectedOwner {
+            path: canonical.clone(),
+            uid
+        }));
+    assert_eq!(
+        vec![Finding::NotADirectory {
+            path: tempdir.path().join("file").canonicalize().unwrap()
+        }],
+        audit_base(tempdir.path().join("file"), None).unwrap()
+    );
+
+    set_permissions(&base, Permissions::from_mode(0o1777)).unwrap();
+    assert_eq!(
+        vec![Finding::Writable {
+            path: canonical,
+            mode: base.metadata().unwrap().mode()
+        }],
+        audit_base(&base, None).unwrap()
+    );
+}
@@ -0,0 +1,180 @@
+#![cfg(feature = "std-path")]
+
+use safe_path::{http::StatusTable, Error, FileName, Policy, SafePath};
+use std::{io, path::Path};
+
+fn status(result: io::Result<impl std::fmt::Debug>) -> u16 {
+    Error::downcast_ref(&result.unwrap_err())
+        .unwrap()
+        .http_status()
+}
+
+#[test]
+fn default_table() {
+    let dir = Path::new("x");
+    let policy = Policy::new()
+        .deny(".git")
+        .max_len(10)
+        .allow_extension("png");
+    assert_eq!(404, status(dir.safe_join("../y")));
+    assert_eq!(404, status(dir.safe_join("/y")));
+    assert_eq!(404, status(dir.join("..").safe_parent()));
+    assert_eq!(404, status(dir.safe_join_with(&policy, ".git/y.png")));
+    assert_eq!(400, status(dir.safe_join_with(&policy, "yy/zz/w.png")));
+    assert_eq!(400, status(dir.safe_join_with(&policy, "y.gif")));
+    assert_eq!(400, status(FileName::new("y/z")));
+    assert_eq!(400, status(dir.safe_join_component("y/z")));
+    assert_eq!(400, status(dir.safe_with_extension("y/z")));
+
+    let not_found = Error::wrap_os_error("open", "x/y", io::ErrorKind::NotFound.into());
+    assert_eq!(404, StatusTable::default().io_status(&not_found));
+    let denied = Error::wrap_os_error("open", "x/y", io::ErrorKind::PermissionDenied.into());
+    assert_eq!(500, StatusTable::default().io_status(&denied));
+    assert_eq!(
+        500,
+        StatusTable::default().io_status(&io::Error::other("x"))
+    );
+}
+
+#[test]
+fn custom_table() {
+    let mut table = StatusTable::default();
+    table.traversal = 403;
+    let err = Path::new("x").safe_join("../y").unwrap_err();
+    assert_eq!(
+        403,
+        Error::downcast_ref(&err).unwrap().http_status_with(&table)
+    );
+}
+
+#[cfg(feature = "axum")]
+#[test]
+fn rejection() {
+    use axum_core::response::IntoResponse;
+    use safe_path::http::Rejection;
+
+    let err = Path::new("x").safe_join("../y").unwrap_err();
+    let response = Rejection::from(err).into_response();
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[cfg(feature = "axum")]
+#[tokio::test]
+async fn safe_request_path() {
+    use axum_core::{extract::FromRequestParts, response::IntoResponse};
+    use safe_path::{http::SafeRequestPath, url::Docroot};
+
+    let docroot = Docroot::new("www").prefix("/static/").index("index.html");
+    for (uri, expected) in &[
+        ("/static/x.css", Ok("www/x.css")),
+        ("/static/a%20b/x.css?v=2", Ok("www/a b/x.css")),
+        ("/static", Ok("www/index.html")),
+        ("/static/../x", Err(404)),
+        ("/static/.env", Err(404)),
+        ("/static/%zz", Err(400)),
+        ("/other/x.css", Err(404)),
+    ] {
+        let (mut parts, ()) = ::http::Request::builder()
+            .uri(*uri)
+            .body(())
+            .unwrap()
+            .into_parts();
+        let result = SafeRequestPath::from_request_parts(&mut parts, &docroot)
+            .await
+            .map(|SafeRequestPath(path)| path)
+            .map_err(|rejection| rejection.into_response().status().as_u16());
+        assert_eq!(
+            expected.map(|path| Path::new(path).to_path_buf()),
+            result,
+            "{:?}",
+            uri
+        );
+    }
+}
+
+#[cfg(feature = "warp")]
+#[tokio::test]
+async fn safe_tail() {
+    use safe_path::{
+        http::{safe_tail, Rejection},
+        url::Docroot,
+    };
+    use warp::Filter;
+
+    let filter = warp::path("static").and(safe_tail(Docroot::new("www").index("index.html")));
+    for (path, expected) in &[
+        ("/static/x.css", Ok("www/x.css")),
+        ("/static/a%20b/x.css?v=2", Ok("www/a b/x.css")),
+        ("/static/", Ok("www/index.html")),
+        ("/static/../x", Err(404)),
+        ("/static/.env", Err(404)),
+        ("/static/%zz", Err(400)),
+    ] {
+        let result = warp::test::request()
+            .path(path)
+            .filter(&filter)
+            .await
+            .map_err(|rejection| rejection.find::<Rejection>().unwrap().status());
+        assert_eq!(
+            expected.map(|path| Path::new(path).to_path_buf()),
+            result,
+            "{:?}",
+            path
+        );
+    }
+    assert!(warp::test::request()
+        .path("/other/x.css")
+        .filter(&filter)
+        .await
+        .is_err());
+}
+
+#[cfg(feature = "tower")]
+#[tokio::test]
+async fn validate_path() {
+    use safe_path::{
+        http::{SafeRequestPath, ValidatePathLayer},
+        url::Docroot,
+    };
+    use std::{
+        convert::Infallible,
+        future::{ready, Ready},
+        task::{Context, Poll},
+    };
+    use tower_layer::Layer;
+    use tower_service::Service;
+
+    struct Inner;
+
+    impl Service<::http::Request<()>> for Inner {
+        type Response = ::http::Response<String>;
+        type Error = Infallible;
+        type Future = Ready<Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: ::http::Request<()>) -> Self::Future {
+            let SafeRequestPath(path) = request.extensions().get().unwrap();
+            ready(Ok(::http::Response::new(
+                path.to_string_lossy().into_owned(),
+            )))
+        }
+    }
+
+    let docroot = Docroot::new("www").prefix("/static");
+    let mut service = ValidatePathLayer::new(docroot).layer(Inner);
+    for (uri, status, body) in &[
+        ("/static/x.css", 200, "www/x.css"),
+        ("/static/../x", 404, ""),
+        ("/static/.env", 404, ""),
+        ("/static/%zz", 400, ""),
+        ("/other/x.css", 404, ""),
+    ] {
+        let request = ::http::Request::builder().uri(*uri).body(()).unwrap();
+        let response = service.call(request).await.unwrap();
+        assert_eq!(*status, response.status().as_u16(), "{:?}", uri);
+        assert_eq!(body, response.body(), "{:?}", uri);
+    }
+}
@@ -0,0 +1,214 @@
+#![cfg(feature = "std-path")]
+
+use safe_path::lexical::{
+    lexically_equal, lexically_starts_with, normalize, LexicalKey, Normalized,
+};
+use std::path::Path;
+
+#[test]
+fn normalize_cases() {
+    for (path, expected) in &[
+        ("", "."),
+        (".", "."),
+        ("./.", "."),
+        ("x", "x"),
+        ("x/.", "x"),
+        ("x/..", "."),
+        ("x/../..", ".."),
+        ("../x", "../x"),
+        ("x/y/../z", "x/z"),
+        ("/", "/"),
+        ("/..", "/"),
+        ("/x/../../y", "/y"),
+        ("x//y/", "x/y"),
+    ] {
+        assert_eq!(
+            Path::new(expected),
+            normalize(Path::new(path)).to_path_buf(),
+            "{:?}",
+            path
+        );
+    }
+}
+
+#[test]
+fn spill() {
+    let path = Path::new("a/b/c/d");
+    assert!(!Normalized::<4>::new(path).spilled());
+    assert!(Normalized::<3>::new(path).spilled());
+    assert_eq!(
+        Normalized::<4>::new(path).components(),
+        Normalized::<3>::new(path).components()
+    );
+}
+
+#[test]
+fn lexically_equal_cases() {
+    for (a, b) in &[
+        ("", "."),
+        ("x/y/../z", "x/./z"),
+        ("/../x", "/x"),
+        ("x/", "x"),
+    ] {
+        assert!(lexically_equal(a, b), "{:?} {:?}", a, b);
+    }
+    for (a, b) in &[("x", "/x"), ("x/../../y", "y"), ("x/y", "y/x")] {
+        assert!(!lexically_equal(a, b), "{:?} {:?}", a, b);
+    }
+}
+
+#[test]
+fn lexically_starts_with_cases() {
+    for (a, b) in &[
+        ("x/y", "x"),
+        ("x/y", ""),
+        ("x/z/../y", "x/y"),
+        ("/x/y", "/"),
+        ("../x", ".."),
+    ] {
+        assert!(lexically_starts_with(a, b), "{:?} {:?}", a, b);
+    }
+    for (a, b) in &[("x/../y", "x"), ("xy", "x"), ("x", "x/y"), ("x", "/")] {
+        assert!(!lexically_starts_with(a, b), "{:?} {:?}", a, b);
+    }
+}
+
+#[test]
+fn lexical_key() {
+    use std::collections::HashMap;
+
+    let mut map = HashMap::new();
+    for path in &["a", "./a", "a/.", "a/b/..", "a//"] {
+        *map.entry(LexicalKey::new(path)).or_insert(0) += 1;
+    }
+    map.insert(LexicalKey::from(Path::new("../a")), 0);
+    map.insert(LexicalKey::from(Path::new("/a").to_path_buf()), 0);
+    assert_eq!(3, map.len());
+    assert_eq!(Some(&5), map.get(&LexicalKey::new("a")));
+    assert_eq!(Path::new("a"), LexicalKey::new("./a/b/..").as_path());
+    assert_eq!(Path::new("."), LexicalKey::new("").into_path_buf());
+}
+
+#[cfg(not(windows))]
+#[test]
+fn normalize_prefix_is_identity() {
+    use safe_path::lexical::normalize_prefix;
+    use std::borrow::Cow;
+
+    for path in &[r"\\?\C:\x", r"c:\x", "/x/y", "x"] {
+        assert!(
+            matches!(normalize_prefix(Path::new(path)), Cow::Borrowed(p) if p == Path::new(path))
+        );
+    }
+}
+
+#[cfg(windows)]
+#[test]
+fn normalize_prefix_spellings() {
+    use safe_path::lexical::normalize_prefix;
+
+    for (path, expected) in &[
+        (r"C:\x", r"C:\x"),
+        (r"c:\x", r"C:\x"),
+        (r"\\?\C:\x", r"C:\x"),
+        (r"\\?\c:\x\y", r"C:\x\y"),
+        (r"\\.\C:\x", r"C:\x"),
+        (r"c:x", r"C:x"),
+        (r"\\?\UNC\server\share\x", r"\\server\share\x"),
+        (r"\\server\share\x", r"\\server\share\x"),
+        (r"\\?\C:\x/y", r"\\?\C:\x/y"),
+        (r"\\.\pipe\x", r"\\.\pipe\x"),
+        (r"\x", r"\x"),
+    ] {
+        assert_eq!(
+            Path::new(expected),
+            normalize_prefix(Path::new(path)),
+            "{:?}",
+            path
+        );
+    }
+    assert!(lexically_equal(r"\\?\C:\x\y", r"c:\x\.\y"));
+    assert!(lexically_starts_with(
+        r"\\?\UNC\server\share\x",
+        r"\\server\share"
+    ));
+    assert_eq!(LexicalKey::new(r"\\?\C:\x"), LexicalKey::new(r"C:\x"));
+}
+
+#[test]
+fn long_path_short() {
+    use safe_path::lexical::long_path;
+    use std::borrow::Cow;
+
+    let long = format!("/{}", "x/".repeat(200));
+    for path in &["/x", "x", r"C:\x", &long] {
+        assert!(matches!(long_path(Path::new(path)), Cow::Borrowed(p) if p == Path::new(path)));
+    }
+}
+
+#[cfg(windows)]
+#[test]
+fn long_path_prefixes() {
+    use safe_path::lexical::{long_path, MAX_PATH};
+
+    let name = "x".repeat(MAX_PATH);
+    for (path, expected) in &[
+        (
+            format!(r"C:\a\..\b/{}", name),
+            format!(r"\\?\C:\b\{}", name),
+        ),
+        (
+            format!(r"\\server\share\{}\.", name),
+            format!(r"\\?\UNC\server\share\{}", name),
+        ),
+        (format!(r"\\?\C:\{}", name), format!(r"\\?\C:\{}", name)),
+        (format!(r"a\{}", name), format!(r"a\{}", name)),
+        (format!(r"C:{}", name), format!(r"C:{}", name)),
+    ] {
+        assert_eq!(
+            Path::new(expected),
+            long_path(Path::new(path)),
+            "{:?}",
+            path
+        );
+    }
+}
+
+#[test]
+fn simplified_is_identity() {
+    use safe_path::lexical::simplified;
+    use std::borrow::Cow;
+
+    for path in &["/x", "x", r"C:\x"] {
+        assert!(matches!(simplified(Path::new(path)), Cow::Borrowed(p) if p == Path::new(path)));
+    }
+}
+
+#[cfg(windows)]
+#[test]
+fn simplified_prefixes() {
+    use safe_path::lexical::{simplified, MAX_PATH};
+
+    let name = "x".repeat(MAX_PATH);
+    let long = format!(r"\\?\C:\{}", name);
+    for (path, expected) in &[
+        (r"\\?\C:\x\y", r"C:\x\y"),
+        (r"\\?\C:\", r"C:\"),
+        (r"\\?\UNC\server\share\x", r"\\server\share\x"),
+        (r"C:\x", r"C:\x"),
+        (r"\\?\C:\x\con", r"\\?\C:\x\con"),
+        (r"\\?\C:\x.", r"\\?\C:\x."),
+        (r"\\?\C:\x\..\y", r"\\?\C:\x\..\y"),
+        (r"\\?\C:\x/y", r"\\?\C:\x/y"),
+        (r"\\?\C:\x:y", r"\\?\C:\x:y"),
+        (r"\\?\Volume{x}\y", r"\\?\Volume{x}\y"),
+        (&long, &long),
+    ] {
+        assert_eq!(
+            Path::new(expected),
+            simplified(Path::new(path)),
+            "{:?}",
+            path
+        );
+    }
+}
@@ -0,0 +1,166 @@
+// smoelius: A minimal `PathOps` implementation that relies on the trait's default methods, as a
+// third-party backend might. Only `as_std_path` is overridden, so that `Policy` checks can run.
+
+use safe_path::{
+    backend::{self, ComponentKind},
+    PathOps, Policy, SafePath, Separators, Violation,
+};
+use std::io::Result;
+
+#[derive(Debug)]
+#[repr(transparent)]
+struct Slash(str);
+
+#[derive(Clone, Debug)]
+struct SlashBuf(String);
+
+impl Slash {
+    fn new(s: &str) -> &Self {
+        // smoelius: `Slash` is a `repr(transparent)` wrapper around `str`.
+        unsafe { &*(s as *const str as *const Self) }
+    }
+
+    fn kinds(&self) -> impl DoubleEndedIterator<Item = ComponentKind> + Clone + '_ {
+        let root = if self.0.starts_with('/') {
+            Some(ComponentKind::RootDir)
+        } else {
+            None
+        };
+        root.into_iter().chain(
+            self.0
+                .split('/')
+                .filter(|component| !component.is_empty())
+                .map(|component| match component {
+                    "." => ComponentKind::CurDir,
+                    ".." => ComponentKind::ParentDir,
+                    _ => ComponentKind::Normal,
+                }),
+        )
+    }
+}
+
+impl AsRef<Slash> for Slash {
+    fn as_ref(&self) -> &Slash {
+        self
+    }
+}
+
+impl AsRef<Slash> for str {
+    fn as_ref(&self) -> &Slash {
+        Slash::new(self)
+    }
+}
+
+impl AsRef<Slash> for SlashBuf {
+    fn as_ref(&self) -> &Slash {
+        Slash::new(&self.0)
+    }
+}
+
+impl PathOps for Slash {
+    type PathBuf = SlashBuf;
+
+    fn join<P: AsRef<Self>>(&self, path: P) -> SlashBuf {
+        let path = &path.as_ref().0;
+        if self.0.is_empty() || path.starts_with('/') {
+            SlashBuf(path.to_owned())
+        } else {
+            SlashBuf(format!("{}/{}", self.0.trim_end_matches('/'), path))
+        }
+    }
+
+    fn parent(&self) -> Option<&Self> {
+        let s = self.0.trim_end_matches('/');
+        if s.is_empty() {
+            return None;
+        }
+        match s.rfind('/') {
+            Some(0) => Some(Self::new("/")),
+            Some(i) => Some(Self::new(&s[..i])),
+            None => Some(Self::new("")),
+        }
+    }
+
+    fn as_std_path(&self) -> Option<&std::path::Path> {
+        Some(std::path::Path::new(&self.0))
+    }
+
+    fn starts_with<P: AsRef<std::path::Path>>(&self, base: P) -> bool {
+        std::path::Path::new(&self.0).starts_with(base)
+    }
+
+    fn check_join_safety(&self, path: &Self, relaxed: bool) -> Result<()> {
+        backend::check_join_safety(path.kinds(), relaxed, || self.is_root())
+    }
+
+    fn check_parent_safety(&self, relaxed: bool) -> Result<()> {
+        backend::check_parent_safety(
+            self.kinds().next_back(),
+            relaxed,
+            || !matches!(self.parent(), Some(parent) if !parent.is_root()),
+        )
+    }
+
+    fn is_root(&self) -> bool {
+        backend::is_root(self.kinds())
+    }
+}
+
+#[test]
+fn safe_join() {
+    let dir = Slash::new("www");
+    assert_eq!("www/a/b", dir.safe_join("a/b").unwrap().0);
+    assert!(dir.safe_join("a/../../x").is_err());
+    assert!(dir.safe_join("/etc/passwd").is_err());
+}
+
+#[test]
+fn strict_safe_join() {
+    assert!(Slash::new("www").strict_safe_join("x").is_ok());
+    assert!(Slash::new("/").strict_safe_join("x").is_err());
+}
+
+#[test]
+fn explain_join() {
+    assert!(Slash::new("www").explain_join("x").is_empty());
+    assert_eq!(
+        vec![Violation::EscapingParentDir { index: 0 }],
+        Slash::new("www").explain_join("../x")
+    );
+}
+
+#[test]
+fn root_of() {
+    assert_eq!(
+        Some("/"),
+        Slash::new("/x/../y").root_of().map(|root| &root.0)
+    );
+    assert!(Slash::new("x/y").root_of().is_none());
+}
+
+#[test]
+fn is_file_name() {
+    assert!(Slash::new("x").is_file_name());
+    for path in &["", ".", "..", "/", "/x", "x/y"] {
+        assert!(!Slash::new(path).is_file_name(), "{:?}", path);
+    }
+}
+
+#[test]
+fn safe_with_file_name() {
+    let path = Slash::new("www/index.html");
+    assert_eq!(
+        "www/about.html",
+        path.safe_with_file_name("about.html").unwrap().0
+    );
+    assert!(path.safe_with_file_name("../.env").is_err());
+    assert!(path.safe_with_extension("bak").is_err());
+}
+
+#[test]
+fn safe_join_with() {
+    let dir = Slash::new("www");
+    assert_eq!("www/x", dir.safe_join_with(&Policy::new(), "x").unwrap().0);
+    let policy = Policy::new().separators(Separators::Normalize);
+    assert!(dir.safe_join_with(&policy, "x").is_err());
+}
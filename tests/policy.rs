@@ -0,0 +1,606 @@
+#![cfg(feature = "std-path")]
+
+use safe_path::{
+    ComponentCheck, Error, PathCheck, Policy, PolicyVersion, SafePath, Separators, Violation,
+};
+use std::path::{Component, Path};
+
+const DIRS: &[&str] = &["/", "x", "x/y"];
+const PATHS: &[&str] = &[
+    "", ".", "..", "/", "/z", "z", "z/..", "z/../w", "../z", "z/../..",
+];
+
+#[test]
+fn equivalences() {
+    for dir in DIRS {
+        let dir = Path::new(dir);
+        for path in PATHS {
+            let check = |policy: Policy| dir.safe_join_with(&policy, path).ok();
+            assert_eq!(dir.safe_join(path).ok(), check(Policy::new()));
+            assert_eq!(
+                dir.relaxed_safe_join(path).ok(),
+                check(Policy::new().relaxed(true))
+            );
+            assert_eq!(
+                dir.strict_safe_join(path).ok(),
+                check(Policy::new().strict(true))
+            );
+            assert_eq!(
+                dir.no_parent_safe_join(path).ok(),
+                check(Policy::new().no_parent(true))
+            );
+        }
+    }
+}
+
+#[test]
+fn combinations() {
+    let dir = Path::new("/");
+    let policy = Policy::new().relaxed(true).strict(true);
+    assert!(dir.safe_join_with(&policy, ".").is_ok());
+    assert!(dir.safe_join_with(&policy, "/z").is_err());
+    assert!(dir.safe_join_with(&policy, "..").is_err());
+
+    let policy = Policy::new().relaxed(true).no_parent(true);
+    assert!(dir.safe_join_with(&policy, ".").is_ok());
+    assert!(dir.safe_join_with(&policy, "z/..").is_err());
+}
+
+#[test]
+fn deny() {
+    let dir = Path::new("x");
+    let policy = Policy::new().deny(".git").deny("node_modules");
+    assert!(dir.safe_join_with(&policy, "y/.gitignore").is_ok());
+    for (path, index) in &[(".git", 0), ("y/.git/config", 1), ("y/../node_modules", 2)] {
+        let err = dir.safe_join_with(&policy, path).unwrap_err();
+        assert_eq!(
+            Some(&Violation::DeniedComponent { index: *index }),
+            Error::downcast_ref(&err).unwrap().violation()
+        );
+    }
+}
+
+#[test]
+fn limits() {
+    let dir = Path::new("x");
+    let policy = Policy::new().max_len(8).max_depth(3).max_component_len(4);
+    assert!(dir.safe_join_with(&policy, "y/z/w").is_ok());
+    for (path, violation) in &[
+        ("yyyy/zzzz", Violation::TooLong),
+        ("y/z/w/v", Violation::TooDeep),
+        ("y/zzzzz", Violation::ComponentTooLong { index: 1 }),
+    ] {
+        let err = dir.safe_join_with(&policy, path).unwrap_err();
+        assert_eq!(
+            Some(violation),
+            Error::downcast_ref(&err).unwrap().violation()
+        );
+    }
+}
+
+#[test]
+fn limits_precede_other_checks() {
+    let dir = Path::new("/srv/www");
+    let policy = Policy::new()
+        .allow_absolute_self(true)
+        .separators(Separators::Reject)
+        .max_len(4);
+    for path in &["/srv/www", "a//b/c"] {
+        let err = dir.safe_join_with(&policy, path).unwrap_err();
+        assert_eq!(
+            Some(&Violation::TooLong),
+            Error::downcast_ref(&err).unwrap().violation()
+        );
+    }
+}
+
+#[test]
+fn allowed_chars() {
+    let dir = Path::new("x");
+    let policy = Policy::new().allowed_chars("abc.".chars());
+    assert!(dir.safe_join_with(&policy, "a/b.c").is_ok());
+    assert!(dir.safe_join_with(&policy, "/").is_err());
+    for (path, index) in &[("d", 0), ("a/b c", 1), ("a/../;", 2)] {
+        let err = dir.safe_join_with(&policy, path).unwrap_err();
+        assert_eq!(
+            Some(&Violation::DisallowedChar { index: *index }),
+            Error::downcast_ref(&err).unwrap().violation()
+        );
+    }
+}
+
+#[test]
+fn version() {
+    let policy = Policy::new().no_parent(true);
+    assert_eq!(PolicyVersion::CURRENT, policy.version());
+    for path in &["z/../w", "../z"] {
+        let err = Path::new("x").safe_join_with(&policy, path).unwrap_err();
+        assert_eq!(
+            Some(PolicyVersion::CURRENT),
+            Error::downcast_ref(&err).unwrap().policy_version()
+        );
+    }
+    let err = Path::new("x").safe_join("../z").unwrap_err();
+    assert_eq!(None, Error::downcast_ref(&err).unwrap().policy_version());
+}
+
+#[test]
+fn no_hidden() {
+    let dir = Path::new("x");
+    let policy = Policy::new().no_hidden(true);
+    assert!(dir.safe_join_with(&policy, "y/z.w").is_ok());
+    assert!(dir.safe_join_with(&policy, "y/./z").is_ok());
+    for (path, index) in &[(".env", 0), ("y/.htpasswd", 1), ("y/../.z", 2)] {
+        let err = dir.safe_join_with(&policy, path).unwrap_err();
+        assert_eq!(
+            Some(&Violation::HiddenComponent { index: *index }),
+            Error::downcast_ref(&err).unwrap().violation()
+        );
+    }
+}
+
+#[test]
+fn extensions() {
+    let dir = Path::new("x");
+    let policy = Policy::new().allow_extension("png").allow_extension("jpg");
+    for path in &["z.png", "y/z.JPG", "y.txt/z.png"] {
+        assert!(dir.safe_join_with(&policy, path).is_ok(), "{:?}", path);
+    }
+    for path in &["z", "z.gif", "z.png/w", "z.png.exe", ".png"] {
+        let err = dir.safe_join_with(&policy, path).unwrap_err();
+        assert_eq!(
+            Some(&Violation::DisallowedExtension),
+            Error::downcast_ref(&err).unwrap().violation(),
+            "{:?}",
+            path
+        );
+    }
+
+    let policy = Policy::new().deny_extension("php");
+    assert!(dir.safe_join_with(&policy, "z").is_ok());
+    assert!(dir.safe_join_with(&policy, "z.html").is_ok());
+    assert!(dir.safe_join_with(&policy, "z.PHP").is_err());
+}
+
+#[derive(Debug)]
+struct NoDigits;
+
+impl ComponentCheck for NoDigits {
+    fn check_component(&self, _: usize, component: Component<'_>) -> Result<(), &'static str> {
+        match component {
+            Component::Normal(name) if name.to_string_lossy().contains(char::is_numeric) => {
+                Err("contains a digit")
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TenantPrefix;
+
+impl PathCheck for TenantPrefix {
+    fn check_path(&self, dir: &Path, result: &Path) -> Result<(), &'static str> {
+        if result.starts_with(dir.join("tenant-a")) {
+            Ok(())
+        } else {
+            Err("outside of tenant")
+        }
+    }
+}
+
+#[test]
+fn custom_checks() {
+    let dir = Path::new("x");
+    let policy = Policy::new()
+        .component_check(NoDigits)
+        .path_check(TenantPrefix);
+    assert!(dir.safe_join_with(&policy, "tenant-a/y").is_ok());
+    for (path, violation) in &[
+        (
+            "tenant-a/y1",
+            Violation::Custom {
+                index: Some(1),
+                reason: "contains a digit",
+            },
+        ),
+        (
+            "tenant-b/y",
+            Violation::Custom {
+                index: None,
+                reason: "outside of tenant",
+            },
+        ),
+    ] {
+        let err = dir.safe_join_with(&policy, path).unwrap_err();
+        assert_eq!(
+            Some(violation),
+            Error::downcast_ref(&err).unwrap().violation()
+        );
+    }
+    // smoelius: The traversal check runs before path checks.
+    let err = dir.safe_join_with(&policy, "../x/tenant-a").unwrap_err();
+    assert_eq!(
+        Some(&Violation::EscapingParentDir { index: 0 }),
+        Error::downcast_ref(&err).unwrap().violation()
+    );
+
+    assert_eq!(policy, policy.clone());
+    assert_ne!(
+        policy,
+        Policy::new()
+            .component_check(NoDigits)
+            .path_check(TenantPrefix)
+    );
+}
+
+#[test]
+fn case_insensitive() {
+    let dir = Path::new("x");
+    let policy = Policy::new().deny(".git").deny("Secrets");
+    assert!(dir.safe_join_with(&policy, ".GIT").is_ok());
+    assert!(dir.safe_join_with(&policy, "secrets").is_ok());
+
+    let policy = policy.case_insensitive(true);
+    for path in &[".git", ".GIT", "y/.Git/z", "SECRETS", "secrets"] {
+        assert!(dir.safe_join_with(&policy, path).is_err(), "{:?}", path);
+    }
+    assert!(dir.safe_join_with(&policy, "Y/../z").is_ok());
+    assert!(dir.safe_join_with(&policy, "Y/../..").is_err());
+}
+
+#[cfg(feature = "unicode-normalization")]
+#[test]
+fn normalization() {
+    use safe_path::NormalizationForm;
+
+    let dir = Path::new("x");
+    let policy = Policy::new().deny("caf\u{e9}").deny("\u{212b}");
+    assert!(dir.safe_join_with(&policy, "cafe\u{301}").is_ok());
+
+    for form in &[NormalizationForm::Nfc, NormalizationForm::Nfd] {
+        let policy = policy.clone().normalization(*form);
+        for path in &["caf\u{e9}", "cafe\u{301}", "y/A\u{30a}", "\u{c5}"] {
+            assert!(dir.safe_join_with(&policy, path).is_err(), "{:?}", path);
+        }
+        assert!(dir.safe_join_with(&policy, "cafe").is_ok());
+        assert!(dir
+            .safe_join_with(&policy.clone().case_insensitive(true), "CAFE\u{301}")
+            .is_err());
+    }
+
+    let policy = Policy::new()
+        .allowed_chars("abcdef\u{e9}".chars())
+        .normalization(NormalizationForm::Nfc);
+    assert!(dir.safe_join_with(&policy, "cafe\u{301}").is_ok());
+}
+
+#[test]
+fn no_invisible() {
+    let dir = Path::new("x");
+    let policy = Policy::new().no_invisible(true);
+    assert!(dir
+        .safe_join_with(&policy, "caf\u{e9}/r\u{e9}sum\u{e9}.pdf")
+        .is_ok());
+    for (path, index) in &[
+        ("\u{202e}fdp.exe", 0),
+        ("y/\u{2067}z", 1),
+        ("y/z/\u{200b}", 2),
+        ("\u{feff}index.html", 0),
+        ("y/.\u{200d}.", 1),
+    ] {
+        let err = dir.safe_join_with(&policy, path).unwrap_err();
+        assert_eq!(
+            Some(&Violation::InvisibleChar { index: *index }),
+            Error::downcast_ref(&err).unwrap().violation(),
+            "{:?}",
+            path
+        );
+    }
+    assert!(dir
+        .safe_join_with(&Policy::new(), "\u{202e}fdp.exe")
+        .is_ok());
+}
+
+#[test]
+fn no_control() {
+    let dir = Path::new("x");
+    let policy = Policy::new().no_control(true);
+    assert!(dir.safe_join_with(&policy, "y/z \u{e9}.txt").is_ok());
+    for (path, index) in &[
+        ("\0", 0),
+        ("y/z\0.png", 1),
+        ("y\n", 0),
+        ("y/\x1b[2J", 1),
+        ("\x7f", 0),
+    ] {
+        let err = dir.safe_join_with(&policy, path).unwrap_err();
+        assert_eq!(
+            Some(&Violation::ControlChar { index: *index }),
+            Error::downcast_ref(&err).unwrap().violation(),
+            "{:?}",
+            path
+        );
+    }
+    assert!(dir.safe_join_with(&Policy::new(), "y\n").is_ok());
+
+    let policy = Policy::hardened();
+    assert_eq!(Policy::new().no_control(true).no_invisible(true), policy);
+    assert!(dir.safe_join_with(&policy, "y\0").is_err());
+    assert!(dir.safe_join_with(&policy, "y\u{200b}").is_err());
+}
+
+#[test]
+fn no_env_vars() {
+    let dir = Path::new("x");
+    let policy = Policy::new().no_env_vars(true);
+    for path in &[
+        "$",
+        "$5",
+        "a$",
+        "100%",
+        "50% off and 20% more",
+        "%1%",
+        "%%",
+        "y/%/z%",
+    ] {
+        assert!(dir.safe_join_with(&policy, path).is_ok(), "{:?}", path);
+    }
+    for (path, index) in &[
+        ("$HOME", 0),
+        ("y/${HOME}", 1),
+        ("y/z$_x", 1),
+        ("%APPDATA%", 0),
+        ("y/a%PROGRAMFILES(X86)%b", 1),
+        ("50%off%", 0),
+    ] {
+        let err = dir.safe_join_with(&policy, path).unwrap_err();
+        assert_eq!(
+            Some(&Violation::EnvVar { index: *index }),
+            Error::downcast_ref(&err).unwrap().violation(),
+            "{:?}",
+            path
+        );
+    }
+    assert!(dir.safe_join_with(&Policy::new(), "$HOME").is_ok());
+}
+
+#[test]
+fn no_tilde() {
+    let dir = Path::new("x");
+    let policy = Policy::new().no_tilde(true);
+    for path in &["y~", "y/z~", "y/a~b", ".~lock"] {
+        assert!(dir.safe_join_with(&policy, path).is_ok(), "{:?}", path);
+    }
+    for (path, index) in &[("~", 0), ("~/.ssh", 0), ("y/~root", 1), ("y/~", 1)] {
+        let err = dir.safe_join_with(&policy, path).unwrap_err();
+        assert_eq!(
+            Some(&Violation::Tilde { index: *index }),
+            Error::downcast_ref(&err).unwrap().violation(),
+            "{:?}",
+            path
+        );
+    }
+    assert!(dir.safe_join_with(&Policy::new(), "~").is_ok());
+}
+
+#[test]
+fn allow_absolute_self() {
+    let dir = Path::new("/srv/www");
+    let policy = Policy::new().allow_absolute_self(true).no_hidden(true);
+    for path in &["/srv/www", "/srv/www/", "/srv/./www/."] {
+        assert_eq!(
+            Path::new(path),
+            dir.safe_join_with(&policy, path).unwrap(),
+            "{:?}",
+            path
+        );
+    }
+    for path in &[
+        "/srv",
+        "/srv/www/x",
+        "/srv/www/x/..",
+        "/srv/x/../www",
+        "../www",
+        "/",
+    ] {
+        assert!(dir.safe_join_with(&policy, path).is_err(), "{:?}", path);
+    }
+    assert!(dir.safe_join_with(&Policy::new(), "/srv/www").is_err());
+    assert!(dir.safe_join_with(&policy, "x").is_ok());
+    assert!(dir.safe_join_with(&policy, "").is_err());
+
+    let dir = Path::new("/home/user/.www");
+    assert!(dir.safe_join_with(&policy, "/home/user/.www").is_ok());
+    assert!(Path::new("www").safe_join_with(&policy, "/www").is_err());
+
+    // smoelius: `case_insensitive` affects only deny-list matching. On a case-sensitive filesystem,
+    // `/SRV/WWW` is a different directory.
+    let policy = policy.case_insensitive(true);
+    assert!(Path::new("/srv/www")
+        .safe_join_with(&policy, "/SRV/WWW")
+        .is_err());
+}
+
+#[test]
+fn no_windows_reserved() {
+    let dir = Path::new("x");
+    let policy = Policy::new().no_windows_reserved(true);
+    for path in &[
+        "console",
+        "com10",
+        "lpt",
+        "y.con",
+        "y/auxiliary.txt",
+        "com0",
+    ] {
+        assert!(dir.safe_join_with(&policy, path).is_ok(), "{:?}", path);
+    }
+    for (path, index) in &[
+        ("CON", 0),
+        ("y/prn.txt", 1),
+        ("aux.tar.gz", 0),
+        ("y/z/NuL", 2),
+        ("com9", 0),
+        ("LPT1.log", 0),
+        ("nul .txt", 0),
+    ] {
+        let err = dir.safe_join_with(&policy, path).unwrap_err();
+        assert_eq!(
+            Some(&Violation::ReservedName { index: *index }),
+            Error::downcast_ref(&err).unwrap().violation(),
+            "{:?}",
+            path
+        );
+    }
+    assert!(dir.safe_join_with(&Policy::new(), "con").is_ok());
+}
+
+#[test]
+fn no_streams() {
+    let dir = Path::new("x");
+    let policy = Policy::new().no_streams(true);
+    assert!(dir.safe_join_with(&policy, "y/z.txt").is_ok());
+    for (path, index) in &[("z.txt:hidden", 0), ("y/z.txt::$DATA", 1), ("y/:", 1)] {
+        let err = dir.safe_join_with(&policy, path).unwrap_err();
+        assert_eq!(
+            Some(&Violation::Stream { index: *index }),
+            Error::downcast_ref(&err).unwrap().violation(),
+            "{:?}",
+            path
+        );
+    }
+    assert!(dir.safe_join_with(&Policy::new(), "y/z.txt:hidden").is_ok());
+}
+
+#[test]
+fn no_trailing_dot_or_space() {
+    let dir = Path::new("x");
+    let policy = Policy::new().no_trailing_dot_or_space(true).deny(".git");
+    for path in &["y/.", "./y", "y/../z", ".y", "y z", "y/z.txt"] {
+        assert!(dir.safe_join_with(&policy, path).is_ok(), "{:?}", path);
+    }
+    for (path, index) in &[(".git.", 0), ("y/.git ", 1), ("...", 0), ("y/z. . ", 1)] {
+        let err = dir.safe_join_with(&policy, path).unwrap_err();
+        assert_eq!(
+            Some(&Violation::TrailingDotOrSpace { index: *index }),
+            Error::downcast_ref(&err).unwrap().violation(),
+            "{:?}",
+            path
+        );
+    }
+    assert!(dir.safe_join_with(&Policy::new(), "y.").is_ok());
+}
+
+#[test]
+fn no_short_names() {
+    let dir = Path::new("x");
+    let policy = Policy::new().no_short_names(true);
+    for path in &[
+        "~1",
+        "x~",
+        "x~y",
+        "abcdefg~1",
+        "x~1.html",
+        "x~1.t.t",
+        "x.~1",
+        "~/x",
+    ] {
+        assert!(dir.safe_join_with(&policy, path).is_ok(), "{:?}", path);
+    }
+    for (path, index) in &[
+        ("PROGRA~1", 0),
+        ("y/git~1", 1),
+        ("GIT~1.TXT", 0),
+        ("y/z/AB12~123", 2),
+        ("x~1.", 0),
+    ] {
+        let err = dir.safe_join_with(&policy, path).unwrap_err();
+        assert_eq!(
+            Some(&Violation::ShortName { index: *index }),
+            Error::downcast_ref(&err).unwrap().violation(),
+            "{:?}",
+            path
+        );
+    }
+    assert!(dir.safe_join_with(&Policy::new(), "PROGRA~1").is_ok());
+}
+
+#[test]
+fn separators() {
+    let dir = Path::new("x");
+    for path in &["y/z", "./y/z", "/", "y", ".."] {
+        let policy = Policy::new().relaxed(true).separators(Separators::Reject);
+        assert!(
+            policy.check(Path::new("/"), Path::new(path)).is_ok(),
+            "{:?}",
+            path
+        );
+    }
+    for path in &["y//z", "y/./z", "y/", "y/.", ".//y"] {
+        let result = dir.safe_join_with(&Policy::new(), path).unwrap();
+        assert_eq!(dir.join(path), result);
+
+        let policy = Policy::new().separators(Separators::Normalize);
+        let result = dir.safe_join_with(&policy, path).unwrap();
+        assert!(
+            result == Path::new("x/y/z") || result == Path::new("x/y"),
+            "{:?}",
+            result
+        );
+
+        let policy = Policy::new().separators(Separators::Reject);
+        let error = dir.safe_join_with(&policy, path).unwrap_err();
+        assert_eq!(
+            Some(&Violation::RedundantSeparator),
+            Error::downcast_ref(&error).unwrap().violation(),
+            "{:?}",
+            path
+        );
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde() {
+    use safe_path::Action;
+
+    #[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+    struct Config {
+        root: String,
+        policy: Policy,
+    }
+
+    let config = Config {
+        root: "www".to_owned(),
+        policy: Policy::new()
+            .no_hidden(true)
+            .deny(".git")
+            .max_depth(8)
+            .allowed_chars("abc.".chars())
+            .allow_extension("html")
+            .on_reject(Action::Divert("decoy".into()))
+            .separators(Separators::Normalize),
+    };
+    let json = serde_json::to_string(&config).unwrap();
+    assert_eq!(config, serde_json::from_str(&json).unwrap());
+
+    let config = serde_json::from_str::<Config>(
+        r#"{ "root": "www", "policy": { "no_parent": true, "separators": "reject" } }"#,
+    )
+    .unwrap();
+    assert_eq!(
+        Policy::new().no_parent(true).separators(Separators::Reject),
+        config.policy
+    );
+
+    for json in &[
+        r#"{ "no_hiden": true }"#,
+        r#"{ "deny": ["x/y"] }"#,
+        r#"{ "allow_extensions": ["php"], "deny_extensions": ["php"] }"#,
+    ] {
+        assert!(serde_json::from_str::<Policy>(json).is_err(), "{}", json);
+    }
+
+    assert!(serde_json::to_string(&Policy::new().component_check(NoDigits)).is_err());
+}
@@ -0,0 +1,21 @@
+#![cfg(feature = "quickcheck")]
+
+use quickcheck::quickcheck;
+use safe_path::{
+    adversarial::{ArbitraryPath, MAX_COMPONENTS},
+    FileName, Policy, SafePath, SafeRelativePath,
+};
+use std::path::Path;
+
+quickcheck! {
+    fn arbitrary_path(path: ArbitraryPath) -> bool {
+        path.0.to_string_lossy().split('/').count() <= MAX_COMPONENTS
+    }
+
+    fn wrappers(name: FileName, path: SafeRelativePath, policy: Policy) -> bool {
+        let _ = Path::new("www").safe_join_with(&policy, &path);
+        FileName::new(name.as_os_str()).is_ok()
+            && SafeRelativePath::new(path.as_path()).is_ok()
+            && Path::new("www").safe_join(&path).is_ok()
+    }
+}
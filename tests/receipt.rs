@@ -0,0 +1,58 @@
+#![cfg(feature = "receipt")]
+
+use safe_path::{
+    receipt::{self, Signer},
+    Policy, PolicyVersion,
+};
+use std::path::Path;
+
+struct Sum;
+
+impl Signer for Sum {
+    fn sign(&self, message: &[u8]) -> Vec<u8> {
+        vec![message
+            .iter()
+            .fold(0u8, |acc, byte| acc.wrapping_add(*byte))]
+    }
+}
+
+#[test]
+fn check() {
+    let dir = Path::new("x");
+    let policy = Policy::new().no_hidden(true);
+
+    let receipt = receipt::check(&policy, dir, Path::new("./y/z/../w"));
+    assert!(receipt.accepted);
+    assert_eq!("y/w", receipt.path);
+    assert_eq!(receipt::root_hash(dir), receipt.root_hash);
+    assert_eq!(PolicyVersion::CURRENT.get(), receipt.policy_version);
+    assert!(receipt.signature.is_none());
+
+    let receipt = receipt::check(&policy, dir, Path::new("y/.env"));
+    assert!(!receipt.accepted);
+}
+
+#[test]
+fn sign_and_verify() {
+    let mut receipt = receipt::check(&Policy::new(), Path::new("x"), Path::new("y"));
+    assert!(!receipt.verify(&Sum));
+    receipt.sign(&Sum);
+    assert!(receipt.verify(&Sum));
+    receipt.accepted = !receipt.accepted;
+    assert!(!receipt.verify(&Sum));
+}
+
+#[cfg(unix)]
+#[test]
+fn non_utf8_path() {
+    use safe_path::escape::unescape;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = Path::new(std::ffi::OsStr::from_bytes(b"x/../\xff"));
+    let receipt = receipt::check(&Policy::new(), Path::new("www"), path);
+    assert_eq!("\\xff", receipt.path);
+    assert_eq!(
+        std::ffi::OsStr::from_bytes(b"\xff"),
+        unescape(&receipt.path).unwrap()
+    );
+}
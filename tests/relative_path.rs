@@ -0,0 +1,124 @@
+#![cfg(feature = "std-path")]
+
+use safe_path::{SafePath, SafeRelativePath};
+use std::path::Path;
+
+#[test]
+fn relative_path() {
+    for path in &["x", "x/y", "./x", "x/", "x/../y", "x/./y/.."] {
+        let relative = SafeRelativePath::new(path).unwrap();
+        assert_eq!(Path::new(path), relative.as_path());
+        for dir in &["www", "../www", "."] {
+            assert!(Path::new(dir).safe_join(&relative).is_ok(), "{:?}", path);
+        }
+    }
+    for path in &[
+        "", ".", "..", "x/..", "x/../..", "/", "/x", "../x", "./../x",
+    ] {
+        assert!(SafeRelativePath::new(path).is_err(), "{:?}", path);
+    }
+}
+
+#[test]
+fn from_components() {
+    let path = SafeRelativePath::from_components(&["x", "..", "y"]).unwrap();
+    assert_eq!(Path::new("x/../y"), path.as_path());
+    assert_eq!(
+        r#"SafeRelativePath(["x", "..", "y"])"#,
+        format!("{:?}", path)
+    );
+    for components in &[
+        &[][..],
+        &["x/y"],
+        &["x/"],
+        &["."],
+        &[""],
+        &["/x"],
+        &["..", "x"],
+        &["x", ".."],
+    ] {
+        assert!(
+            SafeRelativePath::from_components(*components).is_err(),
+            "{:?}",
+            components
+        );
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde() {
+    #[derive(Debug, serde::Deserialize, serde::Serialize)]
+    struct Config {
+        assets: SafeRelativePath,
+    }
+
+    let config: Config = serde_json::from_str(r#"{"assets":"static/img"}"#).unwrap();
+    assert_eq!(Path::new("static/img"), config.assets.as_path());
+    assert_eq!(
+        r#"{"assets":"static/img"}"#,
+        serde_json::to_string(&config).unwrap()
+    );
+    for json in &[
+        r#"{"assets":"../img"}"#,
+        r#"{"assets":"/etc"}"#,
+        r#"{"assets":""}"#,
+    ] {
+        let error = serde_json::from_str::<Config>(json).unwrap_err();
+        assert!(
+            error.to_string().contains("unsafe relative path"),
+            "{:?}",
+            error
+        );
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn deserialize_under() {
+    use safe_path::SafePath;
+
+    let base = Path::new("/srv/www");
+    let cached = serde_json::to_string(&base.safe_join("x/../y").unwrap()).unwrap();
+    let mut deserializer = serde_json::Deserializer::from_str(&cached);
+    let path = SafeRelativePath::deserialize_under(base, &mut deserializer).unwrap();
+    assert_eq!(Path::new("x/../y"), path.as_path());
+
+    for (base, json) in &[
+        ("/srv/www", r#""/srv/www""#),
+        ("/srv/www", r#""/srv/www/x/../..""#),
+        ("/srv/www", r#""/srv/www2/x""#),
+        ("/srv/www", r#""x""#),
+        ("/srv/www/x", r#""/srv/www/y""#),
+    ] {
+        let mut deserializer = serde_json::Deserializer::from_str(json);
+        assert!(
+            SafeRelativePath::deserialize_under(base, &mut deserializer).is_err(),
+            "{}",
+            json
+        );
+    }
+}
+
+#[test]
+fn from_str() {
+    use std::convert::TryFrom;
+
+    let path: SafeRelativePath = "x/y".parse().unwrap();
+    assert_eq!(Path::new("x/y"), path.as_path());
+    assert_eq!(path, SafeRelativePath::try_from("x/y").unwrap());
+    assert!("../x".parse::<SafeRelativePath>().is_err());
+    assert!(SafeRelativePath::try_from("/x").is_err());
+}
+
+#[cfg(feature = "schemars")]
+#[test]
+fn json_schema() {
+    let schema = schemars::schema_for!(SafeRelativePath);
+    assert_eq!(
+        Some("string"),
+        schema.get("type").and_then(|value| value.as_str())
+    );
+    assert!(schema.get("pattern").is_some());
+    assert!(schema.get("description").is_some());
+}
@@ -0,0 +1,31 @@
+#![cfg(feature = "std-path")]
+
+use safe_path::{report::RateLimiter, Policy, SafePath};
+use std::{path::Path, time::Duration};
+
+#[test]
+fn keys() {
+    let limiter = RateLimiter::new(1, Duration::from_secs(3600));
+    let policy = Policy::new().no_hidden(true);
+    let x = Path::new("x");
+    let y = Path::new("y");
+
+    let escape = x.safe_join("..").unwrap_err();
+    assert!(limiter.allow(x, &escape));
+    assert!(!limiter.allow(x, &escape));
+    assert!(limiter.allow(y, &escape));
+
+    let hidden = x.safe_join_with(&policy, ".env").unwrap_err();
+    assert!(limiter.allow(x, &hidden));
+    assert!(!limiter.allow(x, &x.safe_join_with(&policy, "z/.git").unwrap_err()));
+}
+
+#[test]
+fn window() {
+    let limiter = RateLimiter::new(1, Duration::from_secs(0));
+    let x = Path::new("x");
+    let escape = x.safe_join("..").unwrap_err();
+    for _ in 0..3 {
+        assert!(limiter.allow(x, &escape));
+    }
+}
@@ -1,21 +1,22 @@
 #![cfg_attr(nightly, feature(bench_black_box, test))]
+#![cfg(feature = "std-path")]
 
 #[cfg(nightly)]
 extern crate test;
 
-use safe_path::{PathOps, SafePath};
-use std::path::Path;
+use safe_path::{PathOps, SafePath, Violation};
+use std::path::{Component, Path};
 
 mod common;
 use common::{adopt, fresh_normal, NORMALIZATION_FUNCTIONS};
 
-fn test_cases<P, F>(
-    from_str: F,
-) -> (
-    P::PathBuf,
-    Vec<(bool, P::PathBuf)>,
-    Vec<(bool, bool, P::PathBuf)>,
-)
+type TestCases<P> = (
+    <P as PathOps>::PathBuf,
+    Vec<(bool, <P as PathOps>::PathBuf)>,
+    Vec<(bool, bool, <P as PathOps>::PathBuf)>,
+);
+
+fn test_cases<P, F>(from_str: F) -> TestCases<P>
 where
     P: ?Sized + PathOps + AsRef<P>,
     F: Fn(&'static str) -> P::PathBuf,
@@ -118,7 +119,7 @@ fn safe_join_guarantee(expected: bool, relaxed: bool, dir: &Path, path: &Path) {
         }
 
         #[cfg(not(fuzzing))]
-        assert_eq!(left, expected, "{}", info(&path, &np_dir_join_path));
+        assert_eq!(left, expected, "{}", info(path, &np_dir_join_path));
 
         let mut right = true;
 
@@ -129,12 +130,12 @@ fn safe_join_guarantee(expected: bool, relaxed: bool, dir: &Path, path: &Path) {
             right &= np_dir_join_prefix.starts_with(&np_dir);
 
             if left {
-                assert_eq!(left, right, "{}", info(&prefix, &np_dir_join_prefix));
+                assert_eq!(left, right, "{}", info(prefix, &np_dir_join_prefix));
             }
         }
 
         if !left {
-            assert_eq!(left, right, "{}", info(&path, &np_dir_join_path));
+            assert_eq!(left, right, "{}", info(path, &np_dir_join_path));
         }
     }
 }
@@ -155,7 +156,41 @@ where
                 as_std_path(dir.as_ref()),
                 as_std_path(path.as_ref()),
             );
+            assert_eq!(
+                dir.as_ref().safe_join(path).is_ok()
+                    && !as_std_path(path.as_ref())
+                        .components()
+                        .any(|component| component == Component::ParentDir),
+                dir.as_ref().no_parent_safe_join(path).is_ok()
+            );
+            let violations = dir.as_ref().explain_join(path);
+            assert_eq!(
+                dir.as_ref().safe_join(path).is_ok(),
+                violations.is_empty(),
+                "{:?}",
+                violations
+            );
+            assert_eq!(
+                dir.as_ref().relaxed_safe_join(path).is_ok(),
+                violations
+                    .iter()
+                    .all(|violation| *violation == Violation::SelfJoin),
+                "{:?}",
+                violations
+            );
         }
+        let results = dir
+            .as_ref()
+            .safe_join_all(paths.iter().map(|(_, _, path)| path));
+        for ((_, _, path), result) in paths.iter().zip(&results) {
+            assert_eq!(dir.as_ref().safe_join(path).is_ok(), result.is_ok());
+        }
+        assert_eq!(
+            results.iter().all(Result::is_ok),
+            dir.as_ref()
+                .try_safe_join_all(paths.iter().map(|(_, _, path)| path))
+                .is_ok()
+        );
     }
     safe_join_guarantee(
         true,
@@ -183,6 +218,23 @@ fn bench<P>(
     });
 }
 
+#[cfg(nightly)]
+fn bench_single_component<P>(
+    bencher: &mut test::Bencher,
+    from_str: impl Fn(&'static str) -> P::PathBuf,
+    join_like_op: impl Fn(&P, &P),
+) where
+    P: ?Sized + PathOps + AsRef<P>,
+{
+    let normal = from_str("y");
+    let (_, dirs, _) = test_cases::<P, _>(from_str);
+    bencher.iter(|| {
+        for (_, dir) in &dirs {
+            join_like_op(dir.as_ref(), normal.as_ref());
+        }
+    });
+}
+
 macro_rules! mod_body {
     {$path_ty: path, $from_str: expr, $as_std_path: expr} => {
         #[cfg(nightly)]
@@ -232,6 +284,20 @@ macro_rules! mod_body {
                     let _ = black_box(normalize_and_compare(2, $as_std_path, dir, &dir.join(path)));
                 });
             }
+
+            #[bench]
+            fn f_join_single_component(bencher: &mut test::Bencher) {
+                bench_single_component(bencher, $from_str, |dir: &$path_ty, path: &$path_ty| {
+                    let _ = black_box(dir.join(path));
+                });
+            }
+
+            #[bench]
+            fn g_safe_join_single_component(bencher: &mut test::Bencher) {
+                bench_single_component(bencher, $from_str, |dir: &$path_ty, path: &$path_ty| {
+                    let _ = black_box(dir.safe_join(path));
+                });
+            }
         }
     };
 }
@@ -248,3 +314,115 @@ mod camino {
 
     mod_body! {Utf8Path, Utf8PathBuf::from, Utf8Path::as_std_path}
 }
+
+#[cfg(windows)]
+#[test]
+fn verbatim_prefix() {
+    for dir in &[
+        r"C:\",
+        r"\\?\C:\",
+        r"\\?\UNC\server\share\",
+        r"\\server\share\",
+    ] {
+        assert!(Path::new(dir).is_root(), "{:?}", dir);
+    }
+    for dir in &[r"C:\", r"\\?\C:\", r"C:\x"] {
+        for path in &[r"\\?\C:\x", r"\\?\UNC\server\share\x", r"\\?\C:\x\..\.."] {
+            assert!(
+                Path::new(dir).safe_join(path).is_err(),
+                "{:?} {:?}",
+                dir,
+                path
+            );
+        }
+    }
+    assert!(Path::new(r"\\?\C:\x").safe_join("y").is_ok());
+}
+
+#[cfg(windows)]
+#[test]
+fn drive_relative() {
+    for dir in &[r"C:\", r"C:\x", "x"] {
+        for path in &["C:y", "D:y", "c:", r"C:..\y"] {
+            let violations = Path::new(dir).explain_join(path);
+            assert!(
+                violations.contains(&Violation::DriveRelative { index: 0 }),
+                "{:?} {:?}: {:?}",
+                dir,
+                path,
+                violations
+            );
+            assert!(Path::new(dir).relaxed_safe_join(path).is_err());
+        }
+    }
+}
+
+#[cfg(windows)]
+#[test]
+fn prefix_mismatch() {
+    for (dir, path) in &[
+        (r"\\server\share\", r"\\other\share\x"),
+        (r"\\server\share\", r"\\server\other\x"),
+        (r"\\?\UNC\server\share\", r"\\other\share\x"),
+        (r"\\server\share\", r"C:\x"),
+        (r"C:\", r"D:\x"),
+        (r"C:\", r"\\server\share\x"),
+    ] {
+        assert!(
+            Path::new(dir).safe_join(path).is_err(),
+            "{:?} {:?}",
+            dir,
+            path
+        );
+        assert_eq!(
+            Some(&Violation::PrefixMismatch { index: 0 }),
+            Path::new(dir).explain_join(path).first()
+        );
+    }
+    for (dir, path) in &[
+        (r"\\server\share\", r"\\SERVER\Share\x"),
+        (r"\\?\UNC\server\share\", r"\\server\share\x"),
+        (r"\\?\C:\", r"c:\x"),
+    ] {
+        assert!(
+            Path::new(dir).safe_join(path).is_ok(),
+            "{:?} {:?}",
+            dir,
+            path
+        );
+    }
+    for dir in &[r"\\server\share\", r"\\?\UNC\server\share\"] {
+        assert!(Path::new(dir).safe_parent().is_err());
+        assert!(Path::new(dir).join("x").join("..").safe_parent().is_err());
+        assert!(safe_path::lexical::lexically_equal(
+            Path::new(dir).join("y"),
+            Path::new(dir).safe_join(r"x\..\..\y").unwrap()
+        ));
+    }
+}
+
+#[test]
+fn check_join_safety_bytes() {
+    let base = Path::new("srv");
+    for raw in &[&b"x"[..], b"x/y", b"x/../y", b"./x"] {
+        assert!(
+            safe_path::check_join_safety_bytes(base, raw).is_ok(),
+            "{:?}",
+            raw
+        );
+    }
+    for raw in &[&b""[..], b".", b"..", b"x/../..", b"/x"] {
+        assert!(
+            safe_path::check_join_safety_bytes(base, raw).is_err(),
+            "{:?}",
+            raw
+        );
+    }
+    #[cfg(unix)]
+    {
+        assert!(safe_path::check_join_safety_bytes(base, b"caf\xe9/\xff").is_ok());
+        assert!(safe_path::check_join_safety_bytes(base, b"\xff/../..").is_err());
+    }
+    #[cfg(not(unix))]
+    assert!(safe_path::check_join_safety_bytes(base, b"caf\xe9").is_err());
+}
@@ -1,4 +1,5 @@
 #![cfg_attr(nightly, feature(bench_black_box, test))]
+#![cfg(feature = "std-path")]
 
 #[cfg(nightly)]
 extern crate test;
@@ -183,3 +184,64 @@ mod camino {
 
     mod_body! {Utf8Path, Utf8PathBuf::from, Utf8Path::as_std_path}
 }
+
+#[test]
+fn safe_parent_n() {
+    let dir = Path::new("w/x/y");
+    assert_eq!(dir, dir.safe_parent_n(0).unwrap());
+    assert_eq!(Path::new("w/x"), dir.safe_parent_n(1).unwrap());
+    assert_eq!(Path::new("w"), dir.safe_parent_n(2).unwrap());
+    assert_eq!(Path::new(""), dir.safe_parent_n(3).unwrap());
+    assert!(dir.safe_parent_n(4).is_err());
+    assert!(Path::new("w/..").safe_parent_n(1).is_err());
+    assert!(Path::new("w/x/..").safe_parent_n(1).is_err());
+}
+
+#[test]
+fn safe_parent_n_within() {
+    let base = Path::new("/w");
+    let dir = Path::new("/w/x/y");
+    assert_eq!(
+        Path::new("/w/x"),
+        dir.safe_parent_n_within(1, base).unwrap()
+    );
+    assert_eq!(base, dir.safe_parent_n_within(2, base).unwrap());
+    assert!(dir.safe_parent_n_within(3, base).is_err());
+    assert!(dir.safe_parent_n_within(1, Path::new("/v")).is_err());
+    assert_eq!(dir, dir.safe_parent_n_within(0, Path::new("/v")).unwrap());
+}
+
+#[test]
+fn root_of() {
+    for (path, root) in &[
+        ("/", Some("/")),
+        ("/x/../y", Some("/")),
+        ("/..", Some("/")),
+        ("x", None),
+        ("../x", None),
+        ("", None),
+    ] {
+        assert_eq!(root.map(Path::new), Path::new(path).root_of(), "{:?}", path);
+    }
+}
+
+#[cfg(windows)]
+#[test]
+fn windows_roots() {
+    for (path, root) in &[
+        (r"C:\", Some(r"C:\")),
+        (r"C:\x\..", Some(r"C:\")),
+        (r"\\?\C:\", Some(r"\\?\C:\")),
+        (r"\\server\share\", Some(r"\\server\share\")),
+        (r"\\server\share\x", Some(r"\\server\share\")),
+        (r"\\?\UNC\server\share\x", Some(r"\\?\UNC\server\share\")),
+        ("C:", None),
+        ("C:x", None),
+    ] {
+        assert_eq!(root.map(Path::new), Path::new(path).root_of(), "{:?}", path);
+        assert_eq!(root == Some(path), Path::new(path).is_root(), "{:?}", path);
+    }
+    assert!(Path::new(r"\\server\share\x").safe_parent().is_ok());
+    assert!(Path::new(r"\\server\share\").safe_parent().is_err());
+    assert!(Path::new("C:").relaxed_safe_join(r"C:\x").is_err());
+}
@@ -0,0 +1,125 @@
+#![cfg(feature = "std-path")]
+
+use safe_path::{
+    sanitize::{decode, encode, sanitize, sanitize_with, MAX_LEN},
+    FileName, SafePath,
+};
+use std::path::Path;
+
+#[test]
+fn sanitize_cases() {
+    for (name, expected) in &[
+        ("x.txt", "x.txt"),
+        ("", "_"),
+        (".", "_"),
+        ("..", "_"),
+        ("...", "_"),
+        (".x", ".x"),
+        ("x/y", "x_y"),
+        ("x\\y", "x_y"),
+        ("/", "_"),
+        ("C:x", "C_x"),
+        ("x\0y\n", "x_y_"),
+        ("a<b>c:d\"e|f?g*h", "a_b_c_d_e_f_g_h"),
+        ("x. . ", "x"),
+        ("CON", "_CON"),
+        ("nul.tar.gz", "_nul.tar.gz"),
+        ("com1 .txt", "_com1 .txt"),
+        ("console", "console"),
+    ] {
+        assert_eq!(*expected, sanitize(name), "{:?}", name);
+    }
+}
+
+#[test]
+fn replacement() {
+    assert_eq!("x-y", sanitize_with("x/y", "-"));
+    assert_eq!("xy", sanitize_with("x/y", ""));
+    assert_eq!("xy", sanitize_with("x/y", "/"));
+    assert_eq!("_", sanitize_with("..", ""));
+    assert_eq!("_aux", sanitize_with("aux", "."));
+}
+
+#[test]
+fn truncation() {
+    let name = "é".repeat(MAX_LEN);
+    let sanitized = sanitize(&name);
+    assert!(sanitized.len() <= MAX_LEN);
+    assert!(name.starts_with(&sanitized));
+}
+
+#[test]
+fn always_file_name() {
+    let dir = Path::new("dir");
+    for name in &[
+        "", ".", "..", "../..", "/", "//", "./x", "x/..", "..\\..", "\u{0}", " . ",
+    ] {
+        let sanitized = sanitize(name);
+        assert!(FileName::new(&sanitized).is_ok(), "{:?}", name);
+        assert!(dir.safe_join_component(&sanitized).is_ok(), "{:?}", name);
+        assert_eq!(sanitized, FileName::sanitize(name).to_string());
+    }
+}
+
+#[test]
+fn encode_decode() {
+    let dir = Path::new("dir");
+    for name in &[
+        "",
+        ".",
+        "..",
+        "x",
+        "x.txt",
+        ".x",
+        "x.",
+        "x ",
+        " x",
+        "%",
+        "%25",
+        "a/b\\c",
+        "C:x",
+        "x\0y",
+        "\u{85}",
+        "é",
+        "nul",
+        "NUL.txt",
+        "console",
+        "a<b>c:d\"e|f?g*h",
+    ] {
+        let encoded = encode(name);
+        assert!(FileName::new(&encoded).is_ok(), "{:?}", name);
+        assert!(dir.safe_join_component(&encoded).is_ok(), "{:?}", name);
+        assert_eq!(sanitize(&encoded), encoded, "{:?}", name);
+        assert_eq!(*name, decode(&encoded).unwrap(), "{:?}", name);
+    }
+    for encoded in &["%2", "%zz", "%2e", "%78", "x.", "a/b", "%FF", "nul"] {
+        assert!(decode(encoded).is_err(), "{:?}", encoded);
+    }
+}
+
+#[test]
+fn upload_path() {
+    use safe_path::sanitize::upload_path;
+
+    for (filename, expected) in &[
+        ("report.pdf", "report.pdf"),
+        ("/home/x/report.pdf", "report.pdf"),
+        ("C:\\Users\\x\\report.pdf", "report.pdf"),
+        ("..\\..\\report.pdf", "report.pdf"),
+        (".htaccess", "htaccess"),
+        ("...x", "x"),
+        ("C:report.pdf", "C_report.pdf"),
+        ("con.txt", "_con.txt"),
+        ("a<b>.txt ", "a_b_.txt"),
+    ] {
+        assert_eq!(
+            Path::new("uploads").join(expected),
+            upload_path("uploads", filename).unwrap(),
+            "{:?}",
+            filename
+        );
+    }
+    for filename in &["", " ", ".", "..", "x/", "x\\..", "/"] {
+        assert!(upload_path("uploads", filename).is_err(), "{:?}", filename);
+    }
+}
@@ -0,0 +1,23 @@
+#![cfg(feature = "proptest")]
+
+use proptest::prelude::*;
+use safe_path::{strategies, SafePath, SafeRelativePath};
+use std::path::Path;
+
+proptest! {
+    #[test]
+    fn adversarial_path(path in strategies::adversarial_path()) {
+        prop_assert!(path.components().count() <= safe_path::adversarial::MAX_COMPONENTS);
+    }
+
+    #[test]
+    fn contained_in(path in strategies::contained_in("www")) {
+        prop_assert!(Path::new("www").safe_join(&path).is_ok());
+        prop_assert!(SafeRelativePath::new(&path).is_ok());
+    }
+
+    #[test]
+    fn escaping(path in strategies::escaping("www")) {
+        prop_assert!(Path::new("www").safe_join(&path).is_err());
+    }
+}
@@ -0,0 +1,29 @@
+#![cfg(feature = "std-path")]
+
+use safe_path::{tenant::TenantRoot, FileName, Policy};
+use std::path::Path;
+
+#[test]
+fn join() {
+    let tenant = TenantRoot::new("root", FileName::new("a").unwrap());
+    assert_eq!(Path::new("root/a"), tenant.tenant_dir());
+    assert_eq!(Path::new("root/a/x/y"), tenant.join("x/y").unwrap());
+    for path in &["", ".", "..", "../b", "/root/a/x", "x/../.."] {
+        assert!(tenant.join(path).is_err(), "{:?}", path);
+    }
+}
+
+#[test]
+fn switch_tenant() {
+    let policy = Policy::new().no_hidden(true);
+    let a = TenantRoot::with_policy("root", FileName::new("a").unwrap(), policy.clone());
+    let b = a.switch_tenant(FileName::new("b").unwrap());
+    assert_eq!(a.root(), b.root());
+    assert_eq!("b", b.tenant().to_string());
+    assert_eq!(Path::new("root/b/x"), b.join("x").unwrap());
+    assert!(b.join(".x").is_err());
+    assert_eq!(
+        b,
+        TenantRoot::with_policy("root", FileName::new("b").unwrap(), policy)
+    );
+}
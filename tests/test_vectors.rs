@@ -0,0 +1,43 @@
+#![cfg(feature = "test-vectors")]
+
+use safe_path::{
+    test_vectors::{Category, Verdict, VECTORS},
+    url::{resolve_request_path, safe_join_url_path},
+};
+use std::path::Path;
+
+#[test]
+fn safe_join_url_path_agrees() {
+    let base = Path::new("www");
+    for vector in VECTORS {
+        let result = safe_join_url_path(base, vector.payload);
+        assert!(vector.check(base, result.as_deref().ok()), "{:?}", vector);
+    }
+}
+
+#[test]
+fn resolve_request_path_agrees() {
+    let base = Path::new("www");
+    for vector in VECTORS {
+        let result = resolve_request_path(base, vector.payload, None);
+        assert!(vector.check(base, result.as_deref().ok()), "{:?}", vector);
+    }
+}
+
+#[test]
+fn check() {
+    let base = Path::new("www");
+    let vector = VECTORS
+        .iter()
+        .find(|vector| vector.verdict == Verdict::Contained)
+        .unwrap();
+    assert!(vector.check(base, None));
+    assert!(vector.check(base, Some(Path::new("www/x"))));
+    assert!(!vector.check(base, Some(Path::new("www/../x"))));
+    assert!(!vector.check(base, Some(Path::new("www"))));
+    let vector = VECTORS
+        .iter()
+        .find(|vector| vector.category == Category::Benign && vector.verdict == Verdict::Accept)
+        .unwrap();
+    assert!(!vector.check(base, None));
+}
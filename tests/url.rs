@@ -0,0 +1,142 @@
+#![cfg(feature = "url")]
+
+use safe_path::{
+    url::{resolve_request_path, resolve_request_path_with, safe_join_url_path},
+    Error, Policy, Violation,
+};
+use std::path::Path;
+
+#[test]
+fn decode() {
+    for (raw, expected) in &[
+        ("/x", "www/x"),
+        ("x/y/", "www/x/y"),
+        ("//x/./y", "www/x/y"),
+        ("/x/../y", "www/x/../y"),
+        ("/x%20y", "www/x y"),
+        ("/caf%C3%A9", "www/caf\u{e9}"),
+        ("/%78/%2e%2E/%79", "www/x/../y"),
+        ("/x+y", "www/x+y"),
+    ] {
+        assert_eq!(
+            Path::new(expected),
+            safe_join_url_path("www", raw).unwrap(),
+            "{:?}",
+            raw
+        );
+    }
+}
+
+#[test]
+fn reject() {
+    for raw in &[
+        "/",
+        "",
+        "/..",
+        "/x/../..",
+        "/%2e%2e/x",
+        "/x/%2E%2E/%2e%2e/y",
+        "/%",
+        "/%2",
+        "/%zz",
+        "/%c0%ae%c0%ae/x",
+        "/%e0%80%ae",
+        "/%ff",
+        "/x%2fy",
+    ] {
+        assert!(safe_join_url_path("www", raw).is_err(), "{:?}", raw);
+    }
+}
+
+#[test]
+fn encoded_separator() {
+    for raw in &["/x/..%2f..%2fy", "/x/..%5C..%5Cy"] {
+        let error = safe_join_url_path("www", raw).unwrap_err();
+        assert_eq!(
+            Some(&Violation::EncodedSeparator { index: 1 }),
+            Error::downcast_ref(&error).unwrap().violation(),
+            "{:?}",
+            raw
+        );
+    }
+    let error = safe_join_url_path("www", "/%zz").unwrap_err();
+    assert_eq!(400, Error::downcast_ref(&error).unwrap().http_status());
+}
+
+#[test]
+fn double_encoding() {
+    for raw in &[
+        "/x/%252e%252e/y",
+        "/x/%252E./y",
+        "/x/..%252f..",
+        "/x/..%255c..",
+        "/x/%25252e%25252e",
+    ] {
+        let error = safe_join_url_path("www", raw).unwrap_err();
+        assert_eq!(
+            Some(&Violation::DoubleEncoding { index: 1 }),
+            Error::downcast_ref(&error).unwrap().violation(),
+            "{:?}",
+            raw
+        );
+    }
+    assert_eq!(
+        Path::new("www/x/100%"),
+        safe_join_url_path("www", "/x/100%25").unwrap()
+    );
+    assert_eq!(
+        Path::new("www/x/%41"),
+        safe_join_url_path("www", "/x/%2541").unwrap()
+    );
+}
+
+#[test]
+fn resolve() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let docroot = tempdir.path();
+    std::fs::create_dir(docroot.join("docs")).unwrap();
+    let index = Some("index.html");
+    for (raw, expected) in &[
+        ("/", "index.html"),
+        ("", "index.html"),
+        ("/?x=/../..", "index.html"),
+        ("/docs", "docs/index.html"),
+        ("/docs/#top", "docs/index.html"),
+        ("/other/", "other/index.html"),
+        ("/other", "other"),
+        ("/docs/../x%2Ehtml", "x.html"),
+    ] {
+        assert_eq!(
+            docroot.join(expected),
+            resolve_request_path(docroot, raw, index).unwrap(),
+            "{:?}",
+            raw
+        );
+    }
+    assert_eq!(
+        docroot.join("docs"),
+        resolve_request_path(docroot, "/docs", None).unwrap()
+    );
+    for (raw, violation) in &[
+        ("/.env", Some(Violation::HiddenComponent { index: 0 })),
+        (
+            "/docs/%2egit/config",
+            Some(Violation::HiddenComponent { index: 1 }),
+        ),
+        ("/../x", Some(Violation::EscapingParentDir { index: 0 })),
+        ("/x%2f..", Some(Violation::EncodedSeparator { index: 0 })),
+        ("/%zz", Some(Violation::InvalidEncoding)),
+    ] {
+        let error = resolve_request_path(docroot, raw, index).unwrap_err();
+        assert_eq!(
+            violation.as_ref(),
+            Error::downcast_ref(&error).unwrap().violation(),
+            "{:?}",
+            raw
+        );
+    }
+    assert!(resolve_request_path(docroot, "/", None).is_err());
+    let policy = Policy::new().deny_extension("php");
+    assert!(resolve_request_path_with(&policy, docroot, "/x.php", index).is_err());
+    assert!(resolve_request_path_with(&policy, docroot, "/.env", index).is_ok());
+}
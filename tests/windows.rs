@@ -0,0 +1,119 @@
+#![cfg(feature = "typed-path")]
+
+use safe_path::{windows, Error, Policy, Separators, Violation};
+use typed_path::Utf8WindowsPath;
+
+fn violation(dir: &str, path: &str) -> Option<Violation> {
+    let error =
+        windows::safe_join(Utf8WindowsPath::new(dir), Utf8WindowsPath::new(path)).unwrap_err();
+    Error::downcast_ref(&error).unwrap().violation().cloned()
+}
+
+#[test]
+fn join() {
+    for (dir, path) in &[
+        (r"C:\srv", r"x\y"),
+        (r"C:\srv", "x/y"),
+        (r"C:\srv", r"x\..\y"),
+        (r"C:\", r"C:\x"),
+        (r"C:\", r"c:\x"),
+        (r"\\server\share\", r"\\SERVER\share\x"),
+        ("srv", "x"),
+    ] {
+        assert!(
+            windows::safe_join(Utf8WindowsPath::new(dir), Utf8WindowsPath::new(path)).is_ok(),
+            "{:?} {:?}",
+            dir,
+            path
+        );
+    }
+    for (dir, path) in &[
+        (r"C:\srv", r"..\x"),
+        (r"C:\srv", "../x"),
+        (r"C:\srv", r"x\..\.."),
+        (r"C:\srv", r"\x"),
+        (r"C:\srv", r"C:\x"),
+        (r"C:\srv", "C:x"),
+        (r"C:\srv", r"\\?\C:\srv\x"),
+        (r"C:\", r"D:\x"),
+        (r"\\server\share\", r"\\other\share\x"),
+        ("srv", "."),
+    ] {
+        assert!(
+            windows::safe_join(Utf8WindowsPath::new(dir), Utf8WindowsPath::new(path)).is_err(),
+            "{:?} {:?}",
+            dir,
+            path
+        );
+        assert!(
+            !windows::explain_join(Utf8WindowsPath::new(dir), Utf8WindowsPath::new(path))
+                .is_empty(),
+            "{:?} {:?}",
+            dir,
+            path
+        );
+    }
+}
+
+#[test]
+fn names() {
+    assert_eq!(
+        Some(Violation::ReservedName { index: 1 }),
+        violation("srv", r"x\CON.txt")
+    );
+    assert_eq!(
+        Some(Violation::Stream { index: 0 }),
+        violation("srv", "file.txt:stream")
+    );
+    assert_eq!(
+        Some(Violation::TrailingDotOrSpace { index: 0 }),
+        violation("srv", "x.")
+    );
+    assert!(windows::safe_join_with(
+        &Policy::new(),
+        Utf8WindowsPath::new("srv"),
+        Utf8WindowsPath::new("x.")
+    )
+    .is_ok());
+}
+
+#[test]
+fn policy() {
+    let policy = windows::policy().no_parent(true).deny_extension("exe");
+    let dir = Utf8WindowsPath::new(r"C:\srv");
+    assert!(windows::safe_join_with(&policy, dir, Utf8WindowsPath::new(r"x\..\y")).is_err());
+    assert!(windows::safe_join_with(&policy, dir, Utf8WindowsPath::new(r"x\y.EXE")).is_err());
+    assert!(windows::safe_join_with(&policy, dir, Utf8WindowsPath::new(r"x\y.txt")).is_ok());
+    let policy = windows::policy().allow_absolute_self(true);
+    assert!(windows::safe_join_with(&policy, dir, Utf8WindowsPath::new(r"C:\.\srv")).is_ok());
+    assert!(windows::safe_join_with(&policy, dir, Utf8WindowsPath::new(r"C:\SRV")).is_err());
+}
+
+#[test]
+fn is_root() {
+    for dir in &[r"C:\", r"C:\x\..", r"\\server\share\", r"\\server\share"] {
+        assert!(windows::is_root(Utf8WindowsPath::new(dir)), "{:?}", dir);
+    }
+    for dir in &["C:", r"C:\x", "/x", "x"] {
+        assert!(!windows::is_root(Utf8WindowsPath::new(dir)), "{:?}", dir);
+    }
+}
+
+#[test]
+fn separators() {
+    let dir = Utf8WindowsPath::new(r"C:\srv");
+    let policy = windows::policy().separators(Separators::Normalize);
+    assert_eq!(
+        Utf8WindowsPath::new(r"C:\srv\x\y"),
+        windows::safe_join_with(&policy, dir, Utf8WindowsPath::new(r"x\\.\y\")).unwrap()
+    );
+    let policy = windows::policy().separators(Separators::Reject);
+    for path in &[r"x\\y", r"x/.\y", r"x\", "x/"] {
+        assert!(
+            windows::safe_join_with(&policy, dir, Utf8WindowsPath::new(path)).is_err(),
+            "{:?}",
+            path
+        );
+    }
+    assert!(windows::safe_join_with(&policy, dir, Utf8WindowsPath::new(r"x/y\z")).is_ok());
+}